@@ -0,0 +1,80 @@
+use std::io::{Read, Write};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use orc_format::proto::CompressionKind;
+use orc_format::read::decompress::Decompressor;
+
+const BLOCK_SIZE: usize = 256 * 1024;
+
+/// Returns a Zlib stream of `size` bytes split in chunks of [`BLOCK_SIZE`]
+fn stream(size: usize) -> Vec<u8> {
+    let data = (0..size).map(|x| (x % 7) as u8).collect::<Vec<_>>();
+
+    let mut stream = vec![];
+    for chunk in data.chunks(BLOCK_SIZE) {
+        let mut encoder = flate2::write::DeflateEncoder::new(vec![], flate2::Compression::fast());
+        encoder.write_all(chunk).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let header = ((compressed.len() as u32) << 1).to_le_bytes();
+        stream.extend_from_slice(&header[..3]);
+        stream.extend(compressed);
+    }
+    stream
+}
+
+/// Decompresses `stream` allocating a new buffer per chunk, as a streaming decoder does
+fn decompress_streaming(mut stream: &[u8], result: &mut Vec<u8>) {
+    while !stream.is_empty() {
+        let header = [0, stream[0], stream[1], stream[2]];
+        let length = (u32::from_le_bytes(header) >> 9) as usize;
+        let (chunk, remaining) = stream[3..].split_at(length);
+        stream = remaining;
+
+        let mut buffer = vec![];
+        flate2::read::DeflateDecoder::new(chunk)
+            .read_to_end(&mut buffer)
+            .unwrap();
+        result.extend_from_slice(&buffer);
+    }
+}
+
+fn add_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decompress");
+    for log2_size in [20, 24] {
+        let size = 1 << log2_size;
+        let stream = stream(size);
+
+        group.bench_with_input(BenchmarkId::new("streaming", size), &stream, |b, stream| {
+            let mut result = Vec::with_capacity(size);
+            b.iter(|| {
+                result.clear();
+                decompress_streaming(stream, &mut result)
+            })
+        });
+
+        let mut scratch = Vec::with_capacity(BLOCK_SIZE + 1);
+        group.bench_with_input(
+            BenchmarkId::new("decompressor", size),
+            &stream,
+            |b, stream| {
+                let mut result = Vec::with_capacity(size);
+                b.iter(|| {
+                    result.clear();
+                    let mut decompressor = Decompressor::new(
+                        stream,
+                        CompressionKind::Zlib,
+                        std::mem::take(&mut scratch),
+                    )
+                    .with_max_chunk_size(BLOCK_SIZE);
+                    decompressor.read_to_end(&mut result).unwrap();
+                    scratch = decompressor.into_inner();
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, add_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,143 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use orc_format::read::decode::unpack::{unpack, unpack_into};
+use orc_format::read::decode::UnsignedRleV2Iter;
+
+const RUN_LENGTH: usize = 512;
+
+/// Unpacks the value at `index` of `bytes`, as done before the unpackers specialized by bit
+/// width: a value at a time, re-slicing `bytes` and reversing its bytes into a `u64`
+#[inline]
+fn unpack_baseline(bytes: &[u8], num_bits: u8, index: usize) -> u64 {
+    if num_bits == 0 {
+        return 0;
+    };
+    let num_bits = num_bits as usize;
+    let start = num_bits * index; // in bits
+    let length = num_bits; // in bits
+    let byte_start = start / 8;
+    let byte_end = (start + length).div_ceil(8);
+    // copy swapped
+    let slice = &bytes[byte_start..byte_end];
+    let mut a = [0u8; 8];
+    for (i, item) in slice.iter().rev().enumerate() {
+        a[i] = *item;
+    }
+    let bits = u64::from_le_bytes(a);
+    let offset = (slice.len() * 8 - num_bits) % 8 - start % 8;
+    (bits >> offset) & (!0u64 >> (64 - num_bits))
+}
+
+/// Returns the 5 bits encoding of `num_bits` of Direct runs
+fn encode_bit_width(num_bits: usize) -> u8 {
+    match num_bits {
+        1..=24 => num_bits as u8 - 1,
+        26 => 24,
+        28 => 25,
+        30 => 26,
+        32 => 27,
+        40 => 28,
+        48 => 29,
+        56 => 30,
+        64 => 31,
+        _ => unreachable!(),
+    }
+}
+
+/// Returns a stream of `size` values of `num_bits` bits encoded as RLE v2 Direct runs
+fn stream(size: usize, num_bits: usize) -> Vec<u8> {
+    let mask = u64::MAX >> (64 - num_bits);
+    let mut stream = vec![];
+    for run in 0..size / RUN_LENGTH {
+        let length = RUN_LENGTH - 1;
+        stream.push(0b0100_0000 | (encode_bit_width(num_bits) << 1) | (length >> 8) as u8);
+        stream.push(length as u8);
+
+        let start = stream.len();
+        stream.resize(start + (RUN_LENGTH * num_bits).div_ceil(8), 0);
+        for i in 0..RUN_LENGTH {
+            let value = ((run * RUN_LENGTH + i) as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) & mask;
+            for bit in 0..num_bits {
+                if (value >> (num_bits - 1 - bit)) & 1 == 1 {
+                    let position = i * num_bits + bit;
+                    stream[start + position / 8] |= 128 >> (position % 8);
+                }
+            }
+        }
+    }
+    stream
+}
+
+fn add_benchmark(c: &mut Criterion) {
+    let size = 1 << 16;
+    let mut group = c.benchmark_group("rle_v2_direct");
+    for num_bits in [4, 13, 16, 24, 32, 64] {
+        let stream = stream(size, num_bits);
+
+        group.bench_with_input(BenchmarkId::new("next", num_bits), &stream, |b, stream| {
+            let mut values = Vec::with_capacity(size);
+            b.iter(|| {
+                values.clear();
+                for value in UnsignedRleV2Iter::new(stream.as_slice(), size, vec![]) {
+                    values.push(value.unwrap());
+                }
+            })
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("decode_into", num_bits),
+            &stream,
+            |b, stream| {
+                let mut values = vec![0; size];
+                b.iter(|| {
+                    UnsignedRleV2Iter::new(stream.as_slice(), size, vec![])
+                        .decode_into(&mut values)
+                        .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn add_unpack_benchmark(c: &mut Criterion) {
+    let size = 1 << 16;
+    let mut group = c.benchmark_group("unpack");
+    // the widths of the baseline, which supports values spanning at most 8 bytes
+    for num_bits in [1u8, 2, 4, 8, 13, 16, 24, 26, 30, 32, 56, 64] {
+        let bytes = (0..size * num_bits as usize / 8)
+            .map(|i| (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) as u8)
+            .collect::<Vec<_>>();
+        let mut values = vec![0; size];
+
+        group.bench_with_input(
+            BenchmarkId::new("baseline", num_bits),
+            &bytes,
+            |b, bytes| {
+                b.iter(|| {
+                    for (index, value) in values.iter_mut().enumerate() {
+                        *value = unpack_baseline(bytes, num_bits, index);
+                    }
+                })
+            },
+        );
+
+        group.bench_with_input(BenchmarkId::new("unpack", num_bits), &bytes, |b, bytes| {
+            b.iter(|| {
+                for (index, value) in values.iter_mut().enumerate() {
+                    *value = unpack(bytes, num_bits, index);
+                }
+            })
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("unpack_into", num_bits),
+            &bytes,
+            |b, bytes| b.iter(|| unpack_into(bytes, num_bits, 0, &mut values)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, add_benchmark, add_unpack_benchmark);
+criterion_main!(benches);
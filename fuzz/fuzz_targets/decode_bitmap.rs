@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use orc_format::read::decode::{decode_bitmap, scatter};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((length, stream)) = data.split_first() {
+        let length = *length as usize * 8;
+        let mut bitmap = vec![];
+        if let Ok(nulls) = decode_bitmap(stream, length, &mut bitmap) {
+            // values whose number may differ from the number of set bits
+            let values = vec![1u8; (length - nulls).min(stream.len())];
+            let _ = scatter(&values, &bitmap, length, &mut vec![]);
+        }
+    }
+});
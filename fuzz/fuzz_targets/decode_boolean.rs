@@ -0,0 +1,19 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use orc_format::read::decode::{BooleanIter, BooleanRleRunIter};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((length, stream)) = data.split_first() {
+        let length = *length as usize * 8;
+        for item in BooleanIter::new(stream, length) {
+            if item.is_err() {
+                break;
+            }
+        }
+        for run in BooleanRleRunIter::new(stream).take(length) {
+            if run.is_err() {
+                break;
+            }
+        }
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use orc_format::read::decode::ByteIter;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((length, stream)) = data.split_first() {
+        let length = *length as usize * 4;
+        for item in ByteIter::new(stream, length) {
+            if item.is_err() {
+                break;
+            }
+        }
+        let _ = ByteIter::new(stream, length).extend_into(&mut vec![]);
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use orc_format::read::decode::DecimalIter;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((length, stream)) = data.split_first() {
+        for item in DecimalIter::new(stream, *length as usize) {
+            if item.is_err() {
+                break;
+            }
+        }
+        let _ = DecimalIter::new(stream, *length as usize).extend_into(&mut vec![]);
+    }
+});
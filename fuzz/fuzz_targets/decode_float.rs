@@ -0,0 +1,19 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use orc_format::read::decode::FloatIter;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((length, stream)) = data.split_first() {
+        let length = *length as usize;
+        for item in FloatIter::<f32, _>::new(stream, length) {
+            if item.is_err() {
+                break;
+            }
+        }
+        for item in FloatIter::<f64, _>::new(stream, length) {
+            if item.is_err() {
+                break;
+            }
+        }
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use orc_format::read::decode::{SignedRleV2Iter, UnsignedRleV2Iter};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((length, stream)) = data.split_first() {
+        let length = *length as usize * 4;
+        for item in SignedRleV2Iter::new(stream, length, vec![]).take(length) {
+            if item.is_err() {
+                break;
+            }
+        }
+        for item in UnsignedRleV2Iter::new(stream, length, vec![]).take(length) {
+            if item.is_err() {
+                break;
+            }
+        }
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use orc_format::read::decode::Values;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((length, stream)) = data.split_first() {
+        let mut values = Values::new(stream, vec![]);
+        for length in 0..*length as usize {
+            if values.next(length).is_err() {
+                break;
+            }
+        }
+        let _ = Values::new(stream, vec![]).extend_into(*length as usize * 4, &mut vec![]);
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+use std::io::Read;
+
+use libfuzzer_sys::fuzz_target;
+use orc_format::{proto::CompressionKind, read::decompress::Decompressor};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((compression, stream)) = data.split_first() {
+        let compression = CompressionKind::from_i32(*compression as i32 % 4).unwrap();
+        let mut decompressor = Decompressor::new(stream, compression, vec![]);
+        let _ = decompressor.read_to_end(&mut vec![]);
+    }
+});
@@ -0,0 +1,18 @@
+#![no_main]
+use std::io::Cursor;
+
+use libfuzzer_sys::fuzz_target;
+use orc_format::{arrow::read::RecordBatchIter, error::Error, read};
+
+fn read_file(data: &[u8]) -> Result<(), Error> {
+    let mut reader = Cursor::new(data);
+    let metadata = read::read_metadata(&mut reader)?;
+    for batch in RecordBatchIter::try_new(&mut reader, &metadata, None, 1024)? {
+        batch?;
+    }
+    Ok(())
+}
+
+fuzz_target!(|data: &[u8]| {
+    let _ = read_file(data);
+});
@@ -0,0 +1,37 @@
+#![no_main]
+use std::io::{Cursor, Read};
+
+use libfuzzer_sys::fuzz_target;
+use orc_format::{error::Error, proto::stream::Kind, read};
+
+fn read_file(data: &[u8]) -> Result<(), Error> {
+    let mut reader = Cursor::new(data);
+    let metadata = read::read_metadata(&mut reader)?;
+    metadata.metadata()?;
+
+    let mut scratch = vec![];
+    for stripe in 0..metadata.footer.stripes.len() {
+        let footer = read::read_stripe_footer(&mut reader, &metadata, stripe, &mut scratch)?;
+        for column in 0..metadata.footer.types.len() as u32 {
+            let column = read::read_stripe_column(
+                &mut reader,
+                &metadata,
+                stripe,
+                footer.clone(),
+                column,
+                std::mem::take(&mut scratch),
+            )?;
+            for kind in [Kind::Present, Kind::Data, Kind::Length, Kind::Secondary] {
+                if let Ok(mut stream) = column.get_stream(kind, vec![]) {
+                    stream.read_to_end(&mut vec![])?;
+                }
+            }
+            (_, scratch) = column.into_inner();
+        }
+    }
+    Ok(())
+}
+
+fuzz_target!(|data: &[u8]| {
+    let _ = read_file(data);
+});
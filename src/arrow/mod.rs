@@ -0,0 +1,8 @@
+//! Conversion between ORC and [Apache Arrow](https://arrow.apache.org/) (requires the
+//! `arrow` feature).
+//!
+//! [`schema`] maps the file's types to an Arrow schema and back, [`read`] deserializes
+//! stripes to [`arrow_array::RecordBatch`]es and [`mod@write`] serializes them to a file.
+pub mod read;
+pub mod schema;
+pub mod write;
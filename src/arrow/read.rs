@@ -0,0 +1,921 @@
+//! Deserialization of stripes to Arrow's [`RecordBatch`].
+//!
+//! Each stripe is deserialized to a single [`RecordBatch`] by [`read_stripe`];
+//! [`RecordBatchIter`] slices them (without copying) to batches of a given size.
+//!
+//! Timestamps (without timezone) are only read from stripes written in UTC; other writer
+//! timezones return [`Error::NotYetImplemented`].
+use std::io::{Read, Seek};
+use std::sync::Arc;
+
+use arrow_array::types::{
+    BinaryType, ByteArrayType, Date32Type, Decimal128Type, Float32Type, Float64Type, Int16Type,
+    Int32Type, Int64Type, Int8Type, TimestampNanosecondType, Utf8Type,
+};
+use arrow_array::{
+    ArrayRef, ArrowPrimitiveType, BooleanArray, DictionaryArray, GenericByteArray, Int32Array,
+    ListArray, MapArray, PrimitiveArray, RecordBatch, RecordBatchOptions, StructArray, UInt64Array,
+    UnionArray,
+};
+use arrow_buffer::{BooleanBuffer, Buffer, NullBuffer, OffsetBuffer, ScalarBuffer};
+use arrow_schema::{DataType, SchemaRef, TimeUnit};
+use arrow_select::take::take;
+
+use crate::error::Error;
+use crate::proto::column_encoding::Kind as ColumnEncodingKind;
+use crate::proto::r#type::Kind as TypeKind;
+use crate::proto::stream::Kind;
+use crate::proto::StripeFooter;
+use crate::read::decode::{decode_bitmap, scatter, BooleanIter, ByteIter, DecimalIter, FloatIter};
+use crate::read::streams::{check_v2, signed, timestamps, unsigned};
+use crate::read::{get_stripe, read_stripe_column, read_stripe_footer, Column, FileMetadata};
+
+use crate::read::types::{children, get_type, top_level_columns};
+
+use super::schema::infer_schema;
+
+/// Reads the columns `columns` of `stripe` to a [`RecordBatch`] with `schema`, where
+/// `columns[i]` is the (top-level) column of the file deserialized to `schema.field(i)`.
+///
+/// See [`super::schema::infer_schema`] and [`super::schema::top_level_columns`] for how
+/// to obtain them.
+/// # Errors
+/// This function errors when the file is out of spec or when a field's type is not
+/// supported for the type of its column.
+pub fn read_stripe<R: Read + Seek>(
+    reader: &mut R,
+    metadata: &FileMetadata,
+    stripe: usize,
+    schema: SchemaRef,
+    columns: &[u32],
+) -> Result<RecordBatch, Error> {
+    let length = get_stripe(metadata, stripe)?.number_of_rows() as usize;
+    let footer = read_stripe_footer(reader, metadata, stripe, &mut vec![])?;
+
+    let mut stripe = StripeReader {
+        reader,
+        metadata,
+        stripe,
+        footer: Arc::new(footer),
+    };
+    let arrays = schema
+        .fields()
+        .iter()
+        .zip(columns)
+        .map(|(field, column)| stripe.deserialize(*column, length, field.data_type()))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let options = RecordBatchOptions::new().with_row_count(Some(length));
+    Ok(RecordBatch::try_new_with_options(schema, arrays, &options)?)
+}
+
+/// An [`Iterator`] of [`RecordBatch`]es of at most `batch_size` rows, read stripe by stripe.
+///
+/// Batches never span stripes; the last batch of each stripe may be smaller than
+/// `batch_size`. The iterator stops after the first error.
+pub struct RecordBatchIter<'a, R> {
+    reader: &'a mut R,
+    metadata: &'a FileMetadata,
+    schema: SchemaRef,
+    columns: Vec<u32>,
+    batch_size: usize,
+    next_stripe: usize,
+    current: Option<RecordBatch>,
+    offset: usize,
+}
+
+impl<'a, R: Read + Seek> RecordBatchIter<'a, R> {
+    /// Returns a new [`RecordBatchIter`] of the top-level columns `projection` (all when `None`)
+    /// with the schema inferred from the file via [`infer_schema`].
+    /// # Panics
+    /// This function panics if `batch_size` is zero.
+    pub fn try_new(
+        reader: &'a mut R,
+        metadata: &'a FileMetadata,
+        projection: Option<&[usize]>,
+        batch_size: usize,
+    ) -> Result<Self, Error> {
+        let schema = infer_schema(&metadata.footer)?;
+        let columns = top_level_columns(&metadata.footer.types)?;
+        let (schema, columns) = match projection {
+            Some(projection) => (
+                schema.project(projection)?,
+                projection.iter().map(|index| columns[*index]).collect(),
+            ),
+            None => (schema, columns.to_vec()),
+        };
+        Ok(Self::with_schema(
+            reader,
+            metadata,
+            Arc::new(schema),
+            columns,
+            batch_size,
+        ))
+    }
+
+    /// Returns a new [`RecordBatchIter`] deserializing the columns `columns` to `schema`.
+    /// See [`read_stripe`] for the meaning of the arguments.
+    /// # Panics
+    /// This function panics if `batch_size` is zero.
+    pub fn with_schema(
+        reader: &'a mut R,
+        metadata: &'a FileMetadata,
+        schema: SchemaRef,
+        columns: Vec<u32>,
+        batch_size: usize,
+    ) -> Self {
+        assert!(batch_size > 0, "the batch size must be positive");
+        Self {
+            reader,
+            metadata,
+            schema,
+            columns,
+            batch_size,
+            next_stripe: 0,
+            current: None,
+            offset: 0,
+        }
+    }
+
+    /// Returns the schema of the batches of this iterator
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl<'a, R: Read + Seek> Iterator for RecordBatchIter<'a, R> {
+    type Item = Result<RecordBatch, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(current) = &self.current {
+                if self.offset < current.num_rows() {
+                    let length = self.batch_size.min(current.num_rows() - self.offset);
+                    let batch = current.slice(self.offset, length);
+                    self.offset += length;
+                    return Some(Ok(batch));
+                }
+                self.current = None;
+            }
+
+            let stripes = self.metadata.footer.stripes.len();
+            if self.next_stripe >= stripes {
+                return None;
+            }
+            let stripe = self.next_stripe;
+            self.next_stripe += 1;
+            match read_stripe(
+                self.reader,
+                self.metadata,
+                stripe,
+                self.schema.clone(),
+                &self.columns,
+            ) {
+                Ok(batch) => {
+                    self.current = Some(batch);
+                    self.offset = 0;
+                }
+                Err(error) => {
+                    self.next_stripe = stripes;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+/// Reads the columns of a stripe on demand
+struct StripeReader<'a, R> {
+    reader: &'a mut R,
+    metadata: &'a FileMetadata,
+    stripe: usize,
+    footer: Arc<StripeFooter>,
+}
+
+impl<'a, R: Read + Seek> StripeReader<'a, R> {
+    /// Deserializes the `length` rows of `column` (and its children) to `data_type`
+    fn deserialize(
+        &mut self,
+        column: u32,
+        length: usize,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        // the lengths of children are declared by their parents' streams
+        self.metadata.limits().check_values(length as u64)?;
+        let orc = read_stripe_column(
+            self.reader,
+            self.metadata,
+            self.stripe,
+            Arc::clone(&self.footer),
+            column,
+            vec![],
+        )?;
+        let location = orc.location();
+        self.deserialize_column(&orc, column, length, data_type)
+            .map_err(|error| error.at(location))
+    }
+
+    fn deserialize_column(
+        &mut self,
+        column: &Column,
+        index: u32,
+        length: usize,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        let types = &self.metadata.footer.types;
+        let kind = get_type(types, index)?.kind();
+        let children = children(types, index)?.to_vec();
+
+        let nulls = nulls(column, length)?;
+        let non_null = length - nulls.as_ref().map_or(0, |nulls| nulls.null_count());
+
+        Ok(match (kind, data_type) {
+            (TypeKind::Boolean, DataType::Boolean) => {
+                let mut values = vec![];
+                BooleanIter::new(column.get_stream(Kind::Data, vec![])?, non_null)
+                    .extend_into(&mut values)?;
+                let values = scattered(values, nulls.as_ref())?;
+                Arc::new(BooleanArray::new(BooleanBuffer::from_iter(values), nulls))
+            }
+            (TypeKind::Byte, DataType::Int8) => {
+                let mut values = vec![];
+                ByteIter::new(column.get_stream(Kind::Data, vec![])?, non_null)
+                    .extend_into(&mut values)?;
+                let values = values.into_iter().map(|x| x as i8).collect();
+                primitive::<Int8Type>(values, nulls, data_type)?
+            }
+            (TypeKind::Short, DataType::Int16) => {
+                integers::<Int16Type>(column, non_null, nulls, data_type)?
+            }
+            (TypeKind::Int, DataType::Int32) => {
+                integers::<Int32Type>(column, non_null, nulls, data_type)?
+            }
+            (TypeKind::Long, DataType::Int64) => {
+                integers::<Int64Type>(column, non_null, nulls, data_type)?
+            }
+            (TypeKind::Date, DataType::Date32) => {
+                integers::<Date32Type>(column, non_null, nulls, data_type)?
+            }
+            (TypeKind::Float, DataType::Float32) => {
+                let mut values = vec![];
+                FloatIter::<f32, _>::new(column.get_stream(Kind::Data, vec![])?, non_null)
+                    .extend_into(&mut values)?;
+                primitive::<Float32Type>(values, nulls, data_type)?
+            }
+            (TypeKind::Double, DataType::Float64) => {
+                let mut values = vec![];
+                FloatIter::<f64, _>::new(column.get_stream(Kind::Data, vec![])?, non_null)
+                    .extend_into(&mut values)?;
+                primitive::<Float64Type>(values, nulls, data_type)?
+            }
+            (
+                TypeKind::Timestamp | TypeKind::TimestampInstant,
+                DataType::Timestamp(TimeUnit::Nanosecond, _),
+            ) => {
+                let values = timestamps(column, kind, non_null)?;
+                primitive::<TimestampNanosecondType>(values, nulls, data_type)?
+            }
+            (TypeKind::Decimal, DataType::Decimal128(_, scale)) => {
+                let values = decimals(column, non_null, *scale)?;
+                primitive::<Decimal128Type>(values, nulls, data_type)?
+            }
+            (TypeKind::String | TypeKind::Varchar | TypeKind::Char, DataType::Utf8) => {
+                binary::<Utf8Type>(column, non_null, nulls)?
+            }
+            (TypeKind::Binary, DataType::Binary) => binary::<BinaryType>(column, non_null, nulls)?,
+            (
+                TypeKind::String | TypeKind::Varchar | TypeKind::Char,
+                DataType::Dictionary(key, value),
+            ) if key.as_ref() == &DataType::Int32 && value.as_ref() == &DataType::Utf8 => {
+                dictionary::<Utf8Type>(column, non_null, nulls)?
+            }
+            (TypeKind::List, DataType::List(field)) => {
+                let offsets = offsets(column, non_null, nulls.as_ref())?;
+                let child_length = offsets.last().copied().unwrap_or_default() as usize;
+                let values = self.deserialize(children[0], child_length, field.data_type())?;
+                Arc::new(ListArray::try_new(field.clone(), offsets, values, nulls)?)
+            }
+            (TypeKind::Map, DataType::Map(field, ordered)) => {
+                let DataType::Struct(fields) = field.data_type() else {
+                    return Err(Error::NotYetImplemented(
+                        "maps whose entries are not structs",
+                    ));
+                };
+                if fields.len() != 2 || children.len() != 2 {
+                    return Err(Error::OutOfSpec("a map must have a key and a value"));
+                }
+                let offsets = offsets(column, non_null, nulls.as_ref())?;
+                let child_length = offsets.last().copied().unwrap_or_default() as usize;
+                let keys = self.deserialize(children[0], child_length, fields[0].data_type())?;
+                let values = self.deserialize(children[1], child_length, fields[1].data_type())?;
+                let entries = StructArray::try_new(fields.clone(), vec![keys, values], None)?;
+                Arc::new(MapArray::try_new(
+                    field.clone(),
+                    offsets,
+                    entries,
+                    nulls,
+                    *ordered,
+                )?)
+            }
+            (TypeKind::Struct, DataType::Struct(fields)) => {
+                if fields.len() != children.len() {
+                    return Err(Error::OutOfSpec("a struct must have one field per child"));
+                }
+                if fields.is_empty() {
+                    return Ok(Arc::new(StructArray::new_empty_fields(length, nulls)));
+                }
+                let indices = nulls.as_ref().map(expand_indices);
+                let arrays = children
+                    .iter()
+                    .zip(fields.iter())
+                    .map(|(child, field)| {
+                        let array = self.deserialize(*child, non_null, field.data_type())?;
+                        // children only contain the rows where the struct is valid
+                        Ok(match &indices {
+                            Some(indices) => take(&array, indices, None)?,
+                            None => array,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Arc::new(StructArray::try_new(fields.clone(), arrays, nulls)?)
+            }
+            (TypeKind::Union, DataType::Union(fields, arrow_schema::UnionMode::Dense)) => {
+                if fields.len() != children.len() {
+                    return Err(Error::OutOfSpec("a union must have one field per child"));
+                }
+                self.union(column, &children, length, nulls, data_type)?
+            }
+            (TypeKind::Union, DataType::Union(..)) => {
+                return Err(Error::NotYetImplemented("reading unions to sparse unions"))
+            }
+            _ => {
+                return Err(Error::NotYetImplemented(
+                    "reading this type of column to this Arrow type",
+                ))
+            }
+        })
+    }
+
+    /// Deserializes a union to a dense union. Arrow's unions have no validity:
+    /// null rows point to nulls appended to the first child.
+    fn union(
+        &mut self,
+        column: &Column,
+        children: &[u32],
+        length: usize,
+        nulls: Option<NullBuffer>,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        let DataType::Union(fields, _) = data_type else {
+            unreachable!()
+        };
+        let non_null = length - nulls.as_ref().map_or(0, |nulls| nulls.null_count());
+
+        let mut tags = vec![];
+        ByteIter::new(column.get_stream(Kind::Data, vec![])?, non_null).extend_into(&mut tags)?;
+
+        if i32::try_from(length).is_err() {
+            return Err(Error::OutOfSpec(
+                "the length of a union must fit in 32 bits",
+            ));
+        }
+        let mut counts = vec![0i32; children.len()];
+        let mut type_ids = Vec::with_capacity(length);
+        let mut offsets = Vec::with_capacity(length);
+        // the indices of the first child (and of the nulls) in the (dense) first child
+        let mut first = Vec::new();
+        let mut tags = tags.into_iter();
+        for is_valid in (0..length).map(|i| nulls.as_ref().is_none_or(|n| n.is_valid(i))) {
+            let tag = if is_valid {
+                tags.next().unwrap_or_default() as usize
+            } else {
+                0
+            };
+            let count = counts.get_mut(tag).ok_or(Error::OutOfSpec(
+                "a union's tag must be one of its variants",
+            ))?;
+            if tag == 0 {
+                first.push(is_valid.then_some(*count as u64));
+                offsets.push(first.len() as i32 - 1);
+            } else {
+                offsets.push(*count);
+            }
+            if is_valid {
+                *count += 1;
+            }
+            type_ids.push(tag as i8);
+        }
+
+        let arrays = children
+            .iter()
+            .zip(fields.iter())
+            .zip(counts)
+            .enumerate()
+            .map(|(index, ((child, (_, field)), count))| {
+                let array = self.deserialize(*child, count as usize, field.data_type())?;
+                Ok(if index == 0 && nulls.is_some() {
+                    take(&array, &UInt64Array::from(first.clone()), None)?
+                } else {
+                    array
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(Arc::new(UnionArray::try_new(
+            fields.clone(),
+            type_ids.into(),
+            Some(offsets.into()),
+            arrays,
+        )?))
+    }
+}
+
+/// Returns the validity of `column` from its "Present" stream, if any
+fn nulls(column: &Column, length: usize) -> Result<Option<NullBuffer>, Error> {
+    if !column.has_stream(Kind::Present) {
+        return Ok(None);
+    }
+    let mut bitmap = Vec::new();
+    let null_count = decode_bitmap(
+        column.get_stream(Kind::Present, vec![])?,
+        length,
+        &mut bitmap,
+    )?;
+    Ok((null_count > 0)
+        .then(|| NullBuffer::new(BooleanBuffer::new(Buffer::from_vec(bitmap), 0, length))))
+}
+
+/// Returns the indices that expand dense (non-null) values to `nulls`
+fn expand_indices(nulls: &NullBuffer) -> UInt64Array {
+    let mut index = 0;
+    nulls
+        .iter()
+        .map(|is_valid| {
+            is_valid.then(|| {
+                index += 1;
+                index - 1
+            })
+        })
+        .collect()
+}
+
+/// Scatters dense `values` according to `nulls`
+fn scattered<T: Copy + Default>(
+    values: Vec<T>,
+    nulls: Option<&NullBuffer>,
+) -> Result<Vec<T>, Error> {
+    match nulls {
+        Some(nulls) => {
+            let mut result = Vec::with_capacity(nulls.len());
+            scatter(&values, nulls.validity(), nulls.len(), &mut result)?;
+            Ok(result)
+        }
+        None => Ok(values),
+    }
+}
+
+fn primitive<T: ArrowPrimitiveType>(
+    values: Vec<T::Native>,
+    nulls: Option<NullBuffer>,
+    data_type: &DataType,
+) -> Result<ArrayRef, Error> {
+    let values = scattered(values, nulls.as_ref())?;
+    let array = PrimitiveArray::<T>::try_new(values.into(), nulls)?;
+    Ok(Arc::new(array.with_data_type(data_type.clone())))
+}
+
+fn integers<T>(
+    column: &Column,
+    length: usize,
+    nulls: Option<NullBuffer>,
+    data_type: &DataType,
+) -> Result<ArrayRef, Error>
+where
+    T: ArrowPrimitiveType,
+    T::Native: TryFrom<i64>,
+{
+    let values = signed(column, Kind::Data, length)?
+        .into_iter()
+        .map(|value| {
+            T::Native::try_from(value)
+                .map_err(|_| Error::OutOfSpec("an integer is out of the range of its type"))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    primitive::<T>(values, nulls, data_type)
+}
+
+/// Returns the decimals of `column`, rescaled to `scale`
+fn decimals(column: &Column, length: usize, scale: i8) -> Result<Vec<i128>, Error> {
+    let values = DecimalIter::new(column.get_stream(Kind::Data, vec![])?, length)
+        .collect::<Result<Vec<_>, Error>>()?;
+    let scales = signed(column, Kind::Secondary, length)?;
+    values
+        .into_iter()
+        .zip(scales)
+        .map(|(value, value_scale)| {
+            let difference = i64::from(scale) - value_scale;
+            let factor = |difference: i64| {
+                u32::try_from(difference)
+                    .ok()
+                    .and_then(|difference| 10i128.checked_pow(difference))
+            };
+            if difference >= 0 {
+                factor(difference)
+                    .and_then(|factor| value.checked_mul(factor))
+                    .ok_or(Error::OutOfSpec(
+                        "a decimal is out of the range of its type",
+                    ))
+            } else {
+                // digits beyond the type's scale are truncated
+                Ok(factor(-difference).map_or(0, |factor| value / factor))
+            }
+        })
+        .collect()
+}
+
+/// Returns the offsets of a list or map from its "Length" stream
+fn offsets(
+    column: &Column,
+    length: usize,
+    nulls: Option<&NullBuffer>,
+) -> Result<OffsetBuffer<i32>, Error> {
+    let lengths = scattered(unsigned(column, Kind::Length, length)?, nulls)?;
+    let mut offsets = Vec::with_capacity(lengths.len() + 1);
+    let mut offset = 0i32;
+    offsets.push(offset);
+    for length in lengths {
+        offset = i32::try_from(length)
+            .ok()
+            .and_then(|length| offset.checked_add(length))
+            .ok_or(Error::OutOfSpec(
+                "the lengths of a column must fit in 32 bits",
+            ))?;
+        offsets.push(offset);
+    }
+    Ok(OffsetBuffer::new(ScalarBuffer::from(offsets)))
+}
+
+/// Returns `length` variable-length values from the streams `kind` (bytes) and "Length"
+fn byte_values<T: ByteArrayType<Offset = i32>>(
+    column: &Column,
+    kind: Kind,
+    length: usize,
+) -> Result<GenericByteArray<T>, Error> {
+    let offsets = offsets(column, length, None)?;
+    let size = offsets.last().copied().unwrap_or_default() as usize;
+
+    let mut values = vec![];
+    column
+        .get_stream(kind, vec![])?
+        .take(size as u64)
+        .read_to_end(&mut values)?;
+    if values.len() != size {
+        return Err(Error::OutOfSpec("the values are larger than their stream"));
+    }
+    Ok(GenericByteArray::try_new(
+        offsets,
+        Buffer::from_vec(values),
+        None,
+    )?)
+}
+
+/// Returns the (dense) values of a string or binary column and, for each of its non-null rows,
+/// the index of its value.
+fn dictionary_parts<T: ByteArrayType<Offset = i32>>(
+    column: &Column,
+    length: usize,
+) -> Result<(GenericByteArray<T>, Vec<u64>), Error> {
+    check_v2(column)?;
+    match column.encoding().kind() {
+        ColumnEncodingKind::DictionaryV2 => {
+            let size = column.dictionary_size().unwrap_or_default();
+            let values = byte_values::<T>(column, Kind::DictionaryData, size)?;
+            let keys = unsigned(column, Kind::Data, length)?;
+            if keys.iter().any(|key| *key >= size as u64) {
+                return Err(Error::OutOfSpec("a dictionary key is out of bounds"));
+            }
+            Ok((values, keys))
+        }
+        _ => Ok((
+            byte_values::<T>(column, Kind::Data, length)?,
+            (0..length as u64).collect(),
+        )),
+    }
+}
+
+fn binary<T: ByteArrayType<Offset = i32>>(
+    column: &Column,
+    length: usize,
+    nulls: Option<NullBuffer>,
+) -> Result<ArrayRef, Error> {
+    let (values, keys) = dictionary_parts::<T>(column, length)?;
+    if nulls.is_none() && column.encoding().kind() == ColumnEncodingKind::DirectV2 {
+        return Ok(Arc::new(values));
+    }
+    let keys = UInt64Array::try_new(scattered(keys, nulls.as_ref())?.into(), nulls)?;
+    Ok(take(&values, &keys, None)?)
+}
+
+fn dictionary<T: ByteArrayType<Offset = i32>>(
+    column: &Column,
+    length: usize,
+    nulls: Option<NullBuffer>,
+) -> Result<ArrayRef, Error> {
+    let (values, keys) = dictionary_parts::<T>(column, length)?;
+    let keys = keys
+        .into_iter()
+        .map(|key| {
+            i32::try_from(key).map_err(|_| Error::OutOfSpec("a dictionary key must fit in 32 bits"))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let keys = Int32Array::try_new(scattered(keys, nulls.as_ref())?.into(), nulls)?;
+    Ok(Arc::new(DictionaryArray::<Int32Type>::try_new(
+        keys,
+        Arc::new(values),
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use arrow_array::cast::AsArray;
+    use arrow_array::{
+        Array, BooleanArray, Decimal128Array, Float64Array, Int32Array, StringArray,
+        TimestampNanosecondArray,
+    };
+    use arrow_schema::{Field, Schema};
+
+    use crate::proto::Type;
+    use crate::read::read_metadata;
+    use crate::read::ORC_EPOCH;
+    use crate::test_util::{self, bools, bytes, decimal, ints, type_, uints, Streams};
+
+    use super::*;
+
+    fn types() -> Vec<Type> {
+        vec![
+            type_(
+                TypeKind::Struct,
+                vec![1, 2, 3, 4, 6, 8, 9, 10, 13],
+                vec!["a", "s", "d", "l", "st", "ts", "dec", "u", "m"],
+            ),
+            type_(TypeKind::Int, vec![], vec![]),
+            type_(TypeKind::String, vec![], vec![]),
+            type_(TypeKind::String, vec![], vec![]),
+            type_(TypeKind::List, vec![5], vec![]),
+            type_(TypeKind::Long, vec![], vec![]),
+            type_(TypeKind::Struct, vec![7], vec!["x"]),
+            type_(TypeKind::Double, vec![], vec![]),
+            type_(TypeKind::Timestamp, vec![], vec![]),
+            decimal(10, 2),
+            type_(TypeKind::Union, vec![11, 12], vec![]),
+            type_(TypeKind::Int, vec![], vec![]),
+            type_(TypeKind::String, vec![], vec![]),
+            type_(TypeKind::Map, vec![14, 15], vec![]),
+            type_(TypeKind::String, vec![], vec![]),
+            type_(TypeKind::Boolean, vec![], vec![]),
+        ]
+    }
+
+    /// The streams of each column of a stripe of 4 rows
+    fn streams() -> Streams {
+        let present = |values: &[bool]| (Kind::Present, bools(values));
+        vec![
+            vec![],
+            // a: [1, null, 3, 4]
+            vec![
+                present(&[true, false, true, true]),
+                (Kind::Data, ints(&[1, 3, 4])),
+            ],
+            // s: ["a", "bc", null, ""]
+            vec![
+                present(&[true, true, false, true]),
+                (Kind::Data, b"abc".to_vec()),
+                (Kind::Length, uints(&[1, 2, 0])),
+            ],
+            // d: ["x", "y", "x", "x"], dictionary-encoded
+            vec![
+                (Kind::Data, uints(&[0, 1, 0, 0])),
+                (Kind::DictionaryData, b"xy".to_vec()),
+                (Kind::Length, uints(&[1, 1])),
+            ],
+            // l: [[1, 2], null, [], [3]]
+            vec![
+                present(&[true, false, true, true]),
+                (Kind::Length, uints(&[2, 0, 1])),
+            ],
+            vec![(Kind::Data, ints(&[1, 2, 3]))],
+            // st: [{x: 1.5}, {x: null}, null, {x: 2.5}]
+            vec![present(&[true, true, false, true])],
+            vec![
+                present(&[true, false, true]),
+                (
+                    Kind::Data,
+                    [1.5f64, 2.5].iter().flat_map(|x| x.to_le_bytes()).collect(),
+                ),
+            ],
+            // ts: seconds since 2015 and nanoseconds
+            vec![
+                (Kind::Data, ints(&[0, 1, -1, 10])),
+                (Kind::Secondary, uints(&[0, (5 << 3) | 7, 0, 1 << 3])),
+            ],
+            // dec: [123.45, 5, 0.001, -0.7] as zigzag varints and their scales
+            vec![
+                (Kind::Data, vec![0xf2, 0xc0, 0x01, 10, 2, 13]),
+                (Kind::Secondary, ints(&[2, 0, 3, 1])),
+            ],
+            // u: [1, null, "z", 2]
+            vec![
+                present(&[true, false, true, true]),
+                (Kind::Data, bytes(&[0, 1, 0])),
+            ],
+            vec![(Kind::Data, ints(&[1, 2]))],
+            vec![(Kind::Data, b"z".to_vec()), (Kind::Length, uints(&[1]))],
+            // m: [{"k": true, "j": false}, {}, {"k": null}, {}]
+            vec![(Kind::Length, uints(&[2, 0, 1, 0]))],
+            vec![
+                (Kind::Data, b"kjk".to_vec()),
+                (Kind::Length, uints(&[1, 1, 1])),
+            ],
+            vec![
+                present(&[true, true, false]),
+                (Kind::Data, bools(&[true, false])),
+            ],
+        ]
+    }
+
+    /// Returns an uncompressed file with `stripes` equal stripes
+    fn file(stripes: usize) -> Vec<u8> {
+        test_util::file(types(), &vec![streams(); stripes], 4, &[(3, 2)])
+    }
+
+    #[test]
+    fn read() -> Result<(), Error> {
+        let file = file(2);
+        let mut reader = Cursor::new(&file);
+        let metadata = read_metadata(&mut reader)?;
+
+        let batches = RecordBatchIter::try_new(&mut reader, &metadata, None, 3)?
+            .collect::<Result<Vec<_>, Error>>()?;
+        assert_eq!(
+            batches.iter().map(|x| x.num_rows()).collect::<Vec<_>>(),
+            vec![3, 1, 3, 1]
+        );
+
+        let batch = read_stripe(
+            &mut reader,
+            &metadata,
+            1,
+            batches[0].schema(),
+            top_level_columns(&metadata.footer.types)?,
+        )?;
+
+        let expected = Int32Array::from(vec![Some(1), None, Some(3), Some(4)]);
+        assert_eq!(batch.column(0).as_ref(), &expected as &dyn Array);
+
+        let expected = StringArray::from(vec![Some("a"), Some("bc"), None, Some("")]);
+        assert_eq!(batch.column(1).as_ref(), &expected as &dyn Array);
+
+        let expected = StringArray::from(vec!["x", "y", "x", "x"]);
+        assert_eq!(batch.column(2).as_ref(), &expected as &dyn Array);
+
+        let expected = ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+            Some(vec![Some(1), Some(2)]),
+            None,
+            Some(vec![]),
+            Some(vec![Some(3)]),
+        ]);
+        assert_eq!(batch.column(3).as_ref(), &expected as &dyn Array);
+
+        let struct_ = batch.column(4).as_struct();
+        assert_eq!(struct_.logical_nulls().unwrap().null_count(), 1);
+        assert!(struct_.is_null(2));
+        let expected = Float64Array::from(vec![Some(1.5), None, None, Some(2.5)]);
+        assert_eq!(struct_.column(0).as_ref(), &expected as &dyn Array);
+
+        let second = 1_000_000_000;
+        let expected = TimestampNanosecondArray::from(vec![
+            ORC_EPOCH * second,
+            (ORC_EPOCH + 1) * second + 500_000_000,
+            (ORC_EPOCH - 1) * second,
+            (ORC_EPOCH + 10) * second + 1,
+        ]);
+        assert_eq!(batch.column(5).as_ref(), &expected as &dyn Array);
+
+        let expected = Decimal128Array::from(vec![12345, 500, 0, -70])
+            .with_precision_and_scale(10, 2)
+            .unwrap();
+        assert_eq!(batch.column(6).as_ref(), &expected as &dyn Array);
+
+        let union = batch.column(7).as_union();
+        assert_eq!(union.type_ids(), &[0, 0, 1, 0]);
+        assert_eq!(union.offsets().unwrap(), &[0, 1, 0, 2]);
+        let expected = Int32Array::from(vec![Some(1), None, Some(2)]);
+        assert_eq!(union.child(0).as_ref(), &expected as &dyn Array);
+        let expected = StringArray::from(vec!["z"]);
+        assert_eq!(union.child(1).as_ref(), &expected as &dyn Array);
+
+        let map = batch.column(8).as_map();
+        assert_eq!(map.value_offsets(), &[0, 2, 2, 3, 3]);
+        let expected = StringArray::from(vec!["k", "j", "k"]);
+        assert_eq!(map.keys().as_ref(), &expected as &dyn Array);
+        let expected = BooleanArray::from(vec![Some(true), Some(false), None]);
+        assert_eq!(map.values().as_ref(), &expected as &dyn Array);
+        Ok(())
+    }
+
+    #[test]
+    fn dictionaries() -> Result<(), Error> {
+        let file = file(1);
+        let mut reader = Cursor::new(&file);
+        let metadata = read_metadata(&mut reader)?;
+
+        let dictionary = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("s", dictionary.clone(), true),
+            Field::new("d", dictionary, true),
+        ]));
+        let batch = read_stripe(&mut reader, &metadata, 0, schema, &[2, 3])?;
+
+        let values = |column: usize| {
+            batch
+                .column(column)
+                .as_dictionary::<Int32Type>()
+                .downcast_dict::<StringArray>()
+                .unwrap()
+                .into_iter()
+                .map(|x| x.map(|x| x.to_string()))
+                .collect::<Vec<_>>()
+        };
+        let string = |x: &str| Some(x.to_string());
+        assert_eq!(values(0), vec![string("a"), string("bc"), None, string("")]);
+        assert_eq!(
+            values(1),
+            vec![string("x"), string("y"), string("x"), string("x")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn projection() -> Result<(), Error> {
+        let file = file(1);
+        let mut reader = Cursor::new(&file);
+        let metadata = read_metadata(&mut reader)?;
+
+        let mut iter = RecordBatchIter::try_new(&mut reader, &metadata, Some(&[5, 0]), 10)?;
+        assert_eq!(iter.schema().field(0).name(), "ts");
+        let batch = iter.next().unwrap()?;
+        assert_eq!(batch.num_columns(), 2);
+        assert_eq!(batch.column(1).as_primitive::<Int32Type>().value(3), 4);
+        assert!(iter.next().is_none());
+
+        // reading a column to an incompatible type errors
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Utf8, true)]));
+        let error = read_stripe(&mut reader, &metadata, 0, schema, &[1]).unwrap_err();
+        assert!(matches!(error.unlocated(), Error::NotYetImplemented(_)));
+        assert_eq!(error.location().unwrap().column, Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn writer_timezone() -> Result<(), Error> {
+        let read = |timezone| {
+            let file = test_util::file_with_timezone(types(), &[streams()], 4, &[(3, 2)], timezone);
+            let mut reader = Cursor::new(file);
+            let metadata = read_metadata(&mut reader)?;
+            let schema = Arc::new(infer_schema(&metadata.footer)?.project(&[5])?);
+            read_stripe(&mut reader, &metadata, 0, schema, &[8])
+        };
+        assert!(read(None).is_ok());
+        assert!(read(Some("UTC")).is_ok());
+        // timestamps are stored relative to the writer's timezone
+        let error = read(Some("America/Los_Angeles")).unwrap_err();
+        assert!(matches!(error.unlocated(), Error::NotYetImplemented(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn declared_lengths_are_limited() -> Result<(), Error> {
+        // a stripe declaring far more rows than its streams contain
+        let file = test_util::file(types(), &[streams()], 1_000_000_000_000, &[(3, 2)]);
+        let mut reader = Cursor::new(&file);
+        let metadata = read_metadata(&mut reader)?;
+        let error = RecordBatchIter::try_new(&mut reader, &metadata, None, 10)?
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(error.unlocated(), Error::LimitExceeded(_)));
+
+        // a list declaring far more items than its child contains
+        let mut streams = streams();
+        streams[4][1] = (Kind::Length, uints(&[1 << 29, 1 << 29, 1 << 29]));
+        let file = test_util::file(types(), &[streams], 4, &[(3, 2)]);
+        let mut reader = Cursor::new(&file);
+        let metadata = read_metadata(&mut reader)?;
+        let schema = Arc::new(infer_schema(&metadata.footer)?.project(&[3])?);
+        let error = read_stripe(&mut reader, &metadata, 0, schema, &[4]).unwrap_err();
+        assert!(matches!(error.unlocated(), Error::LimitExceeded(_)));
+        Ok(())
+    }
+}
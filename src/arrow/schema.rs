@@ -0,0 +1,568 @@
+//! Mapping between ORC's types and Arrow's [`Schema`], in both directions.
+//!
+//! [`infer_schema`] maps the file's types to a [`Schema`] and [`to_types`] maps a [`Schema`]
+//! to the types of a file. What Arrow's types cannot represent is kept in the fields'
+//! metadata:
+//! * chars and varchars have [`TYPE_KEY`] (`"char"` or `"varchar"`) and [`MAX_LENGTH_KEY`]
+//! * decimals have [`PRECISION_KEY`] and [`SCALE_KEY`], when the file declares them
+//! * the attributes of a type are stored under [`ATTRIBUTE_PREFIX`] and their key
+//!
+//! The attributes of the root type are stored in the schema's metadata.
+//!
+//! # Lossy cases
+//! Mapping ORC to Arrow and back results in the same types, except that:
+//! * decimals without precision (written by Hive 0.11) are mapped to
+//!   [`DEFAULT_DECIMAL_PRECISION`] and [`DEFAULT_DECIMAL_SCALE`] and written back with them
+//! * attributes are written back ordered by key
+//!
+//! Mapping Arrow to ORC is lossy for types that ORC does not have:
+//! * nullability is dropped, since every ORC type is nullable
+//! * the names of lists' items, maps' entries, keys and values and unions' variants are dropped
+//! * unions' type ids are replaced by the position of their variant
+//! * unsigned integers are widened to the next signed integer; `UInt64` is not supported
+//! * `Float16` is widened to `float`
+//! * large, view and fixed-size variants of strings, binaries and lists are mapped to their
+//!   ORC counterpart
+//! * dictionaries are mapped to the type of their values
+//! * the unit of timestamps is dropped (ORC stores nanoseconds), and so is the timezone of
+//!   timestamps with a timezone, which are mapped to `timestamp with local time zone`
+//! * `Date64` is mapped to `date`, dropping its milliseconds
+//!
+//! Other Arrow types (e.g. times, durations and intervals) are not supported.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_schema::{DataType, Field, Fields, Schema, TimeUnit, UnionFields, UnionMode};
+
+use crate::error::Error;
+use crate::proto::{r#type::Kind, Footer, StringPair, Type};
+pub use crate::read::types::top_level_columns;
+use crate::read::types::{children, get_type};
+
+/// The precision of decimals whose type declares none (written by Hive 0.11)
+pub const DEFAULT_DECIMAL_PRECISION: u8 = 38;
+/// The scale of decimals whose type declares none (written by Hive 0.11)
+pub const DEFAULT_DECIMAL_SCALE: i8 = 10;
+
+/// The key of the metadata of fields of chars and varchars, whose value is `"char"` or `"varchar"`
+pub const TYPE_KEY: &str = "orc.type";
+/// The key of the metadata of fields with the maximum length of chars and varchars
+pub const MAX_LENGTH_KEY: &str = "orc.max_length";
+/// The key of the metadata of fields with the precision of decimals
+pub const PRECISION_KEY: &str = "orc.precision";
+/// The key of the metadata of fields with the scale of decimals
+pub const SCALE_KEY: &str = "orc.scale";
+/// The prefix of the keys of the metadata of fields (and schemas) with the type's attributes
+pub const ATTRIBUTE_PREFIX: &str = "orc.attribute.";
+
+/// The maximum depth of nested types, which bounds the recursion over the (untrusted) schema
+const MAX_DEPTH: usize = 128;
+
+/// Infers the Arrow [`Schema`] of the file, whose fields are its top-level columns.
+///
+/// Strings, varchars and chars are mapped to [`DataType::Utf8`]; use
+/// [`DataType::Dictionary`] of [`DataType::Int32`] and [`DataType::Utf8`] in the schema
+/// passed to [`super::read::RecordBatchIter::with_schema`] to read them as dictionaries.
+pub fn infer_schema(footer: &Footer) -> Result<Schema, Error> {
+    let types = &footer.types;
+    let columns = top_level_columns(types)?;
+    let root = &types[0];
+    if root.field_names.len() != columns.len() {
+        return Err(Error::OutOfSpec(
+            "a struct must have one field name per child",
+        ));
+    }
+    let fields = columns
+        .iter()
+        .zip(&root.field_names)
+        .map(|(column, name)| field(types, *column, name, 1))
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(Schema::new_with_metadata(
+        fields,
+        attributes_to_metadata(root),
+    ))
+}
+
+/// Returns the Arrow [`DataType`] of `column`.
+pub fn to_data_type(types: &[Type], column: u32) -> Result<DataType, Error> {
+    data_type(types, column, 0)
+}
+
+/// Returns the Arrow [`Field`] named `name` of `column`, including its metadata.
+pub fn to_field(types: &[Type], column: u32, name: &str) -> Result<Field, Error> {
+    field(types, column, name, 0)
+}
+
+fn attributes_to_metadata(type_: &Type) -> HashMap<String, String> {
+    type_
+        .attributes
+        .iter()
+        .map(|pair| {
+            (
+                format!("{ATTRIBUTE_PREFIX}{}", pair.key()),
+                pair.value().to_string(),
+            )
+        })
+        .collect()
+}
+
+fn field(types: &[Type], column: u32, name: &str, depth: usize) -> Result<Field, Error> {
+    let type_ = get_type(types, column)?;
+    let mut metadata = attributes_to_metadata(type_);
+    match type_.kind() {
+        Kind::Char | Kind::Varchar => {
+            let kind = if type_.kind() == Kind::Char {
+                "char"
+            } else {
+                "varchar"
+            };
+            metadata.insert(TYPE_KEY.to_string(), kind.to_string());
+            if let Some(length) = type_.maximum_length {
+                metadata.insert(MAX_LENGTH_KEY.to_string(), length.to_string());
+            }
+        }
+        Kind::Decimal if type_.precision() != 0 => {
+            metadata.insert(PRECISION_KEY.to_string(), type_.precision().to_string());
+            metadata.insert(SCALE_KEY.to_string(), type_.scale().to_string());
+        }
+        _ => {}
+    }
+    let data_type = data_type(types, column, depth)?;
+    Ok(Field::new(name, data_type, true).with_metadata(metadata))
+}
+
+fn data_type(types: &[Type], column: u32, depth: usize) -> Result<DataType, Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::LimitExceeded("the schema is nested too deeply"));
+    }
+    let type_ = get_type(types, column)?;
+    let children = children(types, column)?;
+    let child = |index: usize, name: &str| -> Result<Field, Error> {
+        let child = children
+            .get(index)
+            .ok_or(Error::OutOfSpec("a nested type is missing children"))?;
+        field(types, *child, name, depth + 1)
+    };
+
+    Ok(match type_.kind() {
+        Kind::Boolean => DataType::Boolean,
+        Kind::Byte => DataType::Int8,
+        Kind::Short => DataType::Int16,
+        Kind::Int => DataType::Int32,
+        Kind::Long => DataType::Int64,
+        Kind::Float => DataType::Float32,
+        Kind::Double => DataType::Float64,
+        Kind::String | Kind::Varchar | Kind::Char => DataType::Utf8,
+        Kind::Binary => DataType::Binary,
+        // timestamps are the wall clock of the writer's timezone
+        Kind::Timestamp => DataType::Timestamp(TimeUnit::Nanosecond, None),
+        Kind::TimestampInstant => DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+        Kind::Date => DataType::Date32,
+        Kind::Decimal => {
+            let (precision, scale) = (type_.precision(), type_.scale());
+            if precision == 0 {
+                DataType::Decimal128(DEFAULT_DECIMAL_PRECISION, DEFAULT_DECIMAL_SCALE)
+            } else if precision > 38 || scale > precision {
+                return Err(Error::OutOfSpec(
+                    "a decimal's precision must be at most 38 and its scale at most its precision",
+                ));
+            } else {
+                DataType::Decimal128(precision as u8, scale as i8)
+            }
+        }
+        Kind::List => DataType::List(Arc::new(child(0, "item")?)),
+        Kind::Map => {
+            let entries = Fields::from(vec![
+                child(0, "keys")?.with_nullable(false),
+                child(1, "values")?,
+            ]);
+            DataType::Map(
+                Arc::new(Field::new("entries", DataType::Struct(entries), false)),
+                false,
+            )
+        }
+        Kind::Struct => {
+            if type_.field_names.len() != children.len() {
+                return Err(Error::OutOfSpec(
+                    "a struct must have one field name per child",
+                ));
+            }
+            let fields = type_
+                .field_names
+                .iter()
+                .enumerate()
+                .map(|(index, name)| child(index, name))
+                .collect::<Result<Vec<_>, Error>>()?;
+            DataType::Struct(fields.into())
+        }
+        Kind::Union => {
+            if children.len() > i8::MAX as usize + 1 {
+                return Err(Error::NotYetImplemented(
+                    "unions with more than 128 variants",
+                ));
+            }
+            let fields = (0..children.len())
+                .map(|index| {
+                    let field = child(index, &format!("_union_{index}"))?;
+                    Ok((index as i8, Arc::new(field)))
+                })
+                .collect::<Result<UnionFields, Error>>()?;
+            DataType::Union(fields, UnionMode::Dense)
+        }
+    })
+}
+
+/// Returns the ORC types of a file with `schema`, in pre-order, whose root is a struct
+/// with the fields of `schema`. See the [module's documentation](self) for the cases where
+/// this is lossy.
+/// # Errors
+/// This function errors when `schema` contains types not supported by ORC.
+pub fn to_types(schema: &Schema) -> Result<Vec<Type>, Error> {
+    let mut types = vec![Type {
+        kind: Some(Kind::Struct as i32),
+        field_names: schema.fields().iter().map(|f| f.name().clone()).collect(),
+        attributes: metadata_to_attributes(schema.metadata()),
+        ..Default::default()
+    }];
+    for field in schema.fields() {
+        let child = push_field(&mut types, field, 1)?;
+        types[0].subtypes.push(child);
+    }
+    Ok(types)
+}
+
+fn metadata_to_attributes(metadata: &HashMap<String, String>) -> Vec<StringPair> {
+    let mut attributes = metadata
+        .iter()
+        .filter_map(|(key, value)| {
+            key.strip_prefix(ATTRIBUTE_PREFIX).map(|key| StringPair {
+                key: Some(key.to_string()),
+                value: Some(value.clone()),
+            })
+        })
+        .collect::<Vec<_>>();
+    attributes.sort_by(|a, b| a.key.cmp(&b.key));
+    attributes
+}
+
+/// Pushes the type of `field` and of its children to `types`, returning its column
+fn push_field(types: &mut Vec<Type>, field: &Field, depth: usize) -> Result<u32, Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::LimitExceeded("the schema is nested too deeply"));
+    }
+    let column = types.len() as u32;
+    types.push(Type::default());
+
+    let metadata = field.metadata();
+    let parse = |key: &str| {
+        metadata
+            .get(key)
+            .and_then(|value| value.parse::<u32>().ok())
+    };
+    let mut children = |fields: &mut dyn Iterator<Item = &Field>| {
+        fields
+            .map(|child| push_field(types, child, depth + 1))
+            .collect::<Result<Vec<_>, Error>>()
+    };
+
+    let mut type_ = Type {
+        attributes: metadata_to_attributes(metadata),
+        ..Default::default()
+    };
+    let kind = match field.data_type() {
+        DataType::Boolean => Kind::Boolean,
+        DataType::Int8 => Kind::Byte,
+        DataType::Int16 | DataType::UInt8 => Kind::Short,
+        DataType::Int32 | DataType::UInt16 => Kind::Int,
+        DataType::Int64 | DataType::UInt32 => Kind::Long,
+        DataType::Float16 | DataType::Float32 => Kind::Float,
+        DataType::Float64 => Kind::Double,
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => string_kind(field),
+        DataType::Dictionary(_, value) if value.is_string() => string_kind(field),
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(_) => Kind::Binary,
+        DataType::Dictionary(_, value)
+            if matches!(
+                value.as_ref(),
+                DataType::Binary | DataType::LargeBinary | DataType::BinaryView
+            ) =>
+        {
+            Kind::Binary
+        }
+        DataType::Timestamp(_, None) => Kind::Timestamp,
+        DataType::Timestamp(_, Some(_)) => Kind::TimestampInstant,
+        DataType::Date32 | DataType::Date64 => Kind::Date,
+        DataType::Decimal32(precision, scale)
+        | DataType::Decimal64(precision, scale)
+        | DataType::Decimal128(precision, scale)
+        | DataType::Decimal256(precision, scale) => {
+            if *precision > 38 || *scale < 0 {
+                return Err(Error::NotYetImplemented(
+                    "decimals with a precision above 38 or a negative scale",
+                ));
+            }
+            type_.precision = Some(*precision as u32);
+            type_.scale = Some(*scale as u32);
+            Kind::Decimal
+        }
+        DataType::List(child)
+        | DataType::LargeList(child)
+        | DataType::ListView(child)
+        | DataType::LargeListView(child)
+        | DataType::FixedSizeList(child, _) => {
+            type_.subtypes = children(&mut std::iter::once(child.as_ref()))?;
+            Kind::List
+        }
+        DataType::Map(entries, _) => {
+            let DataType::Struct(fields) = entries.data_type() else {
+                return Err(Error::NotYetImplemented(
+                    "maps whose entries are not structs",
+                ));
+            };
+            if fields.len() != 2 {
+                return Err(Error::NotYetImplemented(
+                    "maps whose entries do not have two fields",
+                ));
+            }
+            type_.subtypes = children(&mut fields.iter().map(|f| f.as_ref()))?;
+            Kind::Map
+        }
+        DataType::Struct(fields) => {
+            type_.field_names = fields.iter().map(|f| f.name().clone()).collect();
+            type_.subtypes = children(&mut fields.iter().map(|f| f.as_ref()))?;
+            Kind::Struct
+        }
+        DataType::Union(fields, _) => {
+            type_.subtypes = children(&mut fields.iter().map(|(_, f)| f.as_ref()))?;
+            Kind::Union
+        }
+        _ => return Err(Error::NotYetImplemented("writing this Arrow type to ORC")),
+    };
+    if let (Kind::Char | Kind::Varchar, Some(length)) = (kind, parse(MAX_LENGTH_KEY)) {
+        type_.maximum_length = Some(length);
+    }
+    type_.kind = Some(kind as i32);
+    types[column as usize] = type_;
+    Ok(column)
+}
+
+/// Returns the kind of a string field, which is a char or varchar according to its metadata
+fn string_kind(field: &Field) -> Kind {
+    match field.metadata().get(TYPE_KEY).map(|x| x.as_str()) {
+        Some("char") => Kind::Char,
+        Some("varchar") => Kind::Varchar,
+        _ => Kind::String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{decimal, type_};
+
+    use super::*;
+
+    #[test]
+    fn nested() -> Result<(), Error> {
+        let types = vec![
+            type_(Kind::Struct, vec![1, 2, 4, 7], vec!["a", "b", "c", "d"]),
+            type_(Kind::Int, vec![], vec![]),
+            type_(Kind::List, vec![3], vec![]),
+            type_(Kind::String, vec![], vec![]),
+            type_(Kind::Map, vec![5, 6], vec![]),
+            type_(Kind::Varchar, vec![], vec![]),
+            type_(Kind::TimestampInstant, vec![], vec![]),
+            type_(Kind::Union, vec![8, 9], vec![]),
+            type_(Kind::Decimal, vec![], vec![]),
+            decimal(10, 2),
+        ];
+        let footer = Footer {
+            types,
+            ..Default::default()
+        };
+        let schema = infer_schema(&footer)?;
+
+        assert_eq!(schema.field(0), &Field::new("a", DataType::Int32, true));
+        assert_eq!(
+            schema.field(1).data_type(),
+            &DataType::new_list(DataType::Utf8, true)
+        );
+        assert_eq!(
+            schema.field(2).data_type(),
+            &DataType::Map(
+                Arc::new(Field::new(
+                    "entries",
+                    DataType::Struct(Fields::from(vec![
+                        Field::new("keys", DataType::Utf8, false)
+                            .with_metadata([(TYPE_KEY.to_string(), "varchar".to_string())].into()),
+                        Field::new(
+                            "values",
+                            DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+                            true
+                        ),
+                    ])),
+                    false
+                )),
+                false
+            )
+        );
+        let DataType::Union(fields, UnionMode::Dense) = schema.field(3).data_type() else {
+            panic!()
+        };
+        let types = fields
+            .iter()
+            .map(|(_, field)| field.data_type().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![DataType::Decimal128(38, 10), DataType::Decimal128(10, 2)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        // a type whose child is itself
+        let types = vec![type_(Kind::Struct, vec![0], vec!["a"])];
+        assert!(to_data_type(&types, 0).is_err());
+
+        // a missing child
+        let types = vec![type_(Kind::List, vec![], vec![])];
+        assert!(to_data_type(&types, 0).is_err());
+
+        // the root is not a struct
+        let footer = Footer {
+            types: vec![type_(Kind::Int, vec![], vec![])],
+            ..Default::default()
+        };
+        assert!(infer_schema(&footer).is_err());
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), Error> {
+        let attribute = |key: &str, value: &str| StringPair {
+            key: Some(key.to_string()),
+            value: Some(value.to_string()),
+        };
+        let mut root = type_(
+            Kind::Struct,
+            vec![1, 2, 3, 4, 6, 9],
+            vec!["a", "b", "c", "d", "e", "f"],
+        );
+        root.attributes = vec![attribute("owner", "catalog")];
+        let types = vec![
+            root,
+            Type {
+                maximum_length: Some(3),
+                attributes: vec![attribute("a", "1"), attribute("b", "2")],
+                ..type_(Kind::Char, vec![], vec![])
+            },
+            Type {
+                maximum_length: Some(100),
+                ..type_(Kind::Varchar, vec![], vec![])
+            },
+            decimal(10, 2),
+            type_(Kind::List, vec![5], vec![]),
+            Type {
+                attributes: vec![attribute("unit", "ms")],
+                ..type_(Kind::Timestamp, vec![], vec![])
+            },
+            type_(Kind::Map, vec![7, 8], vec![]),
+            type_(Kind::Binary, vec![], vec![]),
+            type_(Kind::TimestampInstant, vec![], vec![]),
+            type_(Kind::Union, vec![10, 11], vec![]),
+            type_(Kind::Struct, vec![], vec![]),
+            type_(Kind::Date, vec![], vec![]),
+        ];
+        let footer = Footer {
+            types: types.clone(),
+            ..Default::default()
+        };
+        let schema = infer_schema(&footer)?;
+
+        let a = schema.field(0);
+        assert_eq!(a.data_type(), &DataType::Utf8);
+        assert_eq!(a.metadata()[TYPE_KEY], "char");
+        assert_eq!(a.metadata()[MAX_LENGTH_KEY], "3");
+        assert_eq!(a.metadata()["orc.attribute.b"], "2");
+        assert_eq!(schema.field(2).metadata()[PRECISION_KEY], "10");
+        assert_eq!(schema.field(2).metadata()[SCALE_KEY], "2");
+        assert_eq!(schema.metadata()["orc.attribute.owner"], "catalog");
+
+        assert_eq!(to_types(&schema)?, types);
+        Ok(())
+    }
+
+    #[test]
+    fn lossy() -> Result<(), Error> {
+        // decimals without precision are written back with the default precision and scale
+        let types = vec![
+            type_(Kind::Struct, vec![1], vec!["a"]),
+            type_(Kind::Decimal, vec![], vec![]),
+        ];
+        let footer = Footer {
+            types,
+            ..Default::default()
+        };
+        let types = to_types(&infer_schema(&footer)?)?;
+        assert_eq!(types[1].precision, Some(38));
+        assert_eq!(types[1].scale, Some(10));
+
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::UInt8, false),
+            Field::new("b", DataType::UInt32, true),
+            Field::new("c", DataType::Float16, true),
+            Field::new(
+                "d",
+                DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::LargeUtf8)),
+                true,
+            ),
+            Field::new("e", DataType::Timestamp(TimeUnit::Second, None), true),
+            Field::new(
+                "f",
+                DataType::Timestamp(TimeUnit::Millisecond, Some("+01:00".into())),
+                true,
+            ),
+            Field::new("g", DataType::Date64, true),
+            Field::new_fixed_size_list("h", Field::new("x", DataType::Int8, false), 2, true),
+            Field::new("i", DataType::Decimal256(20, 4), true),
+        ]);
+        let kinds = to_types(&schema)?
+            .iter()
+            .map(|type_| type_.kind())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                Kind::Struct,
+                Kind::Short,
+                Kind::Long,
+                Kind::Float,
+                Kind::String,
+                Kind::Timestamp,
+                Kind::TimestampInstant,
+                Kind::Date,
+                Kind::List,
+                Kind::Byte,
+                Kind::Decimal,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported() {
+        for data_type in [
+            DataType::UInt64,
+            DataType::Null,
+            DataType::Time32(TimeUnit::Second),
+            DataType::Duration(TimeUnit::Second),
+            DataType::Decimal256(40, 2),
+            DataType::Decimal128(10, -2),
+        ] {
+            let schema = Schema::new(vec![Field::new("a", data_type, true)]);
+            assert!(to_types(&schema).is_err());
+        }
+    }
+}
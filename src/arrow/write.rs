@@ -0,0 +1,775 @@
+//! Serialization of Arrow's [`RecordBatch`]es to ORC.
+//!
+//! [`OrcArrowWriter`] writes batches whose schema is mapped to the file's types by
+//! [`to_types`], see its documentation for the Arrow types that ORC does not have.
+//! Values are converted as follows:
+//! * dictionaries are written as their values
+//! * timestamps are written in nanoseconds since the unix epoch, and timestamps without
+//!   a timezone as if they were in UTC (the file's writer timezone)
+//! * `Date64` is written in days, rounded down
+//! * the rows of unions are always valid: a null is written to their variant
+use std::io::Write;
+use std::ops::Range;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    ArrowTimestampType, Date32Type, Date64Type, Decimal128Type, Decimal256Type, Decimal32Type,
+    Decimal64Type, Float16Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
+    Int8Type, TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+    TimestampSecondType, UInt16Type, UInt32Type, UInt8Type,
+};
+use arrow_array::{Array, ArrowPrimitiveType, BooleanArray, OffsetSizeTrait, RecordBatch};
+use arrow_schema::{DataType, SchemaRef, TimeUnit};
+use arrow_select::filter::filter;
+use arrow_select::take::take;
+
+use crate::error::Error;
+use crate::write::column::{ColumnWriter, IntegerWriter, TimestampWriter};
+use crate::write::{OrcWriter, WriteOptions};
+
+use super::schema::to_types;
+
+const MILLISECONDS_PER_DAY: i64 = 86_400_000;
+
+/// Writes Arrow's [`RecordBatch`]es to an ORC file.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+///
+/// use arrow_array::{Int64Array, RecordBatch};
+/// use arrow_schema::{DataType, Field, Schema};
+/// use orc_format::arrow::write::OrcArrowWriter;
+/// use orc_format::write::WriteOptions;
+///
+/// let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+/// let array = Int64Array::from(vec![Some(1), None, Some(3)]);
+/// let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(array)])?;
+///
+/// let mut writer = OrcArrowWriter::try_new(vec![], schema, WriteOptions::default())?;
+/// writer.write(&batch)?;
+/// let file = writer.finish()?;
+/// # Ok::<(), orc_format::error::Error>(())
+/// ```
+pub struct OrcArrowWriter<W: Write> {
+    writer: OrcWriter<W>,
+    schema: SchemaRef,
+}
+
+impl<W: Write> OrcArrowWriter<W> {
+    /// Returns a new [`OrcArrowWriter`] of a file with `schema`, writing the file's header
+    /// to `writer`.
+    /// # Errors
+    /// This function errors when `schema` contains types not supported by ORC, `options`
+    /// are not supported or writing fails.
+    pub fn try_new(writer: W, schema: SchemaRef, options: WriteOptions) -> Result<Self, Error> {
+        let types = to_types(&schema)?;
+        Ok(Self {
+            writer: OrcWriter::try_new(writer, types, options)?,
+            schema,
+        })
+    }
+
+    /// Returns the schema of the batches written by this writer
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Writes the rows of `batch`, finishing row groups and stripes as they fill up.
+    /// # Errors
+    /// This function errors when the types of the columns of `batch` are not those of the
+    /// writer's schema, a value is not representable in ORC (e.g. a timestamp beyond
+    /// nanoseconds), and when compressing or writing fails. The writer must not be used
+    /// after an error.
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<(), Error> {
+        let schema = batch.schema_ref();
+        if schema.fields().len() != self.schema.fields().len()
+            || schema
+                .fields()
+                .iter()
+                .zip(self.schema.fields())
+                .any(|(field, expected)| field.data_type() != expected.data_type())
+        {
+            return Err(Error::OutOfSpec(
+                "the batch's schema must be the schema of the writer",
+            ));
+        }
+
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            let max_rows = usize::try_from(self.writer.max_rows()).unwrap_or(usize::MAX);
+            let length = (batch.num_rows() - offset).min(max_rows);
+            for (writer, array) in self.writer.columns_mut().iter_mut().zip(batch.columns()) {
+                serialize(writer, array.slice(offset, length).as_ref())?;
+            }
+            self.writer.write_rows(length as u64)?;
+            offset += length;
+        }
+        Ok(())
+    }
+
+    /// Adds a user-defined `name` and `value` to the file's footer
+    pub fn add_user_metadata(&mut self, name: String, value: Vec<u8>) {
+        self.writer.add_user_metadata(name, value)
+    }
+
+    /// Writes the current stripe and the file's tail, returning the inner writer.
+    /// # Errors
+    /// This function errors when compressing or writing fails.
+    pub fn finish(self) -> Result<W, Error> {
+        self.writer.finish()
+    }
+}
+
+/// Appends `range` to `ranges`, merging it with the last range when they are contiguous
+fn push_range(ranges: &mut Vec<Range<usize>>, range: Range<usize>) {
+    if range.is_empty() {
+        return;
+    }
+    match ranges.last_mut() {
+        Some(last) if last.end == range.start => last.end = range.end,
+        _ => ranges.push(range),
+    }
+}
+
+/// Writes the rows `ranges` of `array` to `writer`
+fn serialize_ranges(
+    writer: &mut ColumnWriter,
+    array: &dyn Array,
+    ranges: &[Range<usize>],
+) -> Result<(), Error> {
+    ranges
+        .iter()
+        .try_for_each(|range| serialize(writer, array.slice(range.start, range.len()).as_ref()))
+}
+
+fn integers<T: ArrowPrimitiveType>(writer: &mut IntegerWriter, array: &dyn Array)
+where
+    T::Native: Into<i64>,
+{
+    let values = array.as_primitive::<T>().iter();
+    values.for_each(|value| writer.write(value.map(Into::into)))
+}
+
+fn timestamps<T: ArrowTimestampType>(
+    writer: &mut TimestampWriter,
+    array: &dyn Array,
+) -> Result<(), Error> {
+    let factor = match T::UNIT {
+        TimeUnit::Second => 1_000_000_000,
+        TimeUnit::Millisecond => 1_000_000,
+        TimeUnit::Microsecond => 1_000,
+        TimeUnit::Nanosecond => 1,
+    };
+    for value in array.as_primitive::<T>().iter() {
+        let value = value
+            .map(|value| value.checked_mul(factor))
+            .map(|value| value.ok_or(Error::OutOfSpec("a timestamp overflows nanoseconds")))
+            .transpose()?;
+        writer.write(value);
+    }
+    Ok(())
+}
+
+/// Writes the lengths of the lists of `array`, whose items are at `offsets(row)`, with
+/// `write`, returning the ranges of the items of its valid lists
+fn lists(
+    array: &dyn Array,
+    offsets: impl Fn(usize) -> Range<usize>,
+    mut write: impl FnMut(Option<usize>),
+) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    for row in 0..array.len() {
+        let range = array.is_valid(row).then(|| offsets(row));
+        write(range.as_ref().map(|range| range.len()));
+        if let Some(range) = range {
+            push_range(&mut ranges, range);
+        }
+    }
+    ranges
+}
+
+/// The items of the lists with `offsets`
+fn offsets<O: OffsetSizeTrait>(offsets: &[O]) -> impl Fn(usize) -> Range<usize> + '_ {
+    |row| offsets[row].as_usize()..offsets[row + 1].as_usize()
+}
+
+/// The items of the list views with `offsets` and `sizes`
+fn views<'a, O: OffsetSizeTrait>(
+    offsets: &'a [O],
+    sizes: &'a [O],
+) -> impl Fn(usize) -> Range<usize> + 'a {
+    |row| {
+        let start = offsets[row].as_usize();
+        start..start + sizes[row].as_usize()
+    }
+}
+
+/// Writes the values of `array` to `writer`, whose type must be the type of `array` mapped
+/// by [`to_types`]
+fn serialize(writer: &mut ColumnWriter, array: &dyn Array) -> Result<(), Error> {
+    if let DataType::Dictionary(_, _) = array.data_type() {
+        let dictionary = array.as_any_dictionary();
+        let values = take(dictionary.values(), dictionary.keys(), None)?;
+        return serialize(writer, values.as_ref());
+    }
+
+    match (writer, array.data_type()) {
+        (ColumnWriter::Boolean(writer), DataType::Boolean) => array
+            .as_boolean()
+            .iter()
+            .for_each(|value| writer.write(value)),
+        (ColumnWriter::Byte(writer), DataType::Int8) => {
+            let values = array.as_primitive::<Int8Type>().iter();
+            values.for_each(|value| writer.write(value))
+        }
+        (ColumnWriter::Integer(writer), DataType::Int16) => integers::<Int16Type>(writer, array),
+        (ColumnWriter::Integer(writer), DataType::UInt8) => integers::<UInt8Type>(writer, array),
+        (ColumnWriter::Integer(writer), DataType::Int32) => integers::<Int32Type>(writer, array),
+        (ColumnWriter::Integer(writer), DataType::UInt16) => integers::<UInt16Type>(writer, array),
+        (ColumnWriter::Integer(writer), DataType::Int64) => integers::<Int64Type>(writer, array),
+        (ColumnWriter::Integer(writer), DataType::UInt32) => integers::<UInt32Type>(writer, array),
+        (ColumnWriter::Float(writer), DataType::Float16) => {
+            let values = array.as_primitive::<Float16Type>().iter();
+            values.for_each(|value| writer.write(value.map(|value| value.to_f32())))
+        }
+        (ColumnWriter::Float(writer), DataType::Float32) => {
+            let values = array.as_primitive::<Float32Type>().iter();
+            values.for_each(|value| writer.write(value))
+        }
+        (ColumnWriter::Double(writer), DataType::Float64) => {
+            let values = array.as_primitive::<Float64Type>().iter();
+            values.for_each(|value| writer.write(value))
+        }
+        (ColumnWriter::String(writer), DataType::Utf8) => array
+            .as_string::<i32>()
+            .iter()
+            .for_each(|value| writer.write(value)),
+        (ColumnWriter::String(writer), DataType::LargeUtf8) => array
+            .as_string::<i64>()
+            .iter()
+            .for_each(|value| writer.write(value)),
+        (ColumnWriter::String(writer), DataType::Utf8View) => array
+            .as_string_view()
+            .iter()
+            .for_each(|value| writer.write(value)),
+        (ColumnWriter::Binary(writer), DataType::Binary) => array
+            .as_binary::<i32>()
+            .iter()
+            .for_each(|value| writer.write(value)),
+        (ColumnWriter::Binary(writer), DataType::LargeBinary) => array
+            .as_binary::<i64>()
+            .iter()
+            .for_each(|value| writer.write(value)),
+        (ColumnWriter::Binary(writer), DataType::BinaryView) => array
+            .as_binary_view()
+            .iter()
+            .for_each(|value| writer.write(value)),
+        (ColumnWriter::Binary(writer), DataType::FixedSizeBinary(_)) => {
+            let values = array.as_fixed_size_binary().iter();
+            values.for_each(|value| writer.write(value))
+        }
+        (ColumnWriter::Date(writer), DataType::Date32) => {
+            let values = array.as_primitive::<Date32Type>().iter();
+            values.for_each(|value| writer.write(value))
+        }
+        (ColumnWriter::Date(writer), DataType::Date64) => {
+            let values = array.as_primitive::<Date64Type>().iter();
+            values.for_each(|value| {
+                writer.write(value.map(|value| value.div_euclid(MILLISECONDS_PER_DAY) as i32))
+            })
+        }
+        (ColumnWriter::Timestamp(writer), DataType::Timestamp(unit, _)) => match unit {
+            TimeUnit::Second => timestamps::<TimestampSecondType>(writer, array)?,
+            TimeUnit::Millisecond => timestamps::<TimestampMillisecondType>(writer, array)?,
+            TimeUnit::Microsecond => timestamps::<TimestampMicrosecondType>(writer, array)?,
+            TimeUnit::Nanosecond => timestamps::<TimestampNanosecondType>(writer, array)?,
+        },
+        (ColumnWriter::Decimal(writer), DataType::Decimal32(_, _)) => {
+            let values = array.as_primitive::<Decimal32Type>().iter();
+            values.for_each(|value| writer.write(value.map(i128::from)))
+        }
+        (ColumnWriter::Decimal(writer), DataType::Decimal64(_, _)) => {
+            let values = array.as_primitive::<Decimal64Type>().iter();
+            values.for_each(|value| writer.write(value.map(i128::from)))
+        }
+        (ColumnWriter::Decimal(writer), DataType::Decimal128(_, _)) => {
+            let values = array.as_primitive::<Decimal128Type>().iter();
+            values.for_each(|value| writer.write(value))
+        }
+        (ColumnWriter::Decimal(writer), DataType::Decimal256(_, _)) => {
+            for value in array.as_primitive::<Decimal256Type>().iter() {
+                let value = value
+                    .map(|value| value.to_i128())
+                    .map(|value| value.ok_or(Error::OutOfSpec("a decimal overflows 128 bits")))
+                    .transpose()?;
+                writer.write(value);
+            }
+        }
+        (ColumnWriter::List(writer), DataType::List(_)) => {
+            let list = array.as_list::<i32>();
+            let ranges = lists(array, offsets(list.value_offsets()), |x| writer.write(x));
+            serialize_ranges(writer.child_mut(), list.values(), &ranges)?
+        }
+        (ColumnWriter::List(writer), DataType::LargeList(_)) => {
+            let list = array.as_list::<i64>();
+            let ranges = lists(array, offsets(list.value_offsets()), |x| writer.write(x));
+            serialize_ranges(writer.child_mut(), list.values(), &ranges)?
+        }
+        (ColumnWriter::List(writer), DataType::ListView(_)) => {
+            let list = array.as_list_view::<i32>();
+            let items = views(list.value_offsets(), list.value_sizes());
+            let ranges = lists(array, items, |x| writer.write(x));
+            serialize_ranges(writer.child_mut(), list.values(), &ranges)?
+        }
+        (ColumnWriter::List(writer), DataType::LargeListView(_)) => {
+            let list = array.as_list_view::<i64>();
+            let items = views(list.value_offsets(), list.value_sizes());
+            let ranges = lists(array, items, |x| writer.write(x));
+            serialize_ranges(writer.child_mut(), list.values(), &ranges)?
+        }
+        (ColumnWriter::List(writer), DataType::FixedSizeList(_, _)) => {
+            let list = array.as_fixed_size_list();
+            let size = list.value_length() as usize;
+            let items = |row: usize| row * size..(row + 1) * size;
+            let ranges = lists(array, items, |x| writer.write(x));
+            serialize_ranges(writer.child_mut(), list.values(), &ranges)?
+        }
+        (ColumnWriter::Map(writer), DataType::Map(_, _)) => {
+            let map = array.as_map();
+            let ranges = lists(array, offsets(map.value_offsets()), |x| writer.write(x));
+            let (keys, values) = writer.children_mut();
+            serialize_ranges(keys, map.keys(), &ranges)?;
+            serialize_ranges(values, map.values(), &ranges)?
+        }
+        (ColumnWriter::Struct(writer), DataType::Struct(_)) => {
+            let struct_ = array.as_struct();
+            (0..array.len()).for_each(|row| writer.write(struct_.is_valid(row)));
+            // the children only contain the valid rows, filtered once per child
+            let valid = struct_
+                .nulls()
+                .filter(|nulls| nulls.null_count() > 0)
+                .map(|nulls| BooleanArray::new(nulls.inner().clone(), None));
+            for (writer, column) in writer.children_mut().iter_mut().zip(struct_.columns()) {
+                match &valid {
+                    Some(valid) => serialize(writer, filter(column, valid)?.as_ref())?,
+                    None => serialize(writer, column.as_ref())?,
+                }
+            }
+        }
+        (ColumnWriter::Union(writer), DataType::Union(fields, _)) => {
+            let union = array.as_union();
+            let mut ranges = vec![vec![]; fields.len()];
+            for row in 0..array.len() {
+                let type_id = union.type_id(row);
+                let tag =
+                    fields
+                        .iter()
+                        .position(|(id, _)| id == type_id)
+                        .ok_or(Error::OutOfSpec(
+                            "a union's type id must be one of its fields",
+                        ))?;
+                writer.write(Some(tag as u8))?;
+                let offset = union.value_offset(row);
+                push_range(&mut ranges[tag], offset..offset + 1);
+            }
+            let children = writer.children_mut().iter_mut().zip(fields.iter());
+            for ((writer, (type_id, _)), ranges) in children.zip(&ranges) {
+                serialize_ranges(writer, union.child(type_id).as_ref(), ranges)?;
+            }
+        }
+        _ => {
+            return Err(Error::NotYetImplemented(
+                "writing this Arrow type to this type of column",
+            ))
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    use arrow_array::builder::{Int64Builder, MapBuilder, StringBuilder};
+    use arrow_array::types::Int8Type;
+    use arrow_array::{
+        ArrayRef, BinaryArray, BooleanArray, Date32Array, Date64Array, Decimal128Array,
+        Decimal256Array, DictionaryArray, FixedSizeBinaryArray, FixedSizeListArray, Float16Array,
+        Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array,
+        LargeStringArray, ListArray, StringArray, StringViewArray, StructArray,
+        TimestampMillisecondArray, TimestampNanosecondArray, TimestampSecondArray, UInt8Array,
+        UnionArray,
+    };
+    use arrow_buffer::{i256, NullBuffer};
+    use arrow_schema::{Field, Fields, Schema, UnionFields};
+    use arrow_select::concat::concat_batches;
+
+    use crate::arrow::read::RecordBatchIter;
+    use crate::proto::CompressionKind;
+    use crate::read::read_metadata;
+    use crate::read::types::top_level_columns;
+
+    use super::*;
+
+    /// Writes `batches` with `options` and reads the file back, as it is written when
+    /// `schema` is `None` and with `schema` otherwise
+    fn round_trip(
+        batches: &[RecordBatch],
+        schema: Option<SchemaRef>,
+        options: WriteOptions,
+    ) -> Result<Vec<RecordBatch>, Error> {
+        let mut writer = OrcArrowWriter::try_new(vec![], batches[0].schema(), options)?;
+        for batch in batches {
+            writer.write(batch)?;
+        }
+        let file = writer.finish()?;
+
+        let mut reader = Cursor::new(&file);
+        let metadata = read_metadata(&mut reader)?;
+        let iter = match schema {
+            Some(schema) => {
+                let columns = top_level_columns(&metadata.footer.types)?.to_vec();
+                RecordBatchIter::with_schema(&mut reader, &metadata, schema, columns, 100)
+            }
+            None => RecordBatchIter::try_new(&mut reader, &metadata, None, 100)?,
+        };
+        iter.collect()
+    }
+
+    fn batch(columns: Vec<(&str, ArrayRef)>) -> RecordBatch {
+        RecordBatch::try_from_iter(columns).unwrap()
+    }
+
+    #[test]
+    fn round_trip_types() -> Result<(), Error> {
+        let list = ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), None]),
+            None,
+            Some(vec![]),
+            Some(vec![Some(4)]),
+            Some(vec![Some(5), Some(6)]),
+        ]);
+        let mut map = MapBuilder::new(None, StringBuilder::new(), Int64Builder::new());
+        for entries in [
+            vec![("a", Some(1))],
+            vec![],
+            vec![("b", None), ("c", Some(3))],
+        ] {
+            for (key, value) in entries {
+                map.keys().append_value(key);
+                map.values().append_option(value);
+            }
+            map.append(true)?;
+        }
+        map.append(false)?;
+        map.keys().append_value("d");
+        map.values().append_value(4);
+        map.append(true)?;
+
+        let fields = Fields::from(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]);
+        let struct_ = StructArray::try_new(
+            fields,
+            vec![
+                Arc::new(Int32Array::from(vec![
+                    Some(1),
+                    None,
+                    None,
+                    Some(4),
+                    Some(5),
+                ])),
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    None,
+                    None,
+                    Some(""),
+                    None,
+                ])),
+            ],
+            Some(NullBuffer::from(vec![true, true, false, true, true])),
+        )?;
+        let union = UnionArray::try_new(
+            UnionFields::try_new(
+                vec![0, 1],
+                vec![
+                    Field::new("_union_0", DataType::Int32, true),
+                    Field::new("_union_1", DataType::Utf8, true),
+                ],
+            )?,
+            vec![0, 1, 0, 1, 1].into(),
+            Some(vec![0, 0, 1, 1, 2].into()),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), None])),
+                Arc::new(StringArray::from(vec!["a", "b", "c"])),
+            ],
+        )?;
+
+        let batch = batch(vec![
+            (
+                "bool",
+                Arc::new(BooleanArray::from(vec![
+                    Some(true),
+                    None,
+                    Some(false),
+                    Some(true),
+                    Some(false),
+                ])),
+            ),
+            (
+                "int8",
+                Arc::new(Int8Array::from(vec![
+                    Some(-1),
+                    Some(2),
+                    None,
+                    Some(4),
+                    Some(5),
+                ])),
+            ),
+            ("int16", Arc::new(Int16Array::from(vec![1, 2, 3, 4, 5]))),
+            (
+                "int64",
+                Arc::new(Int64Array::from(vec![
+                    Some(i64::MIN),
+                    None,
+                    Some(0),
+                    Some(i64::MAX),
+                    None,
+                ])),
+            ),
+            (
+                "float32",
+                Arc::new(Float32Array::from(vec![1.5, -0.0, 3.0, 4.0, 5.0])),
+            ),
+            (
+                "float64",
+                Arc::new(Float64Array::from(vec![
+                    Some(1.5),
+                    None,
+                    Some(f64::INFINITY),
+                    Some(4.0),
+                    Some(-5.0),
+                ])),
+            ),
+            (
+                "string",
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    Some("bc"),
+                    None,
+                    Some(""),
+                    Some("bc"),
+                ])),
+            ),
+            (
+                "binary",
+                Arc::new(BinaryArray::from(vec![
+                    Some(&b"a"[..]),
+                    None,
+                    Some(b""),
+                    Some(b"\x00\xff"),
+                    None,
+                ])),
+            ),
+            (
+                "date",
+                Arc::new(Date32Array::from(vec![
+                    Some(0),
+                    Some(-1),
+                    None,
+                    Some(19_000),
+                    Some(1),
+                ])),
+            ),
+            (
+                "timestamp",
+                Arc::new(TimestampNanosecondArray::from(vec![
+                    Some(1_500_000_000),
+                    Some(-2_000_000_000),
+                    None,
+                    Some(0),
+                    Some(1_700_000_000_123_456_789),
+                ])),
+            ),
+            (
+                "instant",
+                Arc::new(
+                    TimestampNanosecondArray::from(vec![Some(1), None, Some(2), Some(3), Some(4)])
+                        .with_timezone("UTC"),
+                ),
+            ),
+            (
+                "decimal",
+                Arc::new(
+                    Decimal128Array::from(vec![Some(12345), Some(-70), None, Some(0), Some(1)])
+                        .with_precision_and_scale(10, 2)?,
+                ),
+            ),
+            ("list", Arc::new(list)),
+            ("map", Arc::new(map.finish())),
+            ("struct", Arc::new(struct_)),
+            ("union", Arc::new(union)),
+        ]);
+
+        // stripes of 3 rows and row groups of 2, crossing batches
+        let options = WriteOptions {
+            row_index_stride: 2,
+            stripe_row_count: 3,
+            compression: CompressionKind::Zlib,
+            ..Default::default()
+        };
+        let batches = [batch.clone(), batch.slice(1, 3)];
+        let read = round_trip(&batches, Some(batch.schema()), options)?;
+        let rows = read.iter().map(|batch| batch.num_rows());
+        assert_eq!(rows.collect::<Vec<_>>(), vec![3, 3, 2]);
+        let expected = concat_batches(&batch.schema(), &batches)?;
+        assert_eq!(concat_batches(&batch.schema(), &read)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn lossy() -> Result<(), Error> {
+        let strings = vec![Some("a"), None, Some("bc")];
+        let f16 = <Float16Type as ArrowPrimitiveType>::Native::from_f32;
+        let fixed_size_list = FixedSizeListArray::try_new(
+            Arc::new(Field::new("x", DataType::Int8, true)),
+            2,
+            Arc::new(Int8Array::from(vec![1, 2, 3, 4, 5, 6])),
+            Some(NullBuffer::from(vec![true, false, true])),
+        )?;
+        // a sparse union whose type ids are not the positions of its fields
+        let union = UnionArray::try_new(
+            UnionFields::try_new(
+                vec![5, 7],
+                vec![
+                    Field::new("a", DataType::Int32, true),
+                    Field::new("b", DataType::Utf8, true),
+                ],
+            )?,
+            vec![5, 7, 5].into(),
+            None,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec!["a", "b", "c"])),
+            ],
+        )?;
+        let batch = batch(vec![
+            (
+                "uint8",
+                Arc::new(UInt8Array::from(vec![Some(255), None, Some(0)])),
+            ),
+            (
+                "float16",
+                Arc::new(Float16Array::from(vec![
+                    Some(f16(1.5)),
+                    None,
+                    Some(f16(-2.0)),
+                ])),
+            ),
+            ("large", Arc::new(LargeStringArray::from(strings.clone()))),
+            ("view", Arc::new(StringViewArray::from(strings.clone()))),
+            (
+                "dictionary",
+                Arc::new(DictionaryArray::<Int8Type>::from_iter(strings.clone())),
+            ),
+            (
+                "fixed",
+                Arc::new(FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                    vec![Some(b"ab"), None, Some(b"cd")].into_iter(),
+                    2,
+                )?),
+            ),
+            (
+                "seconds",
+                Arc::new(TimestampSecondArray::from(vec![Some(1), None, Some(-1)])),
+            ),
+            (
+                "milliseconds",
+                Arc::new(
+                    TimestampMillisecondArray::from(vec![Some(1), None, Some(-2_500)])
+                        .with_timezone("+01:00"),
+                ),
+            ),
+            (
+                "date",
+                Arc::new(Date64Array::from(vec![Some(86_400_000), None, Some(-1)])),
+            ),
+            (
+                "decimal",
+                Arc::new(
+                    Decimal256Array::from(vec![
+                        Some(i256::from(12345)),
+                        None,
+                        Some(i256::MINUS_ONE),
+                    ])
+                    .with_precision_and_scale(20, 4)?,
+                ),
+            ),
+            ("list", Arc::new(fixed_size_list)),
+            ("union", Arc::new(union)),
+        ]);
+        let read = round_trip(&[batch], None, WriteOptions::default())?;
+        let read = &read[0];
+
+        let expected: Vec<ArrayRef> = vec![
+            Arc::new(Int16Array::from(vec![Some(255), None, Some(0)])),
+            Arc::new(Float32Array::from(vec![Some(1.5), None, Some(-2.0)])),
+            Arc::new(StringArray::from(strings.clone())),
+            Arc::new(StringArray::from(strings.clone())),
+            Arc::new(StringArray::from(strings)),
+            Arc::new(BinaryArray::from(vec![Some(&b"ab"[..]), None, Some(b"cd")])),
+            Arc::new(TimestampNanosecondArray::from(vec![
+                Some(1_000_000_000),
+                None,
+                Some(-1_000_000_000),
+            ])),
+            Arc::new(
+                TimestampNanosecondArray::from(vec![Some(1_000_000), None, Some(-2_500_000_000)])
+                    .with_timezone("UTC"),
+            ),
+            Arc::new(Date32Array::from(vec![Some(1), None, Some(-1)])),
+            Arc::new(
+                Decimal128Array::from(vec![Some(12345), None, Some(-1)])
+                    .with_precision_and_scale(20, 4)?,
+            ),
+        ];
+        for (column, expected) in expected.iter().enumerate() {
+            assert_eq!(read.column(column), expected, "{column}");
+        }
+
+        let list = read.column(10).as_list::<i32>();
+        assert_eq!(list.value_offsets(), &[0, 2, 2, 4]);
+        assert!(list.is_null(1));
+        let values = list.values().as_primitive::<Int8Type>();
+        assert_eq!(values.values(), &[1, 2, 5, 6]);
+
+        let union = read.column(11).as_union();
+        assert_eq!(union.type_ids(), &[0, 1, 0]);
+        assert_eq!(union.child(0).as_primitive::<Int32Type>().values(), &[1, 3]);
+        assert_eq!(union.child(1).as_string::<i32>().value(0), "b");
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() -> Result<(), Error> {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "a",
+            DataType::Timestamp(TimeUnit::Second, None),
+            true,
+        )]));
+        let mut writer = OrcArrowWriter::try_new(vec![], schema, WriteOptions::default())?;
+        let timestamps = TimestampSecondArray::from(vec![i64::MAX]);
+        assert!(writer
+            .write(&batch(vec![("a", Arc::new(timestamps))]))
+            .is_err());
+        let other = batch(vec![("a", Arc::new(Int64Array::from(vec![1])))]);
+        assert!(writer.write(&other).is_err());
+
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::UInt64, true)]));
+        assert!(OrcArrowWriter::try_new(vec![], schema, WriteOptions::default()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+//! Deserialization of ORC files to Rust types with [serde](https://serde.rs)
+//! (requires the `serde` feature).
+//!
+//! [`from_reader`] returns an iterator of rows of the file, each deserialized to a type
+//! implementing [`serde::Deserialize`]. A row is a struct whose fields are the
+//! top-level columns of the file. Columns are deserialized as follows:
+//! * structs to structs (by field name), maps (e.g. `HashMap<String, _>`) or tuples (by position)
+//! * nulls to `None`, so nullable columns are deserialized to `Option`
+//! * lists to sequences (e.g. `Vec`)
+//! * maps to maps (e.g. `HashMap` or `BTreeMap`)
+//! * unions to enums, whose variants are the variants of the union, in order
+//! * strings, chars and varchars to strings, and to unit variants of enums by name
+//! * binaries to bytes (e.g. `serde_bytes::ByteBuf`) or sequences of `u8`
+//! * timestamps to their nanoseconds since the unix epoch (an `i64`)
+//! * dates to their days since the unix epoch (an `i32`)
+//! * decimals to strings (e.g. `"-0.70"`), or to floats (with a loss of precision)
+//!
+//! Each stripe is deserialized to [`Value`]s via [`read_stripe`] before its rows are
+//! deserialized. This is convenient for small files (e.g. configuration or test fixtures);
+//! the `arrow` feature is better suited for large files.
+use std::io::{Read, Seek};
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::Error;
+use crate::read::{read_metadata, FileMetadata};
+
+mod read;
+mod value;
+
+pub use read::read_stripe;
+pub use value::Value;
+
+/// Returns an iterator of the rows of the ORC file in `reader`, deserialized to `T`.
+///
+/// # Example
+/// ```no_run
+/// use std::collections::HashMap;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Row {
+///     id: i64,
+///     name: Option<String>,
+///     tags: Vec<String>,
+///     attributes: HashMap<String, i32>,
+/// }
+///
+/// let file = std::fs::File::open("example.orc")?;
+/// for row in orc_format::de::from_reader::<Row, _>(file)? {
+///     let row = row?;
+///     println!("{}: {:?}", row.id, row.name);
+/// }
+/// # Ok::<(), orc_format::error::Error>(())
+/// ```
+/// # Errors
+/// This function errors when the file's metadata cannot be read.
+pub fn from_reader<T: DeserializeOwned, R: Read + Seek>(
+    mut reader: R,
+) -> Result<RowIter<T, R>, Error> {
+    let metadata = read_metadata(&mut reader)?;
+    Ok(RowIter::new(reader, metadata))
+}
+
+/// An [`Iterator`] of the rows of a file deserialized to `T`, read stripe by stripe.
+///
+/// Errors deserializing a row to `T` do not stop the iterator; errors reading
+/// a stripe do.
+pub struct RowIter<T, R> {
+    reader: R,
+    metadata: FileMetadata,
+    next_stripe: usize,
+    rows: std::vec::IntoIter<Value>,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned, R: Read + Seek> RowIter<T, R> {
+    /// Returns a new [`RowIter`] of the file in `reader` with `metadata`
+    pub fn new(reader: R, metadata: FileMetadata) -> Self {
+        Self {
+            reader,
+            metadata,
+            next_stripe: 0,
+            rows: Vec::new().into_iter(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the metadata of the file
+    pub fn metadata(&self) -> &FileMetadata {
+        &self.metadata
+    }
+
+    /// Returns its internal reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<T: DeserializeOwned, R: Read + Seek> Iterator for RowIter<T, R> {
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.rows.next() {
+                return Some(T::deserialize(row));
+            }
+
+            let stripes = self.metadata.footer.stripes.len();
+            if self.next_stripe >= stripes {
+                return None;
+            }
+            let stripe = self.next_stripe;
+            self.next_stripe += 1;
+            match read_stripe(&mut self.reader, &self.metadata, stripe) {
+                Ok(rows) => self.rows = rows.into_iter(),
+                Err(error) => {
+                    self.next_stripe = stripes;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::Cursor;
+
+    use serde::Deserialize;
+
+    use crate::proto::r#type::Kind as TypeKind;
+    use crate::proto::stream::Kind;
+    use crate::proto::Type;
+    use crate::test_util::{self, bools, bytes, decimal, ints, type_, uints, Streams};
+
+    use super::*;
+
+    fn types() -> Vec<Type> {
+        vec![
+            type_(
+                TypeKind::Struct,
+                vec![1, 2, 3, 5, 8, 9, 12],
+                vec!["id", "name", "tags", "attributes", "price", "kind", "point"],
+            ),
+            type_(TypeKind::Long, vec![], vec![]),
+            type_(TypeKind::String, vec![], vec![]),
+            type_(TypeKind::List, vec![4], vec![]),
+            type_(TypeKind::String, vec![], vec![]),
+            type_(TypeKind::Map, vec![6, 7], vec![]),
+            type_(TypeKind::String, vec![], vec![]),
+            type_(TypeKind::Int, vec![], vec![]),
+            decimal(10, 2),
+            type_(TypeKind::Union, vec![10, 11], vec![]),
+            type_(TypeKind::Int, vec![], vec![]),
+            type_(TypeKind::String, vec![], vec![]),
+            type_(TypeKind::Struct, vec![13, 14], vec!["x", "y"]),
+            type_(TypeKind::Double, vec![], vec![]),
+            type_(TypeKind::Double, vec![], vec![]),
+        ]
+    }
+
+    /// The streams of each column of a stripe of 3 rows
+    fn streams() -> Streams {
+        let present = |values: &[bool]| (Kind::Present, bools(values));
+        let doubles = |values: &[f64]| values.iter().flat_map(|x| x.to_le_bytes()).collect();
+        vec![
+            vec![],
+            // id: [1, 2, 3]
+            vec![(Kind::Data, ints(&[1, 2, 3]))],
+            // name: ["a", null, "c"]
+            vec![
+                present(&[true, false, true]),
+                (Kind::Data, b"ac".to_vec()),
+                (Kind::Length, uints(&[1, 1])),
+            ],
+            // tags: [["x", "y"], [], ["z"]]
+            vec![(Kind::Length, uints(&[2, 0, 1]))],
+            vec![
+                (Kind::Data, b"xyz".to_vec()),
+                (Kind::Length, uints(&[1, 1, 1])),
+            ],
+            // attributes: [{"k": 1}, {}, {"a": 2, "b": 3}]
+            vec![(Kind::Length, uints(&[1, 0, 2]))],
+            vec![
+                (Kind::Data, b"kab".to_vec()),
+                (Kind::Length, uints(&[1, 1, 1])),
+            ],
+            vec![(Kind::Data, ints(&[1, 2, 3]))],
+            // price: [1.20, -0.7, 5] as zigzag varints and their scales
+            vec![
+                (Kind::Data, vec![0xf0, 0x01, 13, 10]),
+                (Kind::Secondary, ints(&[2, 1, 0])),
+            ],
+            // kind: [7, "s", null]
+            vec![present(&[true, true, false]), (Kind::Data, bytes(&[0, 1]))],
+            vec![(Kind::Data, ints(&[7]))],
+            vec![(Kind::Data, b"s".to_vec()), (Kind::Length, uints(&[1]))],
+            // point: [{x: 1, y: 2}, null, {x: 3, y: null}]
+            vec![present(&[true, false, true])],
+            vec![(Kind::Data, doubles(&[1.0, 3.0]))],
+            vec![present(&[true, false]), (Kind::Data, doubles(&[2.0]))],
+        ]
+    }
+
+    fn file(stripes: usize) -> Vec<u8> {
+        test_util::file(types(), &vec![streams(); stripes], 3, &[])
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Kind_ {
+        Number(i32),
+        Text(String),
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Point {
+        x: f64,
+        y: Option<f64>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Row {
+        id: i64,
+        name: Option<String>,
+        tags: Vec<String>,
+        attributes: HashMap<String, i32>,
+        price: String,
+        kind: Option<Kind_>,
+        point: Option<Point>,
+    }
+
+    #[test]
+    fn rows() -> Result<(), Error> {
+        let rows = from_reader::<Row, _>(Cursor::new(file(2)))?.collect::<Result<Vec<_>, _>>()?;
+
+        let expected = vec![
+            Row {
+                id: 1,
+                name: Some("a".to_string()),
+                tags: vec!["x".to_string(), "y".to_string()],
+                attributes: HashMap::from([("k".to_string(), 1)]),
+                price: "1.20".to_string(),
+                kind: Some(Kind_::Number(7)),
+                point: Some(Point {
+                    x: 1.0,
+                    y: Some(2.0),
+                }),
+            },
+            Row {
+                id: 2,
+                name: None,
+                tags: vec![],
+                attributes: HashMap::new(),
+                price: "-0.7".to_string(),
+                kind: Some(Kind_::Text("s".to_string())),
+                point: None,
+            },
+            Row {
+                id: 3,
+                name: Some("c".to_string()),
+                tags: vec!["z".to_string()],
+                attributes: HashMap::from([("a".to_string(), 2), ("b".to_string(), 3)]),
+                price: "5".to_string(),
+                kind: None,
+                point: Some(Point { x: 3.0, y: None }),
+            },
+        ];
+        assert_eq!(rows.len(), 6);
+        assert_eq!(&rows[..3], expected.as_slice());
+        assert_eq!(&rows[3..], expected.as_slice());
+        Ok(())
+    }
+
+    #[test]
+    fn projection_and_conversions() -> Result<(), Error> {
+        // unknown fields are ignored, decimals may be floats and structs may be tuples
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Row {
+            id: u8,
+            price: f64,
+            point: Option<(f32, Option<f32>)>,
+        }
+        let rows = from_reader::<Row, _>(Cursor::new(file(1)))?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            rows[0],
+            Row {
+                id: 1,
+                price: 1.2,
+                point: Some((1.0, Some(2.0))),
+            }
+        );
+        assert_eq!(rows[1].price, -0.7);
+        Ok(())
+    }
+
+    #[test]
+    fn values() -> Result<(), Error> {
+        let file = file(1);
+        let mut reader = Cursor::new(&file);
+        let metadata = read_metadata(&mut reader)?;
+        let rows = read_stripe(&mut reader, &metadata, 0)?;
+
+        let Value::Struct(names, values) = &rows[1] else {
+            panic!()
+        };
+        assert_eq!(names[4], "price");
+        assert_eq!(values[1], Value::Null);
+        assert_eq!(values[4], Value::Decimal(-7, 1));
+        assert_eq!(
+            values[5],
+            Value::Union(1, Box::new(Value::String("s".to_string())))
+        );
+        assert!(read_stripe(&mut reader, &metadata, 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn declared_rows_are_limited() -> Result<(), Error> {
+        // a stripe declaring far more rows than its streams contain
+        let file = test_util::file(types(), &[streams()], 1_000_000_000_000, &[]);
+        let mut reader = Cursor::new(&file);
+        let metadata = read_metadata(&mut reader)?;
+        let error = read_stripe(&mut reader, &metadata, 0).unwrap_err();
+        assert!(matches!(error.unlocated(), Error::LimitExceeded(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_row() -> Result<(), Error> {
+        // rows that cannot be deserialized error without stopping the iterator
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Row {
+            name: String,
+        }
+        let rows = from_reader::<Row, _>(Cursor::new(file(1)))?.collect::<Vec<_>>();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].is_ok());
+        assert!(matches!(rows[1], Err(Error::Deserialize(_))));
+        assert!(rows[2].is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,297 @@
+//! Deserialization of stripes to [`Value`]s.
+use std::io::{Read, Seek};
+use std::sync::Arc;
+
+use crate::error::Error;
+use crate::proto::column_encoding::Kind as ColumnEncodingKind;
+use crate::proto::r#type::Kind as TypeKind;
+use crate::proto::stream::Kind;
+use crate::proto::StripeFooter;
+use crate::read::decode::{
+    decode_bitmap, is_set, BooleanIter, ByteIter, DecimalIter, FloatIter, Values,
+};
+use crate::read::streams::{check_v2, signed, timestamps, unsigned};
+use crate::read::types::{children, get_type, top_level_columns};
+use crate::read::{get_stripe, read_stripe_column, read_stripe_footer, Column, FileMetadata};
+
+use super::Value;
+
+/// Reads the rows of `stripe`, each a [`Value::Struct`] of the top-level columns of the file.
+/// # Errors
+/// This function errors when the file is out of spec.
+pub fn read_stripe<R: Read + Seek>(
+    reader: &mut R,
+    metadata: &FileMetadata,
+    stripe: usize,
+) -> Result<Vec<Value>, Error> {
+    let length = get_stripe(metadata, stripe)?.number_of_rows() as usize;
+    top_level_columns(&metadata.footer.types)?;
+    let footer = read_stripe_footer(reader, metadata, stripe, &mut vec![])?;
+
+    StripeReader {
+        reader,
+        metadata,
+        stripe,
+        footer: Arc::new(footer),
+    }
+    .deserialize(0, length)
+}
+
+/// Reads the columns of a stripe on demand
+struct StripeReader<'a, R> {
+    reader: &'a mut R,
+    metadata: &'a FileMetadata,
+    stripe: usize,
+    footer: Arc<StripeFooter>,
+}
+
+impl<'a, R: Read + Seek> StripeReader<'a, R> {
+    /// Deserializes the `length` rows of `column` (and its children)
+    fn deserialize(&mut self, column: u32, length: usize) -> Result<Vec<Value>, Error> {
+        // the lengths of children are declared by their parents' streams
+        self.metadata.limits().check_values(length as u64)?;
+        let orc = read_stripe_column(
+            self.reader,
+            self.metadata,
+            self.stripe,
+            Arc::clone(&self.footer),
+            column,
+            vec![],
+        )?;
+        let location = orc.location();
+        self.deserialize_column(&orc, column, length)
+            .map_err(|error| error.at(location))
+    }
+
+    fn deserialize_column(
+        &mut self,
+        column: &Column,
+        index: u32,
+        length: usize,
+    ) -> Result<Vec<Value>, Error> {
+        let types = &self.metadata.footer.types;
+        let type_ = get_type(types, index)?;
+        let children = children(types, index)?.to_vec();
+        let child = |index: usize| {
+            children
+                .get(index)
+                .copied()
+                .ok_or(Error::OutOfSpec("a nested type is missing children"))
+        };
+
+        let mut validity = vec![];
+        let non_null = if column.has_stream(Kind::Present) {
+            let nulls = decode_bitmap(
+                column.get_stream(Kind::Present, vec![])?,
+                length,
+                &mut validity,
+            )?;
+            length - nulls
+        } else {
+            validity.resize(length.div_ceil(8), u8::MAX);
+            length
+        };
+
+        // the values of the non-null rows
+        let values = match type_.kind() {
+            TypeKind::Boolean => {
+                let mut values = vec![];
+                BooleanIter::new(column.get_stream(Kind::Data, vec![])?, non_null)
+                    .extend_into(&mut values)?;
+                values.into_iter().map(Value::Boolean).collect()
+            }
+            TypeKind::Byte => {
+                let mut values = vec![];
+                ByteIter::new(column.get_stream(Kind::Data, vec![])?, non_null)
+                    .extend_into(&mut values)?;
+                values.into_iter().map(|x| Value::Byte(x as i8)).collect()
+            }
+            TypeKind::Short => integers(column, non_null, Value::Short)?,
+            TypeKind::Int => integers(column, non_null, Value::Int)?,
+            TypeKind::Long => integers(column, non_null, Value::Long)?,
+            TypeKind::Date => integers(column, non_null, Value::Date)?,
+            TypeKind::Float => {
+                let mut values = vec![];
+                FloatIter::<f32, _>::new(column.get_stream(Kind::Data, vec![])?, non_null)
+                    .extend_into(&mut values)?;
+                values.into_iter().map(Value::Float).collect()
+            }
+            TypeKind::Double => {
+                let mut values = vec![];
+                FloatIter::<f64, _>::new(column.get_stream(Kind::Data, vec![])?, non_null)
+                    .extend_into(&mut values)?;
+                values.into_iter().map(Value::Double).collect()
+            }
+            TypeKind::Timestamp | TypeKind::TimestampInstant => {
+                timestamps(column, type_.kind(), non_null)?
+                    .into_iter()
+                    .map(Value::Timestamp)
+                    .collect()
+            }
+            TypeKind::Decimal => decimals(column, non_null)?,
+            TypeKind::String | TypeKind::Varchar | TypeKind::Char => byte_values(column, non_null)?
+                .into_iter()
+                .map(|value| {
+                    String::from_utf8(value)
+                        .map(Value::String)
+                        .map_err(|_| Error::InvalidUtf8)
+                })
+                .collect::<Result<_, Error>>()?,
+            TypeKind::Binary => byte_values(column, non_null)?
+                .into_iter()
+                .map(Value::Binary)
+                .collect(),
+            TypeKind::List => {
+                let (lengths, total) = lengths(column, non_null)?;
+                let mut items = self.deserialize(child(0)?, total)?.into_iter();
+                lengths
+                    .into_iter()
+                    .map(|length| Value::List(items.by_ref().take(length).collect()))
+                    .collect()
+            }
+            TypeKind::Map => {
+                let (lengths, total) = lengths(column, non_null)?;
+                let keys = self.deserialize(child(0)?, total)?;
+                let values = self.deserialize(child(1)?, total)?;
+                let mut entries = keys.into_iter().zip(values);
+                lengths
+                    .into_iter()
+                    .map(|length| Value::Map(entries.by_ref().take(length).collect()))
+                    .collect()
+            }
+            TypeKind::Struct => {
+                if type_.field_names.len() != children.len() {
+                    return Err(Error::OutOfSpec(
+                        "a struct must have one field name per child",
+                    ));
+                }
+                let names: Arc<[String]> = type_.field_names.clone().into();
+                // children only contain the rows where the struct is valid
+                let mut fields = children
+                    .iter()
+                    .map(|child| Ok(self.deserialize(*child, non_null)?.into_iter()))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                (0..non_null)
+                    .map(|_| {
+                        let values = fields.iter_mut().flat_map(|field| field.next()).collect();
+                        Value::Struct(names.clone(), values)
+                    })
+                    .collect()
+            }
+            TypeKind::Union => {
+                let mut tags = vec![];
+                ByteIter::new(column.get_stream(Kind::Data, vec![])?, non_null)
+                    .extend_into(&mut tags)?;
+                let mut counts = vec![0; children.len()];
+                for tag in &tags {
+                    *counts.get_mut(*tag as usize).ok_or(Error::OutOfSpec(
+                        "a union's tag must be one of its variants",
+                    ))? += 1;
+                }
+                // children only contain the rows with their tag
+                let mut variants = children
+                    .iter()
+                    .zip(counts)
+                    .map(|(child, count)| Ok(self.deserialize(*child, count)?.into_iter()))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                tags.into_iter()
+                    .map(|tag| {
+                        let value = variants[tag as usize].next().unwrap_or(Value::Null);
+                        Value::Union(tag, Box::new(value))
+                    })
+                    .collect()
+            }
+        };
+
+        let mut values: std::vec::IntoIter<Value> = values.into_iter();
+        Ok((0..length)
+            .map(|row| {
+                if is_set(&validity, row) {
+                    values.next().unwrap_or(Value::Null)
+                } else {
+                    Value::Null
+                }
+            })
+            .collect())
+    }
+}
+
+fn integers<T: TryFrom<i64>>(
+    column: &Column,
+    length: usize,
+    value: fn(T) -> Value,
+) -> Result<Vec<Value>, Error> {
+    signed(column, Kind::Data, length)?
+        .into_iter()
+        .map(|x| {
+            T::try_from(x)
+                .map(value)
+                .map_err(|_| Error::OutOfSpec("an integer is out of the range of its type"))
+        })
+        .collect()
+}
+
+fn decimals(column: &Column, length: usize) -> Result<Vec<Value>, Error> {
+    let values = DecimalIter::new(column.get_stream(Kind::Data, vec![])?, length)
+        .collect::<Result<Vec<_>, Error>>()?;
+    let scales = signed(column, Kind::Secondary, length)?;
+    values
+        .into_iter()
+        .zip(scales)
+        .map(|(value, scale)| {
+            u8::try_from(scale)
+                .ok()
+                .filter(|scale| *scale <= 38)
+                .map(|scale| Value::Decimal(value, scale))
+                .ok_or(Error::OutOfSpec(
+                    "the scale of a decimal must be between 0 and 38",
+                ))
+        })
+        .collect()
+}
+
+/// Returns the lengths of a list or map from its "Length" stream, and their sum
+fn lengths(column: &Column, length: usize) -> Result<(Vec<usize>, usize), Error> {
+    let lengths = unsigned(column, Kind::Length, length)?
+        .into_iter()
+        .map(|length| {
+            usize::try_from(length).map_err(|_| Error::OutOfSpec("a length must fit in usize"))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let total = lengths
+        .iter()
+        .try_fold(0usize, |total, length| total.checked_add(*length))
+        .ok_or(Error::OutOfSpec("the lengths of a column overflow"))?;
+    Ok((lengths, total))
+}
+
+/// Returns `length` variable-length values from the streams `kind` (bytes) and "Length"
+fn values(column: &Column, kind: Kind, length: usize) -> Result<Vec<Vec<u8>>, Error> {
+    let (lengths, _) = lengths(column, length)?;
+    let mut values = Values::new(column.get_stream(kind, vec![])?, vec![]);
+    lengths
+        .into_iter()
+        .map(|length| Ok(values.next(length)?.to_vec()))
+        .collect()
+}
+
+/// Returns the values of a string or binary column, resolving its dictionary, if any
+fn byte_values(column: &Column, length: usize) -> Result<Vec<Vec<u8>>, Error> {
+    check_v2(column)?;
+    match column.encoding().kind() {
+        ColumnEncodingKind::DictionaryV2 => {
+            let size = column.dictionary_size().unwrap_or_default();
+            let dictionary = values(column, Kind::DictionaryData, size)?;
+            unsigned(column, Kind::Data, length)?
+                .into_iter()
+                .map(|key| {
+                    dictionary
+                        .get(key as usize)
+                        .cloned()
+                        .ok_or(Error::OutOfSpec("a dictionary key is out of bounds"))
+                })
+                .collect()
+        }
+        _ => values(column, Kind::Data, length),
+    }
+}
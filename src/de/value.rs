@@ -0,0 +1,251 @@
+//! Contains [`Value`] and its implementation of [`serde::Deserializer`].
+use std::sync::Arc;
+
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{
+    DeserializeSeed, Deserializer, EnumAccess, Error as _, IntoDeserializer, Unexpected,
+    VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+
+use crate::error::Error;
+
+/// A dynamically-typed value of a column of an ORC file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A null, of any type
+    Null,
+    Boolean(bool),
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    /// A string, char or varchar
+    String(String),
+    Binary(Vec<u8>),
+    /// A timestamp, in nanoseconds since the unix epoch
+    Timestamp(i64),
+    /// A date, in days since the unix epoch
+    Date(i32),
+    /// A decimal, as its unscaled value and its scale
+    Decimal(i128, u8),
+    List(Vec<Value>),
+    /// A map, as its entries (keys and values) in the order of the file
+    Map(Vec<(Value, Value)>),
+    /// A struct, as the names of its fields (shared by all values of a column) and its values
+    Struct(Arc<[String]>, Vec<Value>),
+    /// A union, as its tag (the index of its variant) and its value
+    Union(u8, Box<Value>),
+}
+
+/// Returns the decimal representation of `value` with `scale`, e.g. `"-0.70"`
+fn decimal_to_string(value: i128, scale: u8) -> String {
+    let scale = scale as usize;
+    let digits = value.unsigned_abs().to_string();
+    let digits = if digits.len() <= scale {
+        format!("{}{digits}", "0".repeat(scale + 1 - digits.len()))
+    } else {
+        digits
+    };
+    let sign = if value < 0 { "-" } else { "" };
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    if fraction.is_empty() {
+        format!("{sign}{integer}")
+    } else {
+        format!("{sign}{integer}.{fraction}")
+    }
+}
+
+impl Value {
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            Value::Null => Unexpected::Unit,
+            Value::Boolean(value) => Unexpected::Bool(*value),
+            Value::Byte(value) => Unexpected::Signed(*value as i64),
+            Value::Short(value) => Unexpected::Signed(*value as i64),
+            Value::Int(value) | Value::Date(value) => Unexpected::Signed(*value as i64),
+            Value::Long(value) | Value::Timestamp(value) => Unexpected::Signed(*value),
+            Value::Float(value) => Unexpected::Float(*value as f64),
+            Value::Double(value) => Unexpected::Float(*value),
+            Value::String(value) => Unexpected::Str(value),
+            Value::Binary(value) => Unexpected::Bytes(value),
+            Value::Decimal(..) => Unexpected::Other("decimal"),
+            Value::List(_) => Unexpected::Seq,
+            Value::Map(_) | Value::Struct(..) => Unexpected::Map,
+            Value::Union(..) => Unexpected::Other("union"),
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Boolean(value) => visitor.visit_bool(value),
+            Value::Byte(value) => visitor.visit_i8(value),
+            Value::Short(value) => visitor.visit_i16(value),
+            Value::Int(value) | Value::Date(value) => visitor.visit_i32(value),
+            Value::Long(value) | Value::Timestamp(value) => visitor.visit_i64(value),
+            Value::Float(value) => visitor.visit_f32(value),
+            Value::Double(value) => visitor.visit_f64(value),
+            Value::String(value) => visitor.visit_string(value),
+            Value::Binary(value) => visitor.visit_byte_buf(value),
+            Value::Decimal(value, scale) => visitor.visit_string(decimal_to_string(value, scale)),
+            Value::List(values) => visitor.visit_seq(SeqDeserializer::new(values.into_iter())),
+            Value::Map(entries) => visitor.visit_map(MapDeserializer::new(entries.into_iter())),
+            Value::Struct(names, values) => {
+                visitor.visit_map(MapDeserializer::new(names.iter().cloned().zip(values)))
+            }
+            Value::Union(_, value) => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            other => visitor.visit_some(other),
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Decimal(value, scale) => {
+                visitor.visit_f64(value as f64 / 10f64.powi(scale as i32))
+            }
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Struct(_, values) => visitor.visit_seq(SeqDeserializer::new(values.into_iter())),
+            Value::Binary(value) => visitor.visit_seq(SeqDeserializer::new(value.into_iter())),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::String(value) => visitor.visit_byte_buf(value.into_bytes()),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Value::Union(tag, value) => visitor.visit_enum(Variant(tag, *value)),
+            Value::String(value) => visitor.visit_enum(value.into_deserializer()),
+            other => Err(Error::invalid_type(other.unexpected(), &"a union")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string
+        unit unit_struct map struct identifier ignored_any
+    }
+}
+
+/// A variant of a union, accessed by its tag
+struct Variant(u8, Value);
+
+impl<'de> EnumAccess<'de> for Variant {
+    type Error = Error;
+    type Variant = Value;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Value), Error> {
+        let tag = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(u32::from(
+            self.0,
+        )))?;
+        Ok((tag, self.1))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Value {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self {
+            Value::Null => Ok(()),
+            other => Err(Error::invalid_type(other.unexpected(), &"a null")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimals() {
+        assert_eq!(decimal_to_string(120, 2), "1.20");
+        assert_eq!(decimal_to_string(-7, 1), "-0.7");
+        assert_eq!(decimal_to_string(5, 0), "5");
+        assert_eq!(decimal_to_string(-5, 3), "-0.005");
+        assert_eq!(
+            decimal_to_string(i128::MIN, 38),
+            format!("-1.{}", &i128::MIN.to_string()[2..])
+        );
+    }
+}
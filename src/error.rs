@@ -1,33 +1,280 @@
 //! Contains [`Error`]
-use crate::proto::stream::Kind;
+use std::sync::Arc;
+
+use crate::proto::{stream::Kind, CompressionKind};
 
 /// Possible errors from this crate.
+///
+/// Errors that happen while reading a stripe are wrapped in [`Error::Located`], which
+/// contains the [`Location`] (stripe, column, stream and offset) where they happened.
+///
+/// Sources that are not [`Clone`] (e.g. [`std::io::Error`]) are shared in an [`Arc`].
 #[derive(Debug, Clone)]
 pub enum Error {
-    /// Generic error returned when the file is out of spec
-    OutOfSpec,
+    /// Generic error returned when the file is out of spec, with a description of what
+    OutOfSpec(&'static str),
     /// When a string column contains a value with invalid UTF8
     InvalidUtf8,
+    /// When the user requests a stripe that does not exist
+    InvalidStripe(usize),
     /// When the user requests a column that does not exist
     InvalidColumn(u32),
     /// When the user requests a type that does not exist for the given column
     InvalidKind(u32, Kind),
     /// When decoding a float fails
     DecodeFloat,
-    /// When decompression fails
-    Decompression,
+    /// When decompression fails, with the error of the codec
+    Decompression(Arc<std::io::Error>),
+    /// When compression fails, with the error of the codec
+    Compression(Arc<std::io::Error>),
+    /// When the file uses a feature of the specification not yet implemented by this crate
+    NotYetImplemented(&'static str),
+    /// When the file is compressed with a compression not supported by this crate
+    UnsupportedCompression(CompressionKind),
     /// When decoding the proto files fail
-    InvalidProto,
+    InvalidProto(prost::DecodeError),
+    /// When reading from the underlying reader fails
+    Io(Arc<std::io::Error>),
+    /// When the file is not an ORC file (its magic is not "ORC")
+    NotOrc,
+    /// When the file's version (major, minor) is not supported by this crate
+    UnsupportedVersion(u32, u32),
+    /// When the file was written by a writer version unknown to this crate
+    UnknownWriterVersion(u32),
+    /// When the file requires more memory than allowed by the reader's limits,
+    /// with a description of which limit
+    LimitExceeded(&'static str),
+    /// When constructing Arrow arrays fails
+    #[cfg(feature = "arrow")]
+    Arrow(Arc<arrow_schema::ArrowError>),
+    /// When deserializing a row to a type with serde fails, with serde's message
+    #[cfg(feature = "serde")]
+    Deserialize(Box<str>),
+    /// An error and the location in the file where it happened
+    Located(Box<(Location, Error)>),
+}
+
+/// The location in the file where an [`Error`] happened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Location {
+    /// The index of the stripe
+    pub stripe: Option<usize>,
+    /// The column
+    pub column: Option<u32>,
+    /// The kind of the stream
+    pub kind: Option<Kind>,
+    /// The offset in bytes from the beginning of the file
+    pub offset: Option<u64>,
+}
+
+impl Location {
+    /// Returns a new location whose unknown fields are taken from `other`
+    fn or(self, other: Location) -> Self {
+        Self {
+            stripe: self.stripe.or(other.stripe),
+            column: self.column.or(other.column),
+            kind: self.kind.or(other.kind),
+            offset: self.offset.or(other.offset),
+        }
+    }
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut separator = "";
+        if let Some(stripe) = self.stripe {
+            write!(f, "stripe {stripe}")?;
+            separator = ", ";
+        }
+        if let Some(column) = self.column {
+            write!(f, "{separator}column {column}")?;
+            separator = ", ";
+        }
+        if let Some(kind) = self.kind {
+            write!(f, "{separator}stream {kind:?}")?;
+            separator = ", ";
+        }
+        if let Some(offset) = self.offset {
+            write!(f, "{separator}offset {offset}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error {
+    /// Returns this error located at `location`. When the error is already located,
+    /// the existing location takes precedence over `location`.
+    pub fn at(self, location: Location) -> Self {
+        match self {
+            Self::Located(located) => {
+                let (existing, error) = *located;
+                Self::Located(Box::new((existing.or(location), error)))
+            }
+            other => Self::Located(Box::new((location, other))),
+        }
+    }
+
+    /// Returns the [`Location`] of this error, if known.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Self::Located(located) => Some(&located.0),
+            _ => None,
+        }
+    }
+
+    /// Returns this error without its [`Location`].
+    pub fn unlocated(&self) -> &Error {
+        match self {
+            Self::Located(located) => &located.1,
+            other => other,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfSpec(reason) => write!(f, "file out of specification: {reason}"),
+            Self::InvalidUtf8 => write!(f, "string value is not valid utf8"),
+            Self::InvalidStripe(stripe) => write!(f, "stripe {stripe} does not exist"),
+            Self::InvalidColumn(column) => write!(f, "column {column} does not exist"),
+            Self::InvalidKind(column, kind) => {
+                write!(f, "column {column} has no stream of kind {kind:?}")
+            }
+            Self::DecodeFloat => write!(f, "could not decode float"),
+            Self::Decompression(error) => write!(f, "could not decompress: {error}"),
+            Self::Compression(error) => write!(f, "could not compress: {error}"),
+            Self::NotYetImplemented(reason) => write!(f, "not yet implemented: {reason}"),
+            Self::UnsupportedCompression(compression) => {
+                write!(f, "compression {compression:?} is not supported")
+            }
+            Self::InvalidProto(error) => write!(f, "could not decode protobuf: {error}"),
+            Self::Io(error) => write!(f, "io error: {error}"),
+            Self::NotOrc => write!(f, "not an ORC file"),
+            Self::UnsupportedVersion(major, minor) => {
+                write!(f, "version {major}.{minor} is not supported")
+            }
+            Self::UnknownWriterVersion(version) => {
+                write!(f, "writer version {version} is unknown")
+            }
+            Self::LimitExceeded(reason) => write!(f, "limit exceeded: {reason}"),
+            #[cfg(feature = "arrow")]
+            Self::Arrow(error) => write!(f, "arrow error: {error}"),
+            #[cfg(feature = "serde")]
+            Self::Deserialize(message) => write!(f, "could not deserialize: {message}"),
+            Self::Located(located) => write!(f, "{} (at {})", located.1, located.0),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decompression(error) => Some(error.as_ref()),
+            Self::Compression(error) => Some(error.as_ref()),
+            Self::InvalidProto(error) => Some(error),
+            Self::Io(error) => Some(error.as_ref()),
+            #[cfg(feature = "arrow")]
+            Self::Arrow(error) => Some(error.as_ref()),
+            Self::Located(located) => located.1.source(),
+            _ => None,
+        }
+    }
 }
 
 impl From<prost::DecodeError> for Error {
-    fn from(_: prost::DecodeError) -> Self {
-        Self::InvalidProto
+    fn from(error: prost::DecodeError) -> Self {
+        Self::InvalidProto(error)
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl From<arrow_schema::ArrowError> for Error {
+    fn from(error: arrow_schema::ArrowError) -> Self {
+        Self::Arrow(Arc::new(error))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::de::Error for Error {
+    fn custom<T: std::fmt::Display>(message: T) -> Self {
+        Self::Deserialize(message.to_string().into())
     }
 }
 
 impl From<std::io::Error> for Error {
-    fn from(_: std::io::Error) -> Self {
-        Self::OutOfSpec
+    /// Converts an [`std::io::Error`] to [`Error`], recovering the original [`Error`]
+    /// when it was raised by a [`std::io::Read`] of this crate.
+    fn from(error: std::io::Error) -> Self {
+        if !error.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            return Self::Io(Arc::new(error));
+        }
+        let kind = error.kind();
+        match error.into_inner().map(|inner| inner.downcast::<Error>()) {
+            Some(Ok(error)) => *error,
+            Some(Err(inner)) => Self::Io(Arc::new(std::io::Error::new(kind, inner))),
+            None => Self::Io(Arc::new(kind.into())),
+        }
+    }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Io(error) => Arc::try_unwrap(error)
+                .unwrap_or_else(|error| std::io::Error::new(error.kind(), Error::Io(error))),
+            other => std::io::Error::new(std::io::ErrorKind::InvalidData, other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size() {
+        // errors are returned on every decoded value; keep them small
+        assert!(std::mem::size_of::<Error>() <= 24);
+    }
+
+    #[test]
+    fn clone() {
+        let error = Error::from(std::io::Error::other("a"));
+        let cloned = error.clone();
+        assert_eq!(cloned.to_string(), error.to_string());
+        // a shared source is wrapped when converted back
+        assert_eq!(std::io::Error::from(cloned).to_string(), "io error: a");
+        assert_eq!(std::io::Error::from(error).to_string(), "a");
+    }
+
+    #[test]
+    fn io_round_trip() {
+        let error = Error::OutOfSpec("a").at(Location {
+            column: Some(1),
+            ..Default::default()
+        });
+        let error: Error = std::io::Error::from(error).into();
+        assert!(matches!(error.unlocated(), Error::OutOfSpec("a")));
+        assert_eq!(error.location().unwrap().column, Some(1));
+    }
+
+    #[test]
+    fn display() {
+        let error = Error::InvalidUtf8
+            .at(Location {
+                column: Some(1),
+                kind: Some(Kind::Data),
+                ..Default::default()
+            })
+            .at(Location {
+                stripe: Some(2),
+                column: Some(3),
+                ..Default::default()
+            });
+        assert_eq!(
+            error.to_string(),
+            "string value is not valid utf8 (at stripe 2, column 1, stream Data)"
+        );
     }
 }
@@ -1,5 +1,15 @@
 #![doc = include_str!("lib.md")]
 #![forbid(unsafe_code)]
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "serde")]
+pub mod de;
 pub mod error;
 pub mod proto;
 pub mod read;
+pub mod version;
+pub mod write;
+
+#[cfg(test)]
+#[allow(dead_code)] // each helper is only used by the tests of some features
+mod test_util;
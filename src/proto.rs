@@ -55,6 +55,11 @@ pub struct TimestampStatistics {
     pub minimum: ::core::option::Option<i64>,
     #[prost(sint64, optional, tag = "2")]
     pub maximum: ::core::option::Option<i64>,
+    /// min,max values saved as milliseconds since UNIX epoch
+    #[prost(sint64, optional, tag = "3")]
+    pub minimum_utc: ::core::option::Option<i64>,
+    #[prost(sint64, optional, tag = "4")]
+    pub maximum_utc: ::core::option::Option<i64>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct BinaryStatistics {
@@ -103,6 +108,8 @@ pub struct BloomFilter {
     pub num_hash_functions: ::core::option::Option<u32>,
     #[prost(fixed64, repeated, packed = "false", tag = "2")]
     pub bitset: ::prost::alloc::vec::Vec<u64>,
+    #[prost(bytes = "vec", optional, tag = "3")]
+    pub utf8bitset: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct BloomFilterIndex {
@@ -133,6 +140,7 @@ pub mod stream {
         Secondary = 5,
         RowIndex = 6,
         BloomFilter = 7,
+        BloomFilterUtf8 = 8,
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -163,6 +171,13 @@ pub struct StripeFooter {
     pub writer_timezone: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StringPair {
+    #[prost(string, optional, tag = "1")]
+    pub key: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "2")]
+    pub value: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Type {
     #[prost(enumeration = "r#type::Kind", optional, tag = "1")]
     pub kind: ::core::option::Option<i32>,
@@ -176,6 +191,8 @@ pub struct Type {
     pub precision: ::core::option::Option<u32>,
     #[prost(uint32, optional, tag = "6")]
     pub scale: ::core::option::Option<u32>,
+    #[prost(message, repeated, tag = "7")]
+    pub attributes: ::prost::alloc::vec::Vec<StringPair>,
 }
 /// Nested message and enum types in `Type`.
 pub mod r#type {
@@ -200,6 +217,7 @@ pub mod r#type {
         Date = 15,
         Varchar = 16,
         Char = 17,
+        TimestampInstant = 18,
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -1,57 +1,110 @@
+use std::sync::Arc;
+
 use crate::{
-    error::Error,
+    error::{Error, Location},
     proto::{stream::Kind, ColumnEncoding, CompressionKind, StripeFooter},
 };
 
-use super::decompress::Decompressor;
+use super::{decompress::Decompressor, is_index, FileMetadata};
 
 /// Helper struct used to access the streams associated to an ORC column.
 /// Its main use [`Column::get_stream`], to get a stream.
+///
+/// The streams are stored in `B`, which is either an owned buffer ([`Vec<u8>`], the default)
+/// or a region borrowed from somewhere else (e.g. `&[u8]` of a file already in memory or
+/// memory-mapped), in which case no copy of the column's streams is performed.
+///
+/// The [`StripeFooter`] is shared by the columns of a stripe, so that reading each column
+/// does not copy it.
 #[derive(Debug)]
-pub struct Column {
-    data: Vec<u8>,
+pub struct Column<B: AsRef<[u8]> = Vec<u8>> {
+    data: B,
+    stripe: usize,
+    offset: u64,
     column: u32,
     number_of_rows: u64,
-    footer: StripeFooter,
+    footer: Arc<StripeFooter>,
     compression: CompressionKind,
+    max_chunk_size: usize,
 }
 
-impl Column {
+impl<B: AsRef<[u8]>> Column<B> {
     pub(crate) fn new(
-        data: Vec<u8>,
+        data: B,
+        metadata: &FileMetadata,
+        stripe: usize,
+        offset: u64,
         column: u32,
         number_of_rows: u64,
-        footer: StripeFooter,
-        compression: CompressionKind,
+        footer: Arc<StripeFooter>,
     ) -> Self {
         Self {
             data,
+            stripe,
+            offset,
             column,
             number_of_rows,
             footer,
-            compression,
+            compression: metadata.postscript.compression(),
+            max_chunk_size: metadata.max_chunk_size(),
         }
     }
 
     /// Returns the stream `kind` associated to this column as a [`Decompressor`].
     /// `scratch` becomes owned by [`Decompressor`], which you can recover via `into_inner`.
-    pub fn get_stream(&self, kind: Kind, scratch: Vec<u8>) -> Result<Decompressor, Error> {
+    ///
+    /// Errors raised by the [`Decompressor`] are located at this column's stripe, column,
+    /// stream and the offset of the chunk in the file.
+    pub fn get_stream(&self, kind: Kind, scratch: Vec<u8>) -> Result<Decompressor<'_>, Error> {
+        self.stream(self.data.as_ref(), kind, scratch)
+    }
+
+    /// Returns the stream `kind` of `data`, the memory region of this column
+    fn stream<'a>(
+        &self,
+        data: &'a [u8],
+        kind: Kind,
+        scratch: Vec<u8>,
+    ) -> Result<Decompressor<'a>, Error> {
         let mut start = 0; // the start of the stream
         self.footer
             .streams
             .iter()
-            .filter(|stream| stream.column() == self.column && stream.kind() != Kind::RowIndex)
-            .map(|stream| {
-                start += stream.length() as usize;
-                stream
-            })
+            .filter(|stream| stream.column() == self.column && !is_index(stream.kind()))
+            .inspect(|stream| start += stream.length() as usize)
             .find(|stream| stream.kind() == kind)
             .map(|stream| {
                 let length = stream.length() as usize;
-                let data = &self.data[start - length..start];
-                Decompressor::new(data, self.compression, scratch)
+                Decompressor::new(&data[start - length..start], self.compression, scratch)
+                    .with_max_chunk_size(self.max_chunk_size)
+                    .located(Location {
+                        stripe: Some(self.stripe),
+                        column: Some(self.column),
+                        kind: Some(kind),
+                        offset: Some(self.offset + (start - length) as u64),
+                    })
             })
-            .ok_or(Error::InvalidKind(self.column, kind))
+            .ok_or_else(|| Error::InvalidKind(self.column, kind).at(self.location()))
+    }
+
+    /// Returns whether this column has a stream `kind`, e.g. whether it has a "Present" stream
+    /// (which writers omit when a column has no nulls).
+    pub fn has_stream(&self, kind: Kind) -> bool {
+        self.footer
+            .streams
+            .iter()
+            .any(|stream| stream.column() == self.column && stream.kind() == kind)
+    }
+
+    /// Returns the [`Location`] of this column in the file, which can be attached to errors
+    /// raised while decoding it via [`Error::at`].
+    pub fn location(&self) -> Location {
+        Location {
+            stripe: Some(self.stripe),
+            column: Some(self.column),
+            kind: None,
+            offset: Some(self.offset),
+        }
     }
 
     /// Returns the encoding of the column
@@ -66,14 +119,35 @@ impl Column {
             .map(|x| x as usize)
     }
 
+    /// Returns the timezone of the writer of this column's stripe, if declared.
+    /// Timestamps (without timezone) are stored relative to 2015-01-01 in this timezone.
+    pub fn writer_timezone(&self) -> Option<&str> {
+        self.footer.writer_timezone.as_deref()
+    }
+
     /// The number of rows on this column
     pub fn number_of_rows(&self) -> usize {
         self.number_of_rows as usize
     }
 
-    /// Returns the underlying footer and the pre-allocated memory region
+    /// Returns the underlying (shared) footer and the memory region
     /// containing all (compressed) streams of this column.
-    pub fn into_inner(self) -> (StripeFooter, Vec<u8>) {
+    pub fn into_inner(self) -> (Arc<StripeFooter>, B) {
         (self.footer, self.data)
     }
 }
+
+impl<'a> Column<&'a [u8]> {
+    /// Returns the stream `kind` associated to this column as a [`Decompressor`] borrowing
+    /// from the region this column was sliced from, rather than from the column.
+    ///
+    /// The chunks of uncompressed files and chunks stored as original are returned by
+    /// [`Decompressor::next_chunk`] as slices of that region, without any copy.
+    pub fn get_stream_slice(
+        &self,
+        kind: Kind,
+        scratch: Vec<u8>,
+    ) -> Result<Decompressor<'a>, Error> {
+        self.stream(self.data, kind, scratch)
+    }
+}
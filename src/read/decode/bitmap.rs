@@ -0,0 +1,147 @@
+use std::io::Read;
+
+use crate::error::Error;
+
+use super::{BooleanRleRunIter, BooleanRun};
+
+/// Returns whether the bit `index` of the (LSB-ordered) `bitmap` is set.
+/// # Panics
+/// This function panics if `index` is out of bounds.
+#[inline]
+pub fn is_set(bitmap: &[u8], index: usize) -> bool {
+    bitmap[index / 8] & (1 << (index % 8)) != 0
+}
+
+/// Decodes `length` booleans (e.g. the Present stream) from `reader` into `bitmap`, packed with
+/// least significant bit ordering, as used by Apache Arrow. Returns the number of unset bits,
+/// which, for the Present stream, is the number of nulls.
+///
+/// `bitmap` is cleared and ends with `length.div_ceil(8)` bytes whose bits after `length` are unset.
+/// Contrarily to [`super::BooleanIter`], this decodes whole bytes at a time.
+pub fn decode_bitmap<R: Read>(
+    reader: R,
+    length: usize,
+    bitmap: &mut Vec<u8>,
+) -> Result<usize, Error> {
+    let bytes = length.div_ceil(8);
+    // `bitmap` grows with the decoded runs, since `length` is declared by the file
+    bitmap.clear();
+
+    // ORC packs booleans with the most significant bit first
+    let mut runs = BooleanRleRunIter::new(reader);
+    while bitmap.len() < bytes {
+        let remaining = bytes - bitmap.len();
+        match runs.next().transpose()? {
+            Some(BooleanRun::Run(value, run_length)) => {
+                let run_length = (run_length as usize).min(remaining);
+                bitmap.resize(bitmap.len() + run_length, value.reverse_bits());
+            }
+            Some(BooleanRun::Literals(values, run_length)) => {
+                let run_length = (run_length as usize).min(remaining);
+                bitmap.extend(values[..run_length].iter().map(|x| x.reverse_bits()));
+            }
+            None => return Err(Error::OutOfSpec("a boolean stream ended early")),
+        }
+    }
+
+    if !length.is_multiple_of(8) {
+        if let Some(last) = bitmap.last_mut() {
+            *last &= (1u8 << (length % 8)) - 1;
+        }
+    }
+    let set = bitmap
+        .iter()
+        .map(|x| x.count_ones() as usize)
+        .sum::<usize>();
+    Ok(length - set)
+}
+
+/// Scatters the dense (non-null) `values` into `output` according to `bitmap` of `length` bits
+/// (as returned by [`decode_bitmap`]), extending `output` by `length` items where unset bits
+/// are [`Default::default`].
+/// # Errors
+/// This function errors if the number of `values` differs from the number of set bits.
+pub fn scatter<T: Copy + Default>(
+    values: &[T],
+    bitmap: &[u8],
+    length: usize,
+    output: &mut Vec<T>,
+) -> Result<(), Error> {
+    if bitmap.len() * 8 < length {
+        return Err(Error::OutOfSpec("the bitmap is smaller than its length"));
+    }
+    let start = output.len();
+    output.reserve(length);
+    let mut values_iter = values.iter();
+    for index in 0..length {
+        if is_set(bitmap, index) {
+            match values_iter.next() {
+                Some(value) => output.push(*value),
+                None => {
+                    output.truncate(start);
+                    return Err(Error::OutOfSpec(
+                        "there are less values than set bits in the bitmap",
+                    ));
+                }
+            }
+        } else {
+            output.push(T::default());
+        }
+    }
+    if values_iter.next().is_some() {
+        output.truncate(start);
+        return Err(Error::OutOfSpec(
+            "there are more values than set bits in the bitmap",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read::decode::BooleanIter;
+
+    #[test]
+    fn decode() {
+        // a run of 3 bytes, 1 literal byte and a run of 3 bytes
+        let data = [0x00, 0xff, 0xff, 0b10101010, 0x00, 0x00];
+
+        for length in [1, 7, 8, 20, 32, 55, 56] {
+            let mut bitmap = vec![1, 2, 3];
+            let nulls = decode_bitmap(data.as_ref(), length, &mut bitmap).unwrap();
+
+            let expected = BooleanIter::new(data.as_ref(), length)
+                .collect::<Result<Vec<_>, Error>>()
+                .unwrap();
+            assert_eq!(bitmap.len(), length.div_ceil(8));
+            assert_eq!(nulls, expected.iter().filter(|x| !**x).count());
+            for (index, expected) in expected.into_iter().enumerate() {
+                assert_eq!(is_set(&bitmap, index), expected);
+            }
+            // bits after the length are unset
+            for index in length..bitmap.len() * 8 {
+                assert!(!is_set(&bitmap, index));
+            }
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        let data = [0x00, 0xff];
+        assert!(decode_bitmap(data.as_ref(), 32, &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn scatter_values() {
+        let bitmap = [0b00001101];
+        let mut output = vec![9];
+        scatter(&[1, 2, 3], &bitmap, 5, &mut output).unwrap();
+        assert_eq!(output, vec![9, 1, 0, 2, 3, 0]);
+
+        assert!(scatter(&[1, 2], &bitmap, 5, &mut output).is_err());
+        assert!(scatter(&[1, 2, 3, 4], &bitmap, 5, &mut output).is_err());
+        assert!(scatter(&[1, 2, 3], &bitmap, 9, &mut output).is_err());
+        assert_eq!(output.len(), 6);
+    }
+}
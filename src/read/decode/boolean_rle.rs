@@ -2,13 +2,15 @@ use std::io::Read;
 
 use crate::error::Error;
 
-use super::read_u8;
+use super::{extend_with, read_u8};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 #[allow(clippy::large_enum_variant)]
 pub enum BooleanRun {
+    /// A byte repeated a number of times
     Run(u8, u16),
-    Literals([u8; 255]),
+    /// A sequence of bytes, of which only the first `.1` are valid
+    Literals([u8; 128], u8),
 }
 
 pub struct BooleanRleRunIter<R: Read> {
@@ -25,16 +27,14 @@ impl<R: Read> BooleanRleRunIter<R> {
     }
 }
 
-fn read_literals<R: Read>(reader: &mut R, header: i8) -> Result<[u8; 255], Error> {
-    let length = (-header) as usize;
+fn read_literals<R: Read>(reader: &mut R, header: i8) -> Result<BooleanRun, Error> {
+    let length = header.unsigned_abs();
 
-    let mut literals = [0u8; 255];
+    let mut literals = [0u8; 128];
 
-    reader
-        .take(length as u64)
-        .read_exact(&mut literals[..length])?;
+    reader.read_exact(&mut literals[..length as usize])?;
 
-    Ok(literals)
+    Ok(BooleanRun::Literals(literals, length))
 }
 
 impl<R: Read> Iterator for BooleanRleRunIter<R> {
@@ -48,7 +48,7 @@ impl<R: Read> Iterator for BooleanRleRunIter<R> {
             Err(e) => return Some(Err(e.into())),
         };
         if header < 0 {
-            Some(read_literals(&mut self.reader, header).map(BooleanRun::Literals))
+            Some(read_literals(&mut self.reader, header))
         } else {
             let length = header as u16 + 3;
             // this is not ok - it may require more than one byte
@@ -70,7 +70,7 @@ pub struct BooleanIter<R: Read> {
     remaining: usize,
 }
 
-impl<'a, R: Read> BooleanIter<R> {
+impl<R: Read> BooleanIter<R> {
     pub fn new(reader: R, length: usize) -> Self {
         Self {
             iter: BooleanRleRunIter::new(reader),
@@ -86,70 +86,104 @@ impl<'a, R: Read> BooleanIter<R> {
     }
 }
 
-impl<R: Read> Iterator for BooleanIter<R> {
-    type Item = Result<bool, Error>;
-
+impl<R: Read> BooleanIter<R> {
+    /// Returns the current byte and the number of bytes of the current run,
+    /// reading the next run if needed.
     #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        if let Some(run) = &self.current {
-            match run {
-                BooleanRun::Run(value, repetitions) => {
-                    let repetitions = *repetitions;
-                    let mask = 128u8 >> self.position;
-                    let result = value & mask == mask;
-                    self.position += 1;
-                    if self.remaining == 0 {
-                        self.current = None;
-                        return None;
-                    } else {
-                        self.remaining -= 1;
-                    }
-                    if self.position == 8 {
-                        if repetitions == 0 {
-                            self.current = None;
-                        } else {
-                            self.current = Some(BooleanRun::Run(*value, repetitions - 1));
-                        }
+    fn current(&mut self) -> Result<(u8, usize), Error> {
+        loop {
+            match &self.current {
+                Some(BooleanRun::Run(value, length)) => return Ok((*value, *length as usize)),
+                Some(BooleanRun::Literals(bytes, length)) => {
+                    return Ok((bytes[self.byte_position], *length as usize))
+                }
+                None => match self.iter.next() {
+                    Some(Ok(run)) => {
+                        self.current = Some(run);
                         self.position = 0;
+                        self.byte_position = 0;
                     }
-                    Some(Ok(result))
-                }
-                BooleanRun::Literals(bytes) => {
-                    let mask = 128u8 >> self.position;
-                    let result = bytes[self.byte_position] & mask == mask;
-                    self.position += 1;
-                    if self.remaining == 0 {
-                        self.current = None;
-                        return None;
-                    } else {
-                        self.remaining -= 1;
+                    Some(Err(e)) => {
+                        self.remaining = 0;
+                        return Err(e);
                     }
-                    if self.position == 8 {
-                        if bytes.len() == 1 {
-                            self.current = None;
-                            self.byte_position = 0;
-                        } else {
-                            self.byte_position += 1;
-                        }
-                        self.position = 0;
+                    None => {
+                        self.remaining = 0;
+                        return Err(Error::OutOfSpec("a boolean stream ended early"));
                     }
-                    Some(Ok(result))
-                }
+                },
             }
-        } else if self.remaining > 0 {
-            match self.iter.next()? {
-                Ok(run) => {
-                    self.current = Some(run);
-                    self.next()
-                }
-                Err(e) => {
-                    self.remaining = 0;
-                    Some(Err(e))
+        }
+    }
+
+    /// Advances `bits` bits within the current run, which must be available
+    #[inline]
+    fn advance(&mut self, bits: usize, run_length: usize) {
+        let position = self.position as usize + bits;
+        self.remaining -= bits;
+        self.position = (position % 8) as u8;
+        self.byte_position += position / 8;
+        if self.byte_position == run_length {
+            self.current = None;
+        }
+    }
+
+    /// Decodes the next values into `values`, returning the number of values decoded,
+    /// which is smaller than `values.len()` only when the iterator is exhausted.
+    ///
+    /// This is equivalent to, but faster than, calling `next` for every item of `values`.
+    pub fn decode_into(&mut self, values: &mut [bool]) -> Result<usize, Error> {
+        let length = values.len().min(self.remaining);
+        let mut written = 0;
+        while written < length {
+            let (byte, run_length) = self.current()?;
+            let is_run = matches!(self.current, Some(BooleanRun::Run(..)));
+            let available = if is_run && (byte == 0 || byte == u8::MAX) {
+                // all bits of the run are equal
+                (run_length - self.byte_position) * 8 - self.position as usize
+            } else {
+                8 - self.position as usize
+            };
+            let additional = available.min(length - written);
+
+            let values = &mut values[written..written + additional];
+            if is_run && (byte == 0 || byte == u8::MAX) {
+                values.fill(byte == u8::MAX);
+            } else {
+                let byte = byte << self.position;
+                for (i, value) in values.iter_mut().enumerate() {
+                    *value = byte & (128u8 >> i) != 0;
                 }
             }
-        } else {
-            None
+            written += additional;
+            self.advance(additional, run_length);
+        }
+        Ok(written)
+    }
+
+    /// Extends `values` with all remaining values of this iterator.
+    pub fn extend_into(&mut self, values: &mut Vec<bool>) -> Result<(), Error> {
+        let remaining = self.remaining;
+        extend_with(values, remaining, |values| self.decode_into(values))
+    }
+}
+
+impl<R: Read> Iterator for BooleanIter<R> {
+    type Item = Result<bool, Error>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
         }
+        let (byte, run_length) = match self.current() {
+            Ok(current) => current,
+            Err(e) => return Some(Err(e)),
+        };
+        let mask = 128u8 >> self.position;
+        let result = byte & mask == mask;
+        self.advance(1, run_length);
+        Some(Ok(result))
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -206,4 +240,66 @@ mod test {
             vec![true, false, false, false, false, false, false, false,]
         )
     }
+
+    #[test]
+    fn runs_and_literals() {
+        // a run of 3 bytes, 1 literal byte and a run of 3 bytes
+        let data = [0x00, 0xff, 0xff, 0b10101010, 0x00, 0x00];
+
+        let data = &mut data.as_ref();
+
+        let iter = BooleanIter::new(data, 56)
+            .collect::<Result<Vec<_>, Error>>()
+            .unwrap();
+        let mut expected = vec![true; 24];
+        expected.extend([true, false].repeat(4));
+        expected.extend([false; 24]);
+        assert_eq!(iter, expected)
+    }
+
+    #[test]
+    fn decode_into() {
+        // a run of 3 bytes, 1 literal byte, a run of 3 bytes and 2 literal bytes
+        let data = [
+            0x00, 0xff, 0xff, 0b10101010, 0x00, 0x00, 0xfe, 0b11001100, 0b00001111,
+        ];
+
+        let expected = BooleanIter::new(data.as_ref(), 72)
+            .collect::<Result<Vec<_>, Error>>()
+            .unwrap();
+
+        // in batches of different sizes, not aligned with the runs
+        for batch in [1, 3, 8, 13, 72, 100] {
+            let mut iter = BooleanIter::new(data.as_ref(), 72);
+            let mut result = vec![];
+            let mut values = vec![false; batch];
+            loop {
+                let written = iter.decode_into(&mut values).unwrap();
+                result.extend_from_slice(&values[..written]);
+                if written < batch {
+                    break;
+                }
+            }
+            assert_eq!(result, expected);
+        }
+
+        // mixed with `next`
+        let mut iter = BooleanIter::new(data.as_ref(), 72);
+        let mut result = vec![iter.next().unwrap().unwrap()];
+        iter.extend_into(&mut result).unwrap();
+        assert_eq!(result, expected);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn truncated() {
+        // 2 literals declared but only 1 present
+        let data = [0xfeu8, 0b01000100];
+
+        let data = &mut data.as_ref();
+
+        assert!(BooleanIter::new(data, 16)
+            .collect::<Result<Vec<_>, Error>>()
+            .is_err());
+    }
 }
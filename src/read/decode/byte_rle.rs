@@ -0,0 +1,173 @@
+use std::io::Read;
+
+use crate::error::Error;
+
+use super::{extend_with, BooleanRleRunIter, BooleanRun};
+
+/// An iterator of bytes encoded with byte run-length encoding, used e.g. by columns of type
+/// `byte` and by the tags of unions.
+///
+/// Byte and boolean run-length encodings share their runs; this iterator
+/// yields each byte of the runs instead of each bit.
+pub struct ByteIter<R: Read> {
+    iter: BooleanRleRunIter<R>,
+    current: Option<BooleanRun>,
+    position: usize,
+    remaining: usize,
+}
+
+impl<R: Read> ByteIter<R> {
+    /// Returns a new [`ByteIter`] of `length` bytes
+    pub fn new(reader: R, length: usize) -> Self {
+        Self {
+            iter: BooleanRleRunIter::new(reader),
+            current: None,
+            position: 0,
+            remaining: length,
+        }
+    }
+
+    /// The number of items remaining
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.remaining
+    }
+
+    /// Whether the iterator is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns its internal reader
+    pub fn into_inner(self) -> R {
+        self.iter.into_inner()
+    }
+
+    /// Returns the length of the current run, reading the next run if needed.
+    #[inline]
+    fn run_length(&mut self) -> Result<usize, Error> {
+        loop {
+            match &self.current {
+                Some(BooleanRun::Run(_, length)) => return Ok(*length as usize),
+                Some(BooleanRun::Literals(_, length)) => return Ok(*length as usize),
+                None => match self.iter.next() {
+                    Some(Ok(run)) => {
+                        self.current = Some(run);
+                        self.position = 0;
+                    }
+                    Some(Err(e)) => {
+                        self.remaining = 0;
+                        return Err(e);
+                    }
+                    None => {
+                        self.remaining = 0;
+                        return Err(Error::OutOfSpec("a byte stream ended early"));
+                    }
+                },
+            }
+        }
+    }
+
+    /// Decodes the next values into `values`, returning the number of values decoded,
+    /// which is smaller than `values.len()` only when the iterator is exhausted.
+    ///
+    /// This is equivalent to, but faster than, calling `next` for every item of `values`.
+    pub fn decode_into(&mut self, values: &mut [u8]) -> Result<usize, Error> {
+        let length = values.len().min(self.remaining);
+        let mut written = 0;
+        while written < length {
+            let run_length = self.run_length()?;
+            let additional = (run_length - self.position).min(length - written);
+            let values = &mut values[written..written + additional];
+            match &self.current {
+                Some(BooleanRun::Run(value, _)) => values.fill(*value),
+                Some(BooleanRun::Literals(bytes, _)) => {
+                    values.copy_from_slice(&bytes[self.position..self.position + additional])
+                }
+                None => unreachable!(),
+            }
+            written += additional;
+            self.remaining -= additional;
+            self.position += additional;
+            if self.position == run_length {
+                self.current = None;
+            }
+        }
+        Ok(written)
+    }
+
+    /// Extends `values` with all remaining values of this iterator.
+    pub fn extend_into(&mut self, values: &mut Vec<u8>) -> Result<(), Error> {
+        let remaining = self.remaining;
+        extend_with(values, remaining, |values| self.decode_into(values))
+    }
+}
+
+impl<R: Read> Iterator for ByteIter<R> {
+    type Item = Result<u8, Error>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let mut value = [0];
+        match self.decode_into(&mut value) {
+            Ok(_) => Some(Ok(value[0])),
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic() {
+        // see https://orc.apache.org/specification/ORCv1/ (Byte Run Length Encoding)
+        let data = [0x61u8, 0x00, 0xfe, 0x44, 0x45];
+        let expected = [vec![0u8; 100], vec![0x44, 0x45]].concat();
+
+        let values = ByteIter::new(data.as_ref(), 102)
+            .collect::<Result<Vec<_>, Error>>()
+            .unwrap();
+        assert_eq!(values, expected);
+
+        let mut values = vec![];
+        ByteIter::new(data.as_ref(), 102)
+            .extend_into(&mut values)
+            .unwrap();
+        assert_eq!(values, expected);
+
+        // partial reads across runs
+        let mut iter = ByteIter::new(data.as_ref(), 102);
+        let mut values = vec![1; 99];
+        assert_eq!(iter.decode_into(&mut values).unwrap(), 99);
+        let mut values = vec![1; 10];
+        assert_eq!(iter.decode_into(&mut values).unwrap(), 3);
+        assert_eq!(&values[..3], &[0, 0x44, 0x45]);
+    }
+
+    #[test]
+    fn truncated() {
+        let data = [0x61u8, 0x00];
+        let mut values = vec![];
+        assert!(ByteIter::new(data.as_ref(), 102)
+            .extend_into(&mut values)
+            .is_err());
+        assert!(values.is_empty());
+
+        // a declared length much larger than the stream errors without allocating it
+        assert!(ByteIter::new(data.as_ref(), usize::MAX)
+            .extend_into(&mut values)
+            .is_err());
+        assert!(values.is_empty());
+    }
+}
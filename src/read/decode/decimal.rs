@@ -0,0 +1,180 @@
+use std::io::Read;
+
+use crate::error::Error;
+
+use super::{extend_with, read_u8};
+
+/// Reads an unbounded (zigzag-encoded) varint of at most 128 bits
+fn signed_varint_i128<R: Read>(reader: &mut R) -> Result<i128, Error> {
+    let mut value = 0u128;
+    let mut shift = 0;
+    loop {
+        let byte = read_u8(reader)?;
+        let bits = u128::from(byte & 0x7F);
+        if shift >= 128 || (shift > 0 && bits >> (128 - shift) != 0) {
+            return Err(Error::OutOfSpec("a decimal must fit in 128 bits"));
+        }
+        value |= bits << shift;
+        if byte >> 7 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Ok(((value >> 1) as i128) ^ -((value & 1) as i128))
+}
+
+/// An iterator of the (unscaled) values of the data stream of a decimal column,
+/// encoded as signed varints of arbitrary length. The scale of each value is stored
+/// separately, on the secondary stream.
+pub struct DecimalIter<R: Read> {
+    reader: R,
+    remaining: usize,
+}
+
+impl<R: Read> DecimalIter<R> {
+    /// Returns a new [`DecimalIter`] of `length` values
+    pub fn new(reader: R, length: usize) -> Self {
+        Self {
+            reader,
+            remaining: length,
+        }
+    }
+
+    /// The number of items remaining
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.remaining
+    }
+
+    /// Whether the iterator is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns its internal reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Decodes the next values into `values`, returning the number of values decoded,
+    /// which is smaller than `values.len()` only when the iterator is exhausted.
+    ///
+    /// This is equivalent to, but faster than, calling `next` for every item of `values`.
+    pub fn decode_into(&mut self, values: &mut [i128]) -> Result<usize, Error> {
+        let length = values.len().min(self.remaining);
+        for value in &mut values[..length] {
+            *value = signed_varint_i128(&mut self.reader).inspect_err(|_| self.remaining = 0)?;
+            self.remaining -= 1;
+        }
+        Ok(length)
+    }
+
+    /// Extends `values` with all remaining values of this iterator.
+    pub fn extend_into(&mut self, values: &mut Vec<i128>) -> Result<(), Error> {
+        let remaining = self.remaining;
+        extend_with(values, remaining, |values| self.decode_into(values))
+    }
+}
+
+impl<R: Read> Iterator for DecimalIter<R> {
+    type Item = Result<i128, Error>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let value = signed_varint_i128(&mut self.reader);
+        self.remaining = if value.is_ok() { self.remaining - 1 } else { 0 };
+        Some(value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(value: i128) -> Vec<u8> {
+        let mut value = ((value << 1) ^ (value >> 127)) as u128;
+        let mut bytes = vec![];
+        loop {
+            if value < 0x80 {
+                bytes.push(value as u8);
+                return bytes;
+            }
+            bytes.push((value as u8 & 0x7F) | 0x80);
+            value >>= 7;
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let values = [
+            0,
+            1,
+            -1,
+            63,
+            -64,
+            64,
+            i64::MAX as i128,
+            i128::MAX,
+            i128::MIN,
+        ];
+        let data = values.iter().flat_map(|x| encode(*x)).collect::<Vec<_>>();
+
+        let result = DecimalIter::new(data.as_slice(), values.len())
+            .collect::<Result<Vec<_>, Error>>()
+            .unwrap();
+        assert_eq!(result, values);
+    }
+
+    #[test]
+    fn decode_into() {
+        let expected = (-1000..1000).map(|x| x * 1_000_003).collect::<Vec<i128>>();
+        let data = expected.iter().flat_map(|x| encode(*x)).collect::<Vec<_>>();
+
+        let mut iter = DecimalIter::new(data.as_slice(), expected.len());
+        let mut values = vec![0; 3];
+        assert_eq!(iter.decode_into(&mut values).unwrap(), 3);
+        assert_eq!(values, &expected[..3]);
+        assert_eq!(iter.next().unwrap().unwrap(), expected[3]);
+
+        let mut values = vec![];
+        iter.extend_into(&mut values).unwrap();
+        assert_eq!(values, &expected[4..]);
+        assert!(iter.is_empty());
+
+        // truncated
+        let mut iter = DecimalIter::new(&data[..5], 3);
+        assert!(iter.extend_into(&mut vec![]).is_err());
+
+        // a declared length much larger than the stream errors without allocating it
+        let mut iter = DecimalIter::new(data.as_slice(), usize::MAX);
+        let mut values = vec![];
+        assert!(iter.extend_into(&mut values).is_err());
+        assert!(values.is_empty());
+    }
+
+    #[test]
+    fn too_large() {
+        // 19 bytes of 7 bits set
+        let mut data = vec![0xff; 18];
+        data.push(0x7f);
+        assert!(DecimalIter::new(data.as_slice(), 1)
+            .next()
+            .unwrap()
+            .is_err());
+
+        let data = [0xffu8; 30];
+        assert!(DecimalIter::new(data.as_slice(), 1)
+            .next()
+            .unwrap()
+            .is_err());
+    }
+}
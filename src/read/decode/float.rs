@@ -1,9 +1,12 @@
 use crate::error::Error;
 
+use super::extend_with;
+
 /// Sealead trait to generically represent f32 and f64.
 pub trait Float: Default + Copy + private::Sealed {
     type Bytes: AsRef<[u8]> + AsMut<[u8]> + Default;
     fn from_le_bytes(bytes: Self::Bytes) -> Self;
+    fn to_le_bytes(self) -> Self::Bytes;
 }
 
 mod private {
@@ -19,6 +22,11 @@ impl Float for f32 {
     fn from_le_bytes(bytes: Self::Bytes) -> Self {
         Self::from_le_bytes(bytes)
     }
+
+    #[inline]
+    fn to_le_bytes(self) -> Self::Bytes {
+        self.to_le_bytes()
+    }
 }
 
 impl Float for f64 {
@@ -28,6 +36,11 @@ impl Float for f64 {
     fn from_le_bytes(bytes: Self::Bytes) -> Self {
         Self::from_le_bytes(bytes)
     }
+
+    #[inline]
+    fn to_le_bytes(self) -> Self::Bytes {
+        self.to_le_bytes()
+    }
 }
 
 /// An iterator
@@ -64,6 +77,37 @@ impl<T: Float, R: std::io::Read> FloatIter<T, R> {
     pub fn into_inner(self) -> R {
         self.reader
     }
+
+    /// Decodes the next values into `values`, returning the number of values decoded,
+    /// which is smaller than `values.len()` only when the iterator is exhausted.
+    ///
+    /// Values are read in blocks of little-endian bytes and converted in bulk, which is
+    /// equivalent to, but much faster than, calling `next` for every item of `values`.
+    pub fn decode_into(&mut self, values: &mut [T]) -> Result<usize, Error> {
+        let length = values.len().min(self.remaining);
+        let size = std::mem::size_of::<T::Bytes>();
+
+        let mut block = [0u8; 4096];
+        for values in values[..length].chunks_mut(block.len() / size) {
+            let block = &mut block[..values.len() * size];
+            self.reader
+                .read_exact(block)
+                .map_err(|_| Error::DecodeFloat)?;
+            for (value, bytes) in values.iter_mut().zip(block.chunks_exact(size)) {
+                let mut chunk: T::Bytes = Default::default();
+                chunk.as_mut().copy_from_slice(bytes);
+                *value = T::from_le_bytes(chunk);
+            }
+            self.remaining -= values.len();
+        }
+        Ok(length)
+    }
+
+    /// Extends `values` with all remaining values of this iterator.
+    pub fn extend_into(&mut self, values: &mut Vec<T>) -> Result<(), Error> {
+        let remaining = self.remaining;
+        extend_with(values, remaining, |values| self.decode_into(values))
+    }
 }
 
 impl<T: Float, R: std::io::Read> Iterator for FloatIter<T, R> {
@@ -89,3 +133,38 @@ impl<T: Float, R: std::io::Read> Iterator for FloatIter<T, R> {
         (remaining, Some(remaining))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_into() {
+        let expected = (0..2000).map(|x| x as f64 * 0.5).collect::<Vec<_>>();
+        let data = expected
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        let mut iter = FloatIter::<f64, _>::new(data.as_slice(), 2000);
+        let mut values = vec![0.0; 3];
+        assert_eq!(iter.decode_into(&mut values).unwrap(), 3);
+        assert_eq!(values, &expected[..3]);
+        assert_eq!(iter.next().unwrap().unwrap(), expected[3]);
+
+        let mut values = vec![];
+        iter.extend_into(&mut values).unwrap();
+        assert_eq!(values, &expected[4..]);
+        assert!(iter.is_empty());
+
+        // truncated
+        let mut iter = FloatIter::<f32, _>::new(&data[..7], 2);
+        assert!(iter.extend_into(&mut vec![]).is_err());
+
+        // a declared length much larger than the stream errors without allocating it
+        let mut iter = FloatIter::<f64, _>::new(data.as_slice(), usize::MAX);
+        let mut values = vec![];
+        assert!(iter.extend_into(&mut values).is_err());
+        assert!(values.is_empty());
+    }
+}
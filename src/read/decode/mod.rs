@@ -1,11 +1,20 @@
 //! Contains different iterators that receive a reader ([`std::io::Read`])
 //! and return values for each of ORC's physical types (e.g. boolean).
+use crate::error::Error;
+
+mod bitmap;
 mod boolean_rle;
+mod byte_rle;
+mod decimal;
 mod float;
 mod rle_v2;
+pub mod unpack;
 mod variable_length;
 
+pub use bitmap::{decode_bitmap, is_set, scatter};
 pub use boolean_rle::{BooleanIter, BooleanRleRunIter, BooleanRun};
+pub use byte_rle::ByteIter;
+pub use decimal::DecimalIter;
 pub use float::{Float, FloatIter};
 pub use rle_v2::{
     SignedRleV2Iter, SignedRleV2Run, SignedRleV2RunIter, UnsignedRleV2Iter, UnsignedRleV2Run,
@@ -13,6 +22,41 @@ pub use rle_v2::{
 };
 pub use variable_length::Values;
 
+pub(crate) use rle_v2::{closest_fixed_bits, rle_v2_direct_bit_width};
+
+/// The maximum number of values by which `extend_into` grows its output before decoding them
+const EXTEND_BLOCK_SIZE: usize = 64 * 1024;
+
+/// Extends `values` with the `remaining` values decoded by `decode_into`, growing it by at most
+/// [`EXTEND_BLOCK_SIZE`] values at a time, so that its allocation is bounded by the values
+/// actually decoded instead of by their (declared) number. On error, `values` is left unchanged.
+fn extend_with<T: Clone + Default>(
+    values: &mut Vec<T>,
+    mut remaining: usize,
+    mut decode_into: impl FnMut(&mut [T]) -> Result<usize, Error>,
+) -> Result<(), Error> {
+    let start = values.len();
+    while remaining > 0 {
+        let offset = values.len();
+        let additional = remaining.min(EXTEND_BLOCK_SIZE);
+        values.resize(offset + additional, T::default());
+        match decode_into(&mut values[offset..]) {
+            Ok(written) => {
+                values.truncate(offset + written);
+                if written < additional {
+                    break;
+                }
+                remaining -= written;
+            }
+            Err(e) => {
+                values.truncate(start);
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
 #[inline]
 fn read_u8<R: std::io::Read>(reader: &mut R) -> Result<u8, std::io::Error> {
     let mut buf = [0; 1];
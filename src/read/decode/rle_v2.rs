@@ -2,6 +2,8 @@ use std::io::Read;
 
 use crate::error::Error;
 
+use super::unpack::{unpack, unpack_into};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum EncodingTypeV2 {
     ShortRepeat,
@@ -18,20 +20,18 @@ fn header_to_rle_v2_short_repeated_count(header: u8) -> u8 {
     header & 0b00000111
 }
 
-fn rle_v2_direct_bit_width(value: u8) -> u8 {
+/// Decodes the (5 bits) encoded bit width of Direct and Patched Base runs
+pub(crate) fn rle_v2_direct_bit_width(value: u8) -> u8 {
     match value {
-        0 => 1,
-        1 => 2,
-        3 => 4,
-        7 => 8,
-        15 => 16,
-        23 => 24,
+        0..=23 => value + 1,
+        24 => 26,
+        25 => 28,
+        26 => 30,
         27 => 32,
         28 => 40,
         29 => 48,
         30 => 56,
-        31 => 64,
-        other => todo!("{other}"),
+        _ => 64,
     }
 }
 
@@ -40,20 +40,11 @@ fn header_to_rle_v2_direct_bit_width(header: u8) -> u8 {
     rle_v2_direct_bit_width(bit_width)
 }
 
+/// Decodes the (5 bits) encoded bit width of Delta runs, where 0 represents a fixed delta
 fn rle_v2_delta_bit_width(value: u8) -> u8 {
     match value {
         0 => 0,
-        1 => 2,
-        3 => 4,
-        7 => 8,
-        15 => 16,
-        23 => 24,
-        27 => 32,
-        28 => 40,
-        29 => 48,
-        30 => 56,
-        31 => 64,
-        other => todo!("{other}"),
+        other => rle_v2_direct_bit_width(other),
     }
 }
 
@@ -75,7 +66,7 @@ fn unsigned_varint<R: Read>(reader: &mut R) -> Result<u64, Error> {
     loop {
         if j > 9 {
             // if j * 7 > 64
-            return Err(Error::OutOfSpec);
+            return Err(Error::OutOfSpec("a varint must have at most 10 bytes"));
         }
         reader.read_exact(&mut buf[..])?;
         i |= (u64::from(buf[0] & 0x7F)) << (j * 7);
@@ -101,25 +92,33 @@ fn signed_varint<R: Read>(reader: &mut R) -> Result<i64, Error> {
     unsigned_varint(reader).map(zigzag)
 }
 
+/// Reads exactly `length` bytes from `reader` to `scratch`, erroring if not enough bytes exist.
+fn read_to_scratch<R: Read>(
+    reader: &mut R,
+    scratch: &mut Vec<u8>,
+    length: usize,
+) -> Result<(), Error> {
+    scratch.clear();
+    scratch.reserve(length);
+    reader.take(length as u64).read_to_end(scratch)?;
+    if scratch.len() != length {
+        return Err(Error::OutOfSpec("a run is larger than its stream"));
+    }
+    Ok(())
+}
+
+/// The number of values unpacked at once to a stack buffer when they require post-processing
+const BUFFER_SIZE: usize = 128;
+
+/// Writes the items of `iter` to `values` until either is exhausted, returning the number written
 #[inline]
-fn unpack(bytes: &[u8], num_bits: u8, index: usize) -> u64 {
-    if num_bits == 0 {
-        return 0;
-    };
-    let num_bits = num_bits as usize;
-    let start = num_bits * index; // in bits
-    let length = num_bits; // in bits
-    let byte_start = start / 8;
-    let byte_end = (start + length + 7) / 8;
-    // copy swapped
-    let slice = &bytes[byte_start..byte_end];
-    let mut a = [0u8; 8];
-    for (i, item) in slice.iter().rev().enumerate() {
-        a[i] = *item;
-    }
-    let bits = u64::from_le_bytes(a);
-    let offset = (slice.len() * 8 - num_bits) % 8 - start % 8;
-    (bits >> offset) & (!0u64 >> (64 - num_bits))
+fn iter_into<T, I: Iterator<Item = T>>(iter: I, values: &mut [T]) -> usize {
+    let mut written = 0;
+    for (value, item) in values.iter_mut().zip(iter) {
+        *value = item;
+        written += 1;
+    }
+    written
 }
 
 #[derive(Debug)]
@@ -143,10 +142,8 @@ impl UnsignedDirectRun {
 
         let length = header_to_rle_v2_direct_length(header, header1[0]);
 
-        let additional = ((bit_width as usize) * (length as usize) + 7) / 8;
-        scratch.clear();
-        scratch.reserve(additional);
-        reader.take(additional as u64).read_to_end(&mut scratch)?;
+        let additional = ((bit_width as usize) * (length as usize)).div_ceil(8);
+        read_to_scratch(reader, &mut scratch, additional)?;
 
         Ok(Self {
             data: scratch,
@@ -160,6 +157,20 @@ impl UnsignedDirectRun {
     pub fn len(&self) -> usize {
         self.length - self.index
     }
+
+    /// Decodes the next values of the run into `values`, returning the number of values decoded
+    #[inline]
+    pub fn decode_into(&mut self, values: &mut [u64]) -> usize {
+        let length = values.len().min(self.len());
+        unpack_into(
+            &self.data,
+            self.bit_width,
+            self.index,
+            &mut values[..length],
+        );
+        self.index += length;
+        length
+    }
 }
 
 impl Iterator for UnsignedDirectRun {
@@ -205,11 +216,8 @@ impl UnsignedDeltaRun {
 
         let base = unsigned_varint(reader)?;
         let delta_base = signed_varint(reader)?;
-        let additional = ((length as usize - 2) * bit_width as usize + 7) / 8;
-
-        scratch.clear();
-        scratch.reserve(additional);
-        reader.take(additional as u64).read_to_end(&mut scratch)?;
+        let additional = ((length as usize).saturating_sub(2) * bit_width as usize).div_ceil(8);
+        read_to_scratch(reader, &mut scratch, additional)?;
 
         Ok(Self {
             base,
@@ -231,6 +239,41 @@ impl UnsignedDeltaRun {
         self.encoded_deltas.clear();
         self.encoded_deltas
     }
+
+    /// Decodes the next values of the run into `values`, returning the number of values decoded
+    #[inline]
+    pub fn decode_into(&mut self, values: &mut [u64]) -> usize {
+        let length = values.len().min(self.len());
+        let values = &mut values[..length];
+
+        // the first two values and fixed deltas are not bit-packed
+        let mut written = 0;
+        while written < length && (self.index < 2 || self.bit_width == 0) {
+            written += iter_into(&mut *self, &mut values[written..written + 1]);
+        }
+        if written == length {
+            return length;
+        }
+
+        let deltas = &mut values[written..];
+        unpack_into(&self.encoded_deltas, self.bit_width, self.index - 2, deltas);
+        self.index += deltas.len();
+        // the sign of the deltas is the sign of the delta base
+        let mut base = self.base;
+        if self.delta_base < 0 {
+            deltas.iter_mut().for_each(|delta| {
+                base = base.wrapping_sub(*delta);
+                *delta = base;
+            });
+        } else {
+            deltas.iter_mut().for_each(|delta| {
+                base = base.wrapping_add(*delta);
+                *delta = base;
+            });
+        }
+        self.base = base;
+        length
+    }
 }
 
 impl Iterator for UnsignedDeltaRun {
@@ -246,19 +289,16 @@ impl Iterator for UnsignedDeltaRun {
             }
             if index == 1 || self.bit_width == 0 {
                 self.index += 1;
-                if self.delta_base > 0 {
-                    self.base += self.delta_base as u64;
-                } else {
-                    self.base -= (-self.delta_base) as u64;
-                }
+                self.base = self.base.wrapping_add_signed(self.delta_base);
                 return self.base;
             }
             self.index += 1;
             let delta = unpack(&self.encoded_deltas, self.bit_width, index - 2);
-            if self.delta_base > 0 {
-                self.base += delta;
+            // the sign of the deltas is the sign of the delta base
+            if self.delta_base < 0 {
+                self.base = self.base.wrapping_sub(delta);
             } else {
-                self.base -= delta;
+                self.base = self.base.wrapping_add(delta);
             }
             self.base
         })
@@ -284,9 +324,7 @@ impl UnsignedShortRepeat {
         let width = 1 + header_to_rle_v2_short_repeated_width(header);
         let count = 3 + header_to_rle_v2_short_repeated_count(header);
 
-        scratch.clear();
-        scratch.reserve(width as usize);
-        reader.take(width as u64).read_to_end(&mut scratch)?;
+        read_to_scratch(reader, &mut scratch, width as usize)?;
 
         let mut a = [0u8; 8];
         a[8 - scratch.len()..].copy_from_slice(&scratch);
@@ -309,6 +347,15 @@ impl UnsignedShortRepeat {
     pub fn into_inner(self) -> Vec<u8> {
         self.scratch
     }
+
+    /// Decodes the next values of the run into `values`, returning the number of values decoded
+    #[inline]
+    pub fn decode_into(&mut self, values: &mut [u64]) -> usize {
+        let length = values.len().min(self.remaining);
+        values[..length].fill(self.value);
+        self.remaining -= length;
+        length
+    }
 }
 
 impl Iterator for UnsignedShortRepeat {
@@ -349,11 +396,8 @@ impl SignedDeltaRun {
 
         let base = unsigned_varint(reader).map(zigzag)?;
         let delta_base = signed_varint(reader)?;
-        let additional = ((length as usize - 2) * bit_width as usize + 7) / 8;
-
-        scratch.clear();
-        scratch.reserve(additional);
-        reader.take(additional as u64).read_to_end(&mut scratch)?;
+        let additional = ((length as usize).saturating_sub(2) * bit_width as usize).div_ceil(8);
+        read_to_scratch(reader, &mut scratch, additional)?;
 
         Ok(Self {
             base,
@@ -373,6 +417,47 @@ impl SignedDeltaRun {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Decodes the next values of the run into `values`, returning the number of values decoded
+    #[inline]
+    pub fn decode_into(&mut self, values: &mut [i64]) -> usize {
+        let length = values.len().min(self.len());
+        let values = &mut values[..length];
+
+        // the first two values and fixed deltas are not bit-packed
+        let mut written = 0;
+        while written < length && (self.index < 2 || self.bit_width == 0) {
+            written += iter_into(&mut *self, &mut values[written..written + 1]);
+        }
+
+        let mut deltas = [0u64; BUFFER_SIZE];
+        for values in values[written..].chunks_mut(BUFFER_SIZE) {
+            let deltas = &mut deltas[..values.len()];
+            unpack_into(&self.encoded_deltas, self.bit_width, self.index - 2, deltas);
+            self.index += values.len();
+            // the sign of the deltas is the sign of the delta base
+            let mut base = self.base;
+            if self.delta_base < 0 {
+                values
+                    .iter_mut()
+                    .zip(deltas.iter())
+                    .for_each(|(value, delta)| {
+                        base = base.wrapping_sub(*delta as i64);
+                        *value = base;
+                    });
+            } else {
+                values
+                    .iter_mut()
+                    .zip(deltas.iter())
+                    .for_each(|(value, delta)| {
+                        base = base.wrapping_add(*delta as i64);
+                        *value = base;
+                    });
+            }
+            self.base = base;
+        }
+        length
+    }
 }
 
 impl Iterator for SignedDeltaRun {
@@ -388,20 +473,17 @@ impl Iterator for SignedDeltaRun {
             }
             if index == 1 || self.bit_width == 0 {
                 self.index += 1;
-                if self.delta_base > 0 {
-                    self.base += self.delta_base as i64;
-                } else {
-                    self.base -= (-self.delta_base) as i64;
-                }
+                self.base = self.base.wrapping_add(self.delta_base);
                 return self.base;
             }
             self.index += 1;
             // edge case where `bit_width == 0`, where deltas are equal to base delta
             let delta = unpack(&self.encoded_deltas, self.bit_width, index - 2);
-            if self.delta_base > 0 {
-                self.base += delta as i64;
+            // the sign of the deltas is the sign of the delta base
+            if self.delta_base < 0 {
+                self.base = self.base.wrapping_sub(delta as i64);
             } else {
-                self.base -= delta as i64;
+                self.base = self.base.wrapping_add(delta as i64);
             }
             self.base
         })
@@ -414,6 +496,189 @@ impl Iterator for SignedDeltaRun {
     }
 }
 
+/// Returns the smallest bit width of Direct and Patched Base runs that can hold `num_bits`
+pub(crate) fn closest_fixed_bits(num_bits: u8) -> u8 {
+    match num_bits {
+        0 => 1,
+        1..=24 => num_bits,
+        25..=26 => 26,
+        27..=28 => 28,
+        29..=30 => 30,
+        31..=32 => 32,
+        33..=40 => 40,
+        41..=48 => 48,
+        49..=56 => 56,
+        _ => 64,
+    }
+}
+
+/// Reads `length` values of `num_bits` bits each from `reader` to `values`
+fn read_packed<R: Read>(
+    reader: &mut R,
+    scratch: &mut Vec<u8>,
+    num_bits: u8,
+    length: usize,
+    values: &mut Vec<u64>,
+) -> Result<(), Error> {
+    let additional = (num_bits as usize * length).div_ceil(8);
+    read_to_scratch(reader, scratch, additional)?;
+    values.clear();
+    values.resize(length, 0);
+    unpack_into(scratch, num_bits, 0, values);
+    Ok(())
+}
+
+/// A Patched Base run: values relative to a base, of which a few (the patches) have
+/// their most significant bits stored separately.
+#[derive(Debug)]
+pub struct UnsignedPatchedBaseRun {
+    values: Vec<u64>,
+    index: usize,
+}
+
+impl UnsignedPatchedBaseRun {
+    #[inline]
+    fn try_new<R: Read>(header: u8, reader: &mut R, mut scratch: Vec<u8>) -> Result<Self, Error> {
+        let mut headers = [0u8; 3];
+        reader.read_exact(&mut headers)?;
+        let bit_width = header_to_rle_v2_direct_bit_width(header);
+        let length = header_to_rle_v2_direct_length(header, headers[0]) as usize;
+        let base_width = ((headers[1] >> 5) & 0b111) as usize + 1;
+        let patch_width = rle_v2_direct_bit_width(headers[1] & 0b11111);
+        let patch_gap_width = ((headers[2] >> 5) & 0b111) + 1;
+        let patch_list_length = (headers[2] & 0b11111) as usize;
+
+        // the base is stored in sign-magnitude, big-endian
+        read_to_scratch(reader, &mut scratch, base_width)?;
+        let mut base = [0u8; 8];
+        base[8 - base_width..].copy_from_slice(&scratch);
+        let base = u64::from_be_bytes(base);
+        let sign = 1u64 << (base_width * 8 - 1);
+        let base = if base & sign == sign {
+            ((base & !sign) as i64).wrapping_neg()
+        } else {
+            base as i64
+        };
+
+        let mut values = vec![];
+        read_packed(reader, &mut scratch, bit_width, length, &mut values)?;
+
+        if patch_width as u32 + patch_gap_width as u32 > 64 {
+            return Err(Error::OutOfSpec(
+                "the patch and gap widths of a Patched Base run are larger than 64 bits",
+            ));
+        }
+        let patch_list_width = closest_fixed_bits(patch_width + patch_gap_width);
+        let mut patches = vec![];
+        read_packed(
+            reader,
+            &mut scratch,
+            patch_list_width,
+            patch_list_length,
+            &mut patches,
+        )?;
+
+        // apply the patches: each entry is the gap since the previous patch and the patch.
+        // Gaps larger than 255 are split in entries with a gap of 255 and an empty patch.
+        let patch_mask = u64::MAX >> (64 - patch_width);
+        let mut position = 0usize;
+        for entry in patches {
+            let gap = entry.checked_shr(patch_width as u32).unwrap_or(0);
+            let patch = entry & patch_mask;
+            position = position.saturating_add(gap as usize);
+            if gap == 255 && patch == 0 {
+                continue;
+            }
+            let value = values.get_mut(position).ok_or(Error::OutOfSpec(
+                "a patch of a Patched Base run is outside of the run",
+            ))?;
+            *value |= patch.checked_shl(bit_width as u32).unwrap_or(0);
+        }
+        values
+            .iter_mut()
+            .for_each(|value| *value = base.wrapping_add(*value as i64) as u64);
+
+        Ok(Self { values, index: 0 })
+    }
+
+    /// The number of items remaining
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.values.len() - self.index
+    }
+
+    /// Whether the iterator is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Decodes the next values of the run into `values`, returning the number of values decoded
+    #[inline]
+    pub fn decode_into(&mut self, values: &mut [u64]) -> usize {
+        let length = values.len().min(self.len());
+        values[..length].copy_from_slice(&self.values[self.index..self.index + length]);
+        self.index += length;
+        length
+    }
+}
+
+impl Iterator for UnsignedPatchedBaseRun {
+    type Item = u64;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.values.get(self.index).copied();
+        self.index += value.is_some() as usize;
+        value
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+/// A Patched Base run of signed integers. Contrarily to other runs, its values are not
+/// zigzag encoded: the sign is carried by the base.
+#[derive(Debug)]
+pub struct SignedPatchedBaseRun(UnsignedPatchedBaseRun);
+
+impl SignedPatchedBaseRun {
+    pub fn try_new<R: Read>(header: u8, reader: &mut R, scratch: Vec<u8>) -> Result<Self, Error> {
+        UnsignedPatchedBaseRun::try_new(header, reader, scratch).map(Self)
+    }
+
+    /// The number of items remaining
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the iterator is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Decodes the next values of the run into `values`, returning the number of values decoded
+    #[inline]
+    pub fn decode_into(&mut self, values: &mut [i64]) -> usize {
+        iter_into(self, values)
+    }
+}
+
+impl Iterator for SignedPatchedBaseRun {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|x| x as i64)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
 #[inline]
 fn run_encoding(header: u8) -> EncodingTypeV2 {
     match (header & 128 == 128, header & 64 == 64) {
@@ -436,6 +701,8 @@ pub enum UnsignedRleV2Run {
     Delta(UnsignedDeltaRun),
     /// Short repeat
     ShortRepeat(UnsignedShortRepeat),
+    /// Patched base
+    PatchedBase(UnsignedPatchedBaseRun),
 }
 
 impl UnsignedRleV2Run {
@@ -456,7 +723,9 @@ impl UnsignedRleV2Run {
             EncodingTypeV2::ShortRepeat => {
                 UnsignedShortRepeat::try_new(header, reader, scratch).map(Self::ShortRepeat)
             }
-            other => todo!("{other:?}"),
+            EncodingTypeV2::PatchedBase => {
+                UnsignedPatchedBaseRun::try_new(header, reader, scratch).map(Self::PatchedBase)
+            }
         }
     }
 
@@ -466,6 +735,18 @@ impl UnsignedRleV2Run {
             Self::Direct(run) => run.len(),
             Self::Delta(run) => run.len(),
             Self::ShortRepeat(run) => run.len(),
+            Self::PatchedBase(run) => run.len(),
+        }
+    }
+
+    /// Decodes the next values of the run into `values`, returning the number of values decoded
+    #[inline]
+    pub fn decode_into(&mut self, values: &mut [u64]) -> usize {
+        match self {
+            Self::Direct(run) => run.decode_into(values),
+            Self::Delta(run) => run.decode_into(values),
+            Self::ShortRepeat(run) => run.decode_into(values),
+            Self::PatchedBase(run) => run.decode_into(values),
         }
     }
 
@@ -507,7 +788,7 @@ impl<R: Read> Iterator for UnsignedRleV2RunIter<R> {
         (self.length != 0).then(|| {
             let run =
                 UnsignedRleV2Run::try_new(&mut self.reader, std::mem::take(&mut self.scratch))?;
-            self.length -= run.len();
+            self.length = self.length.saturating_sub(run.len());
             Ok(run)
         })
     }
@@ -532,6 +813,44 @@ impl<R: Read> UnsignedRleV2Iter<R> {
     pub fn into_inner(self) -> (R, Vec<u8>) {
         self.runs.into_inner()
     }
+
+    /// Decodes the next values into `values`, returning the number of values decoded,
+    /// which is smaller than `values.len()` only when the iterator is exhausted.
+    ///
+    /// Runs are decoded directly into `values`, which is equivalent to, but faster than,
+    /// calling `next` for every item of `values`.
+    pub fn decode_into(&mut self, values: &mut [u64]) -> Result<usize, Error> {
+        let mut written = 0;
+        while written < values.len() {
+            if let Some(run) = &mut self.current {
+                let additional = run.decode_into(&mut values[written..]);
+                written += additional;
+                if additional > 0 {
+                    continue;
+                }
+            }
+            match self.runs.next() {
+                Some(run) => self.current = Some(run?),
+                None => break,
+            }
+        }
+        Ok(written)
+    }
+
+    /// Extends `values` with all remaining values of this iterator.
+    pub fn extend_into(&mut self, values: &mut Vec<u64>) -> Result<(), Error> {
+        loop {
+            if let Some(run) = &mut self.current {
+                let start = values.len();
+                values.resize(start + run.len(), 0);
+                run.decode_into(&mut values[start..]);
+            }
+            match self.runs.next() {
+                Some(run) => self.current = Some(run?),
+                None => return Ok(()),
+            }
+        }
+    }
 }
 
 impl<R: Read> Iterator for UnsignedRleV2Iter<R> {
@@ -544,6 +863,7 @@ impl<R: Read> Iterator for UnsignedRleV2Iter<R> {
                 UnsignedRleV2Run::Direct(values_iter) => values_iter.next(),
                 UnsignedRleV2Run::Delta(values_iter) => values_iter.next(),
                 UnsignedRleV2Run::ShortRepeat(values_iter) => values_iter.next(),
+                UnsignedRleV2Run::PatchedBase(values_iter) => values_iter.next(),
             }
         } else {
             None
@@ -569,6 +889,25 @@ impl SignedDirectRun {
         UnsignedDirectRun::try_new(header, reader, scratch).map(Self)
     }
 
+    /// Decodes the next values of the run into `values`, returning the number of values decoded
+    #[inline]
+    pub fn decode_into(&mut self, values: &mut [i64]) -> usize {
+        let mut buffer = [0u64; BUFFER_SIZE];
+        let mut written = 0;
+        for values in values.chunks_mut(BUFFER_SIZE) {
+            let additional = self.0.decode_into(&mut buffer[..values.len()]);
+            values
+                .iter_mut()
+                .zip(buffer[..additional].iter())
+                .for_each(|(value, unsigned)| *value = zigzag(*unsigned));
+            written += additional;
+            if additional < values.len() {
+                break;
+            }
+        }
+        written
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
@@ -600,6 +939,15 @@ impl SignedShortRepeat {
         UnsignedShortRepeat::try_new(header, reader, scratch).map(Self)
     }
 
+    /// Decodes the next values of the run into `values`, returning the number of values decoded
+    #[inline]
+    pub fn decode_into(&mut self, values: &mut [i64]) -> usize {
+        let length = values.len().min(self.len());
+        values[..length].fill(zigzag(self.0.value));
+        self.0.remaining -= length;
+        length
+    }
+
     /// The number of items remaining
     pub fn len(&self) -> usize {
         self.0.len()
@@ -633,6 +981,8 @@ pub enum SignedRleV2Run {
     Delta(SignedDeltaRun),
     /// Short repeat
     ShortRepeat(SignedShortRepeat),
+    /// Patched base
+    PatchedBase(SignedPatchedBaseRun),
 }
 
 impl SignedRleV2Run {
@@ -653,7 +1003,9 @@ impl SignedRleV2Run {
             EncodingTypeV2::ShortRepeat => {
                 SignedShortRepeat::try_new(header, reader, scratch).map(Self::ShortRepeat)
             }
-            other => todo!("{other:?}"),
+            EncodingTypeV2::PatchedBase => {
+                SignedPatchedBaseRun::try_new(header, reader, scratch).map(Self::PatchedBase)
+            }
         }
     }
 
@@ -663,6 +1015,18 @@ impl SignedRleV2Run {
             Self::Direct(run) => run.len(),
             Self::Delta(run) => run.len(),
             Self::ShortRepeat(run) => run.len(),
+            Self::PatchedBase(run) => run.len(),
+        }
+    }
+
+    /// Decodes the next values of the run into `values`, returning the number of values decoded
+    #[inline]
+    pub fn decode_into(&mut self, values: &mut [i64]) -> usize {
+        match self {
+            Self::Direct(run) => run.decode_into(values),
+            Self::Delta(run) => run.decode_into(values),
+            Self::ShortRepeat(run) => run.decode_into(values),
+            Self::PatchedBase(run) => run.decode_into(values),
         }
     }
 
@@ -703,7 +1067,7 @@ impl<R: Read> Iterator for SignedRleV2RunIter<R> {
     fn next(&mut self) -> Option<Self::Item> {
         (self.length != 0).then(|| {
             let run = SignedRleV2Run::try_new(&mut self.reader, std::mem::take(&mut self.scratch))?;
-            self.length -= run.len();
+            self.length = self.length.saturating_sub(run.len());
             Ok(run)
         })
     }
@@ -728,6 +1092,44 @@ impl<R: Read> SignedRleV2Iter<R> {
     pub fn into_inner(self) -> (R, Vec<u8>) {
         self.runs.into_inner()
     }
+
+    /// Decodes the next values into `values`, returning the number of values decoded,
+    /// which is smaller than `values.len()` only when the iterator is exhausted.
+    ///
+    /// Runs are decoded directly into `values`, which is equivalent to, but faster than,
+    /// calling `next` for every item of `values`.
+    pub fn decode_into(&mut self, values: &mut [i64]) -> Result<usize, Error> {
+        let mut written = 0;
+        while written < values.len() {
+            if let Some(run) = &mut self.current {
+                let additional = run.decode_into(&mut values[written..]);
+                written += additional;
+                if additional > 0 {
+                    continue;
+                }
+            }
+            match self.runs.next() {
+                Some(run) => self.current = Some(run?),
+                None => break,
+            }
+        }
+        Ok(written)
+    }
+
+    /// Extends `values` with all remaining values of this iterator.
+    pub fn extend_into(&mut self, values: &mut Vec<i64>) -> Result<(), Error> {
+        loop {
+            if let Some(run) = &mut self.current {
+                let start = values.len();
+                values.resize(start + run.len(), 0);
+                run.decode_into(&mut values[start..]);
+            }
+            match self.runs.next() {
+                Some(run) => self.current = Some(run?),
+                None => return Ok(()),
+            }
+        }
+    }
 }
 
 impl<R: Read> Iterator for SignedRleV2Iter<R> {
@@ -740,6 +1142,7 @@ impl<R: Read> Iterator for SignedRleV2Iter<R> {
                 SignedRleV2Run::Direct(values_iter) => values_iter.next(),
                 SignedRleV2Run::Delta(values_iter) => values_iter.next(),
                 SignedRleV2Run::ShortRepeat(values_iter) => values_iter.next(),
+                SignedRleV2Run::PatchedBase(values_iter) => values_iter.next(),
             }
         } else {
             None
@@ -813,4 +1216,119 @@ mod test {
             .collect::<Vec<_>>();
         assert_eq!(a, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
     }
+
+    #[test]
+    fn direct_non_aligned() {
+        // [1, 2, 3, 4, 5, 6, 7] with a bit width of 3
+        let data: [u8; 5] = [0b01000100, 0x06, 0b00101001, 0b11001011, 0b10111000];
+        let data = &mut data.as_ref();
+
+        let a = UnsignedDirectRun::try_new(data[0], &mut &data[1..], vec![])
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(a, vec![1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn truncated_runs() {
+        // direct run of 4 values of 16 bits with only 3 values
+        let data: [u8; 8] = [0x5e, 0x03, 0x5c, 0xa1, 0xab, 0x1e, 0xde, 0xad];
+        assert!(UnsignedDirectRun::try_new(data[0], &mut &data[1..], vec![]).is_err());
+
+        // short repeat of a 2 bytes value with only 1 byte
+        let data: [u8; 2] = [0x0a, 0x27];
+        assert!(UnsignedShortRepeat::try_new(data[0], &mut &data[1..], vec![]).is_err());
+
+        // delta run without deltas
+        let data: [u8; 5] = [0xc6, 0x09, 0x02, 0x02, 0x22];
+        assert!(UnsignedDeltaRun::try_new(data[0], &mut &data[1..], vec![]).is_err());
+    }
+
+    #[test]
+    fn delta_of_length_one() {
+        // a fixed delta run of a single value
+        let data: [u8; 4] = [0xc0, 0x00, 0x02, 0x02];
+
+        let a = SignedDeltaRun::try_new(data[0], &mut &data[1..], vec![])
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(a, vec![1]);
+    }
+
+    #[test]
+    fn decode_into() {
+        // a short repeat, a direct and a delta run (see tests above)
+        let mut data = vec![0x0a, 0x27, 0x10];
+        data.extend([0x5e, 0x03, 0x5c, 0xa1, 0xab, 0x1e, 0xde, 0xad, 0xbe, 0xef]);
+        data.extend([0xc6, 0x09, 0x02, 0x02, 0x22, 0x42, 0x42, 0x46]);
+
+        let expected = UnsignedRleV2Iter::new(data.as_slice(), 19, vec![])
+            .collect::<Result<Vec<_>, Error>>()
+            .unwrap();
+        assert_eq!(expected.len(), 19);
+
+        for batch in [1, 3, 4, 7, 19, 30] {
+            let mut iter = UnsignedRleV2Iter::new(data.as_slice(), 19, vec![]);
+            let mut result = vec![];
+            let mut values = vec![0; batch];
+            loop {
+                let written = iter.decode_into(&mut values).unwrap();
+                result.extend_from_slice(&values[..written]);
+                if written < batch {
+                    break;
+                }
+            }
+            assert_eq!(result, expected);
+        }
+
+        let expected = SignedRleV2Iter::new(data.as_slice(), 19, vec![])
+            .collect::<Result<Vec<_>, Error>>()
+            .unwrap();
+        let mut iter = SignedRleV2Iter::new(data.as_slice(), 19, vec![]);
+        let mut result = vec![iter.next().unwrap().unwrap()];
+        iter.extend_into(&mut result).unwrap();
+        assert_eq!(result, expected);
+        assert_eq!(&result[..5], &[zigzag(10000); 5]);
+    }
+
+    #[test]
+    fn patched_base() {
+        // example of the specification
+        let data = [
+            0x8e, 0x13, 0x2b, 0x21, 0x07, 0xd0, 0x1e, 0x00, 0x14, 0x70, 0x28, 0x32, 0x3c, 0x46,
+            0x50, 0x5a, 0x64, 0x6e, 0x78, 0x82, 0x8c, 0x96, 0xa0, 0xaa, 0xb4, 0xbe, 0xfc, 0xe8,
+        ];
+        let expected = vec![
+            2030, 2000, 2020, 1000000, 2040, 2050, 2060, 2070, 2080, 2090, 2100, 2110, 2120, 2130,
+            2140, 2150, 2160, 2170, 2180, 2190,
+        ];
+
+        let a = SignedRleV2Iter::new(data.as_ref(), 20, vec![])
+            .collect::<Result<Vec<_>, Error>>()
+            .unwrap();
+        assert_eq!(a, expected);
+
+        let mut iter = SignedRleV2Iter::new(data.as_ref(), 20, vec![]);
+        let mut a = vec![0; 3];
+        assert_eq!(iter.decode_into(&mut a).unwrap(), 3);
+        iter.extend_into(&mut a).unwrap();
+        assert_eq!(a, expected);
+
+        let a = UnsignedRleV2Iter::new(data.as_ref(), 20, vec![])
+            .collect::<Result<Vec<_>, Error>>()
+            .unwrap();
+        assert_eq!(a, expected.iter().map(|x| *x as u64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn patched_base_out_of_run() {
+        // the example of the specification with only the first 3 values, whose patch
+        // (of the 4th value) is outside of the run
+        let data = [
+            0x8e, 0x02, 0x2b, 0x21, 0x07, 0xd0, 0x1e, 0x00, 0x14, 0xfc, 0xe8,
+        ];
+        assert!(SignedRleV2Iter::new(data.as_ref(), 3, vec![])
+            .collect::<Result<Vec<_>, Error>>()
+            .is_err());
+    }
 }
@@ -0,0 +1,147 @@
+//! Unpacking of big-endian, bit-packed integers, as used by RLE v2.
+//!
+//! Values are unpacked in groups of 8, which occupy exactly `num_bits` bytes. Each group is
+//! unpacked by a function specialized for its bit width, whose loads and shifts are known at
+//! compile time, so that the compiler can unroll and vectorize it.
+
+/// Unpacks the value of `num_bits` bits starting at bit `bit` of `bytes`.
+/// `bytes` must contain the value.
+#[inline(always)]
+fn read_bits(bytes: &[u8], bit: usize, num_bits: usize) -> u64 {
+    let start = bit / 8;
+    let shift = bit % 8;
+    // a value spans at most 9 bytes (64 bits with an offset of 7 bits)
+    let length = (shift + num_bits).div_ceil(8);
+    let mut window = [0u8; 16];
+    window[..length].copy_from_slice(&bytes[start..start + length]);
+    let window = u128::from_be_bytes(window);
+    ((window << shift) >> (128 - num_bits)) as u64
+}
+
+/// Unpacks the value at `index` of `bytes` with `num_bits` bits each.
+/// `bytes` must contain the value.
+#[inline]
+pub fn unpack(bytes: &[u8], num_bits: u8, index: usize) -> u64 {
+    if num_bits == 0 {
+        return 0;
+    }
+    let num_bits = num_bits as usize;
+    read_bits(bytes, num_bits * index, num_bits)
+}
+
+/// Unpacks 8 values of `BITS` bits from `bytes` (of `BITS` bytes) to `values`
+#[inline(always)]
+fn unpack8<const BITS: usize>(bytes: &[u8], values: &mut [u64]) {
+    let bytes = &bytes[..BITS];
+    for (i, value) in values[..8].iter_mut().enumerate() {
+        *value = read_bits(bytes, i * BITS, BITS);
+    }
+}
+
+/// Unpacks all groups of 8 values of `bytes` to `values`, returning the number of values unpacked.
+#[inline(never)]
+fn unpack_groups<const BITS: usize>(bytes: &[u8], values: &mut [u64]) -> usize {
+    let mut written = 0;
+    for (bytes, values) in bytes.chunks_exact(BITS).zip(values.chunks_exact_mut(8)) {
+        unpack8::<BITS>(bytes, values);
+        written += 8;
+    }
+    written
+}
+
+macro_rules! dispatch {
+    ($num_bits:expr, $bytes:expr, $values:expr, [$($bits:literal),*]) => {
+        match $num_bits {
+            $($bits => unpack_groups::<$bits>($bytes, $values),)*
+            _ => 0,
+        }
+    };
+}
+
+/// Unpacks the values `start..start + values.len()` of `bytes`, bit-packed with `num_bits`
+/// bits each, to `values`.
+/// # Panics
+/// This function panics if `bytes` does not contain all values or `num_bits > 64`.
+pub fn unpack_into(bytes: &[u8], num_bits: u8, start: usize, values: &mut [u64]) {
+    if num_bits == 0 {
+        values.fill(0);
+        return;
+    }
+    assert!(num_bits <= 64);
+
+    // values until a group boundary
+    let head = ((8 - start % 8) % 8).min(values.len());
+    let (head_values, values) = values.split_at_mut(head);
+    for (index, value) in (start..).zip(head_values.iter_mut()) {
+        *value = unpack(bytes, num_bits, index);
+    }
+    let start = start + head;
+
+    // groups of 8 values
+    let group_bytes = &bytes[start / 8 * num_bits as usize..];
+    let written = dispatch!(
+        num_bits,
+        group_bytes,
+        values,
+        [
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+            25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46,
+            47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64
+        ]
+    );
+
+    // remaining values, whose group is incomplete
+    let start = start + written;
+    for (index, value) in (start..).zip(values[written..].iter_mut()) {
+        *value = unpack(bytes, num_bits, index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Packs `values` in big-endian with `num_bits` each
+    fn pack(values: &[u64], num_bits: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; (values.len() * num_bits).div_ceil(8)];
+        for (i, value) in values.iter().enumerate() {
+            for bit in 0..num_bits {
+                if (value >> (num_bits - 1 - bit)) & 1 == 1 {
+                    let position = i * num_bits + bit;
+                    bytes[position / 8] |= 128 >> (position % 8);
+                }
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn unpack_all_widths() {
+        for num_bits in 1..=64usize {
+            let mask = u64::MAX >> (64 - num_bits);
+            let values = (0..37u64)
+                .map(|x| x.wrapping_mul(0x9E37_79B9_7F4A_7C15) & mask)
+                .collect::<Vec<_>>();
+            let bytes = pack(&values, num_bits);
+
+            for start in [0, 3, 8, 13] {
+                for length in [0, 1, 8, 20, 37 - start] {
+                    let length = length.min(37 - start);
+                    let mut result = vec![0; length];
+                    unpack_into(&bytes, num_bits as u8, start, &mut result);
+                    assert_eq!(result, &values[start..start + length], "{num_bits}");
+                }
+            }
+            for (index, value) in values.iter().enumerate() {
+                assert_eq!(unpack(&bytes, num_bits as u8, index), *value);
+            }
+        }
+    }
+
+    #[test]
+    fn unpack_zero_width() {
+        let mut result = vec![1; 10];
+        unpack_into(&[], 0, 0, &mut result);
+        assert_eq!(result, vec![0; 10]);
+    }
+}
@@ -2,6 +2,8 @@ use crate::error::Error;
 
 use std::io::Read;
 
+/// A reader of the bytes of variable-length values (e.g. strings), whose lengths are
+/// stored separately, on the "Length" stream.
 pub struct Values<R: Read> {
     reader: R,
     scratch: Vec<u8>,
@@ -14,15 +16,91 @@ impl<R: Read> Values<R> {
 
     pub fn next(&mut self, length: usize) -> Result<&[u8], Error> {
         self.scratch.clear();
-        self.scratch.reserve(length);
         (&mut self.reader)
             .take(length as u64)
             .read_to_end(&mut self.scratch)?;
+        if self.scratch.len() != length {
+            return Err(Error::OutOfSpec("the value is larger than its stream"));
+        }
 
         Ok(&self.scratch)
     }
 
+    /// Reads the bytes of the next values into `values`, returning the number of bytes read,
+    /// which is smaller than `values.len()` only when the stream is exhausted.
+    ///
+    /// This is equivalent to, but faster than, calling `next` for each of the values whose
+    /// lengths add up to `values.len()`.
+    pub fn decode_into(&mut self, values: &mut [u8]) -> Result<usize, Error> {
+        let mut written = 0;
+        while written < values.len() {
+            match self.reader.read(&mut values[written..]) {
+                Ok(0) => break,
+                Ok(read) => written += read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(written)
+    }
+
+    /// Extends `values` with the bytes of the next values, whose lengths add up to `length`.
+    /// `values` grows with the bytes read instead of by `length` at once, and is left
+    /// unchanged on error.
+    pub fn extend_into(&mut self, length: usize, values: &mut Vec<u8>) -> Result<(), Error> {
+        let start = values.len();
+        let read = (&mut self.reader)
+            .take(length as u64)
+            .read_to_end(values)
+            .map_err(Error::from)
+            .and_then(|read| {
+                if read == length {
+                    Ok(())
+                } else {
+                    Err(Error::OutOfSpec("the values are larger than their stream"))
+                }
+            });
+        if read.is_err() {
+            values.truncate(start);
+        }
+        read
+    }
+
     pub fn into_inner(self) -> Vec<u8> {
         self.scratch
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_into() {
+        let data = b"abcdefghij";
+
+        let mut iter = Values::new(data.as_slice(), vec![]);
+        let mut values = vec![0; 3];
+        assert_eq!(iter.decode_into(&mut values).unwrap(), 3);
+        assert_eq!(values, b"abc");
+        assert_eq!(iter.next(2).unwrap(), b"de");
+
+        let mut values = b"z".to_vec();
+        iter.extend_into(4, &mut values).unwrap();
+        assert_eq!(values, b"zfghi");
+        let mut values = vec![0; 3];
+        assert_eq!(iter.decode_into(&mut values).unwrap(), 1);
+        assert_eq!(values[0], b'j');
+
+        // truncated
+        let mut values = vec![];
+        let mut iter = Values::new(data.as_slice(), vec![]);
+        assert!(iter.extend_into(11, &mut values).is_err());
+        assert!(values.is_empty());
+
+        // a declared length much larger than the stream errors without allocating it
+        let mut iter = Values::new(data.as_slice(), vec![]);
+        assert!(iter.extend_into(usize::MAX, &mut values).is_err());
+        assert!(values.is_empty());
+    }
+}
@@ -1,47 +1,107 @@
 //! Contains [`Decompressor`]
-use std::io::Read;
+use std::sync::Arc;
 
 use fallible_streaming_iterator::FallibleStreamingIterator;
 
-use crate::error::Error;
+use crate::error::{Error, Location};
 use crate::proto::CompressionKind;
 
-fn decode_header(bytes: &[u8]) -> (bool, usize) {
-    let a: [u8; 3] = (&bytes[..3]).try_into().unwrap();
-    let a = [0, a[0], a[1], a[2]];
+/// The default maximum size of a (decompressed) chunk, see [`Decompressor::with_max_chunk_size`].
+pub const DEFAULT_MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+fn decode_header(bytes: &[u8]) -> Result<(bool, usize), Error> {
+    let a = match bytes {
+        [a, b, c, ..] => [0, *a, *b, *c],
+        _ => {
+            return Err(Error::OutOfSpec(
+                "a compressed chunk must have a 3 byte header",
+            ))
+        }
+    };
     let length = u32::from_le_bytes(a);
     let is_original = a[1] & 1 == 1;
     let length = (length >> (8 + 1)) as usize;
 
-    (is_original, length)
+    Ok((is_original, length))
 }
 
+/// The initial capacity of the decompression buffer when the chunk size is large.
+/// Chunks are bounded by the file's compression block size (256 KiB by default), which
+/// is usually much smaller than this.
+const MAX_INITIAL_CAPACITY: usize = 4 * 1024 * 1024;
+
 enum State<'a> {
     Original(&'a [u8]),
-    Compressed(Vec<u8>),
+    /// the chunk was decompressed to `scratch`
+    Compressed,
 }
 
 struct DecompressorIter<'a> {
     stream: &'a [u8],
+    length: usize,
     current: Option<State<'a>>, // when we have compression but the value is original
     compression: CompressionKind,
     scratch: Vec<u8>,
+    max_chunk_size: usize,
+    inflate: Option<flate2::Decompress>,
 }
 
 impl<'a> DecompressorIter<'a> {
     pub fn new(stream: &'a [u8], compression: CompressionKind, scratch: Vec<u8>) -> Self {
         Self {
             stream,
+            length: stream.len(),
             current: None,
             compression,
             scratch,
+            max_chunk_size: DEFAULT_MAX_CHUNK_SIZE,
+            inflate: None,
         }
     }
 
+    /// The position in the stream of the next chunk
+    pub fn position(&self) -> usize {
+        self.length - self.stream.len()
+    }
+
     pub fn into_inner(self) -> Vec<u8> {
-        match self.current {
-            Some(State::Compressed(some)) => some,
-            _ => self.scratch,
+        self.scratch
+    }
+}
+
+/// Decompresses a (raw deflate) `chunk` into `buffer` with a single call to the codec
+/// whenever `buffer` can hold `max_chunk_size` bytes, which it does after the first chunk.
+fn inflate(
+    inflate: &mut flate2::Decompress,
+    chunk: &[u8],
+    buffer: &mut Vec<u8>,
+    max_chunk_size: usize,
+) -> Result<(), Error> {
+    // one byte over the limit is used to detect chunks over it
+    let capacity = max_chunk_size.saturating_add(1);
+
+    inflate.reset(false);
+    buffer.clear();
+    buffer.reserve(capacity.min(MAX_INITIAL_CAPACITY));
+    loop {
+        let consumed = inflate.total_in() as usize;
+        let status = inflate
+            .decompress_vec(&chunk[consumed..], buffer, flate2::FlushDecompress::Finish)
+            .map_err(|error| Error::Decompression(Arc::new(error.into())))?;
+        if buffer.len() > max_chunk_size {
+            return Err(Error::LimitExceeded(
+                "a decompressed chunk is larger than the maximum chunk size",
+            ));
+        }
+        match status {
+            flate2::Status::StreamEnd => return Ok(()),
+            _ if buffer.len() < buffer.capacity() => {
+                return Err(Error::Decompression(Arc::new(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "the compressed chunk ended before the end of its deflate stream",
+                ))))
+            }
+            _ => buffer.reserve(buffer.len().min(capacity - buffer.len())),
         }
     }
 }
@@ -59,26 +119,40 @@ impl<'a> FallibleStreamingIterator for DecompressorIter<'a> {
         }
         match self.compression {
             CompressionKind::None => {
-                // todo: take stratch from current State::Compressed for re-use
                 self.current = Some(State::Original(self.stream));
                 self.stream = &[];
             }
             CompressionKind::Zlib => {
-                // todo: take stratch from current State::Compressed for re-use
-                let (is_original, length) = decode_header(self.stream);
+                let (is_original, length) = decode_header(self.stream)?;
                 self.stream = &self.stream[3..];
+                if length > self.stream.len() {
+                    return Err(Error::OutOfSpec(
+                        "the length of a compressed chunk is larger than its stream",
+                    ));
+                }
+                if length > self.max_chunk_size {
+                    return Err(Error::LimitExceeded(
+                        "the length of a chunk is larger than the maximum chunk size",
+                    ));
+                }
                 let (maybe_compressed, remaining) = self.stream.split_at(length);
                 self.stream = remaining;
                 if is_original {
                     self.current = Some(State::Original(maybe_compressed));
                 } else {
-                    let mut gz = flate2::read::DeflateDecoder::new(maybe_compressed);
-                    self.scratch.clear();
-                    gz.read_to_end(&mut self.scratch)?;
-                    self.current = Some(State::Compressed(std::mem::take(&mut self.scratch)));
+                    let decompress = self
+                        .inflate
+                        .get_or_insert_with(|| flate2::Decompress::new(false));
+                    inflate(
+                        decompress,
+                        maybe_compressed,
+                        &mut self.scratch,
+                        self.max_chunk_size,
+                    )?;
+                    self.current = Some(State::Compressed);
                 }
             }
-            _ => todo!(),
+            other => return Err(Error::UnsupportedCompression(other)),
         };
         Ok(())
     }
@@ -87,16 +161,42 @@ impl<'a> FallibleStreamingIterator for DecompressorIter<'a> {
     fn get(&self) -> Option<&Self::Item> {
         self.current.as_ref().map(|x| match x {
             State::Original(x) => *x,
-            State::Compressed(x) => x.as_ref(),
+            State::Compressed => self.scratch.as_ref(),
         })
     }
 }
 
-/// A [`Read`]er fulfilling the ORC specification of reading compressed data.
+/// A chunk of a stream, as returned by [`Decompressor::next_chunk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chunk<'a, 'b> {
+    /// A slice of the stream itself, when the stream is not compressed or the chunk was
+    /// stored as original
+    Borrowed(&'a [u8]),
+    /// A chunk decompressed to the [`Decompressor`]'s memory region
+    Decompressed(&'b [u8]),
+}
+
+impl std::ops::Deref for Chunk<'_, '_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Borrowed(chunk) => chunk,
+            Self::Decompressed(chunk) => chunk,
+        }
+    }
+}
+
+/// A [`std::io::Read`]er fulfilling the ORC specification of reading compressed data.
+///
+/// It also implements [`std::io::BufRead`], which can be used to consume the stream
+/// chunk by chunk without copying uncompressed chunks, and [`Decompressor::next_chunk`],
+/// which returns those chunks with the lifetime of the stream.
 pub struct Decompressor<'a> {
     decompressor: DecompressorIter<'a>,
     offset: usize,
     is_first: bool,
+    location: Location,
 }
 
 impl<'a> Decompressor<'a> {
@@ -106,53 +206,107 @@ impl<'a> Decompressor<'a> {
             decompressor: DecompressorIter::new(stream, compression, scratch),
             offset: 0,
             is_first: true,
+            location: Location::default(),
         }
     }
 
+    /// Sets the maximum size of a chunk of this stream, compressed or decompressed,
+    /// defaulting to [`DEFAULT_MAX_CHUNK_SIZE`].
+    ///
+    /// Chunks larger than this error with [`Error::LimitExceeded`] instead of being
+    /// decompressed, which bounds the memory used by this [`Decompressor`].
+    pub fn with_max_chunk_size(mut self, max_chunk_size: usize) -> Self {
+        self.decompressor.max_chunk_size = max_chunk_size;
+        self
+    }
+
+    /// Sets the location of the stream in the file, attached to errors of this [`Decompressor`]
+    pub(crate) fn located(mut self, location: Location) -> Self {
+        self.location = location;
+        self
+    }
+
+    fn advance(&mut self) -> std::io::Result<()> {
+        let position = self.decompressor.position() as u64;
+        self.decompressor.advance().map_err(|error| {
+            let location = Location {
+                offset: self.location.offset.map(|offset| offset + position),
+                ..self.location
+            };
+            error.at(location).into()
+        })
+    }
+
+    /// Returns the remaining of the current chunk and consumes it, or `None` at the end
+    /// of the stream.
+    ///
+    /// When the stream is not compressed or the chunk was stored as original, this is a
+    /// [`Chunk::Borrowed`] slice of the stream, which outlives this [`Decompressor`].
+    pub fn next_chunk(&mut self) -> Result<Option<Chunk<'a, '_>>, Error> {
+        use std::io::BufRead;
+        if self.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let offset = std::mem::replace(
+            &mut self.offset,
+            self.decompressor.get().map_or(0, <[u8]>::len),
+        );
+        Ok(match &self.decompressor.current {
+            Some(State::Original(chunk)) => Some(Chunk::Borrowed(&chunk[offset..])),
+            Some(State::Compressed) => {
+                Some(Chunk::Decompressed(&self.decompressor.scratch[offset..]))
+            }
+            None => None,
+        })
+    }
+
     /// Returns the internal memory region, so it can be re-used
     pub fn into_inner(self) -> Vec<u8> {
         self.decompressor.into_inner()
     }
 }
 
-impl<'a> std::io::Read for Decompressor<'a> {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+impl<'a> std::io::BufRead for Decompressor<'a> {
+    /// Returns the remaining of the current chunk. When the stream is not compressed
+    /// or the chunk was stored as original, this is a slice of the original stream,
+    /// i.e. no copy nor decompression is performed.
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
         if self.is_first {
             self.is_first = false;
-            self.decompressor.advance().unwrap();
+            self.advance()?;
         }
-        let current = self.decompressor.get();
-        let current = if let Some(current) = current {
-            if current.len() == self.offset {
-                self.decompressor.advance().unwrap();
-                self.offset = 0;
-                let current = self.decompressor.get();
-                if let Some(current) = current {
-                    current
-                } else {
-                    return Ok(0);
-                }
-            } else {
-                &current[self.offset..]
-            }
-        } else {
-            return Ok(0);
-        };
-
-        if current.len() >= buf.len() {
-            buf.copy_from_slice(&current[..buf.len()]);
-            self.offset += buf.len();
-            Ok(buf.len())
-        } else {
-            buf[..current.len()].copy_from_slice(current);
-            self.offset += current.len();
-            Ok(current.len())
+        while matches!(self.decompressor.get(), Some(current) if current.len() == self.offset) {
+            self.advance()?;
+            self.offset = 0;
         }
+        Ok(self
+            .decompressor
+            .get()
+            .map(|current| &current[self.offset..])
+            .unwrap_or(&[]))
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let remaining = self.decompressor.get().map(|x| x.len()).unwrap_or(0) - self.offset;
+        self.offset += amt.min(remaining);
+    }
+}
+
+impl<'a> std::io::Read for Decompressor<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        use std::io::BufRead;
+        let current = self.fill_buf()?;
+        let length = current.len().min(buf.len());
+        buf[..length].copy_from_slice(&current[..length]);
+        self.consume(length);
+        Ok(length)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io::Read;
+
     use super::*;
 
     #[test]
@@ -160,7 +314,7 @@ mod tests {
         // 5 uncompressed = [0x0b, 0x00, 0x00] = [0b1011, 0, 0]
         let bytes = &[0b1011, 0, 0, 0];
 
-        let (is_original, length) = decode_header(bytes);
+        let (is_original, length) = decode_header(bytes).unwrap();
         assert!(is_original);
         assert_eq!(length, 5);
     }
@@ -170,8 +324,195 @@ mod tests {
         // 100_000 compressed = [0x40, 0x0d, 0x03] = [0b01000000, 0b00001101, 0b00000011]
         let bytes = &[0b01000000, 0b00001101, 0b00000011, 0];
 
-        let (is_original, length) = decode_header(bytes);
+        let (is_original, length) = decode_header(bytes).unwrap();
         assert!(!is_original);
         assert_eq!(length, 100_000);
     }
+
+    #[test]
+    fn buf_read_uncompressed_is_zero_copy() {
+        use std::io::BufRead;
+        let stream = [1u8, 2, 3, 4];
+
+        let mut decompressor = Decompressor::new(&stream, CompressionKind::None, vec![]);
+        let chunk = decompressor.fill_buf().unwrap();
+        assert_eq!(chunk, &stream);
+        assert_eq!(chunk.as_ptr(), stream.as_ptr());
+
+        decompressor.consume(3);
+        assert_eq!(decompressor.fill_buf().unwrap(), &[4]);
+        decompressor.consume(1);
+        assert!(decompressor.fill_buf().unwrap().is_empty());
+    }
+
+    #[test]
+    fn next_chunk_borrows_original_chunks() {
+        // two chunks stored as original, [1, 2] and [3], around a compressed chunk
+        let mut stream = vec![0b101, 0, 0, 1, 2];
+        stream.extend(compressed_chunk(&[4; 10]));
+        stream.extend([0b11, 0, 0, 3]);
+        let range = stream.as_ptr_range();
+
+        let mut decompressor = Decompressor::new(&stream, CompressionKind::Zlib, vec![]);
+        let first = match decompressor.next_chunk().unwrap() {
+            Some(Chunk::Borrowed(chunk)) => chunk,
+            other => panic!("{other:?}"),
+        };
+        assert!(matches!(
+            decompressor.next_chunk().unwrap(),
+            Some(Chunk::Decompressed(&[4, 4, 4, 4, 4, 4, 4, 4, 4, 4]))
+        ));
+        let Some(Chunk::Borrowed(last)) = decompressor.next_chunk().unwrap() else {
+            panic!("the last chunk is stored as original")
+        };
+        assert!(decompressor.next_chunk().unwrap().is_none());
+        // the borrowed chunks point into the stream and outlive the decompressor
+        drop(decompressor);
+        assert_eq!((first, last), (&[1, 2][..], &[3][..]));
+        for chunk in [first, last] {
+            let chunk = chunk.as_ptr_range();
+            assert!(range.start <= chunk.start && chunk.end <= range.end);
+        }
+
+        // a partially consumed uncompressed stream
+        let stream = [1u8, 2, 3];
+        let mut decompressor = Decompressor::new(&stream, CompressionKind::None, vec![]);
+        let mut byte = [0];
+        decompressor.read_exact(&mut byte).unwrap();
+        assert_eq!(
+            decompressor.next_chunk().unwrap(),
+            Some(Chunk::Borrowed(&stream[1..]))
+        );
+        assert!(decompressor.next_chunk().unwrap().is_none());
+    }
+
+    #[test]
+    fn read_original_chunks() {
+        // two chunks stored as original: [1, 2] and [3]
+        let stream = [0b101, 0, 0, 1, 2, 0b11, 0, 0, 3];
+
+        let mut decompressor = Decompressor::new(&stream, CompressionKind::Zlib, vec![]);
+        let mut result = vec![];
+        decompressor.read_to_end(&mut result).unwrap();
+        assert_eq!(result, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn invalid_chunks() {
+        // incomplete header
+        let stream = [0b101, 0];
+        let mut decompressor = Decompressor::new(&stream, CompressionKind::Zlib, vec![]);
+        assert!(decompressor.read_to_end(&mut vec![]).is_err());
+
+        // chunk larger than the stream
+        let stream = [0b101, 0, 0, 1];
+        let mut decompressor = Decompressor::new(&stream, CompressionKind::Zlib, vec![]);
+        assert!(decompressor.read_to_end(&mut vec![]).is_err());
+
+        // invalid deflate
+        let stream = [0b100, 0, 0, 0xff, 0xff];
+        let mut decompressor = Decompressor::new(&stream, CompressionKind::Zlib, vec![]);
+        assert!(decompressor.read_to_end(&mut vec![]).is_err());
+
+        // unsupported
+        let stream = [0b100, 0, 0, 0xff, 0xff];
+        let mut decompressor = Decompressor::new(&stream, CompressionKind::Lzo, vec![]);
+        assert!(decompressor.read_to_end(&mut vec![]).is_err());
+    }
+
+    /// Returns `data` as a compressed chunk, including its header
+    fn compressed_chunk(data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, data).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let header = ((compressed.len() as u32) << 1).to_le_bytes();
+        let mut chunk = header[..3].to_vec();
+        chunk.extend(compressed);
+        chunk
+    }
+
+    #[test]
+    fn chunk_larger_than_limit() {
+        let stream = compressed_chunk(&[0; 1000]);
+
+        let mut decompressor =
+            Decompressor::new(&stream, CompressionKind::Zlib, vec![]).with_max_chunk_size(1000);
+        let mut result = vec![];
+        decompressor.read_to_end(&mut result).unwrap();
+        assert_eq!(result, vec![0; 1000]);
+
+        let mut decompressor =
+            Decompressor::new(&stream, CompressionKind::Zlib, vec![]).with_max_chunk_size(999);
+        let error: Error = decompressor.read_to_end(&mut vec![]).unwrap_err().into();
+        assert!(matches!(error.unlocated(), Error::LimitExceeded(_)));
+
+        // the chunk itself is larger than the limit
+        let stream = [0b101, 0, 0, 1, 2];
+        let mut decompressor =
+            Decompressor::new(&stream, CompressionKind::Zlib, vec![]).with_max_chunk_size(1);
+        assert!(decompressor.read_to_end(&mut vec![]).is_err());
+    }
+
+    #[test]
+    fn reuses_buffer() {
+        use std::io::BufRead;
+        let mut stream = compressed_chunk(&[1; 1000]);
+        stream.extend(compressed_chunk(&[2; 1000]));
+        stream.extend(compressed_chunk(&[3; 10]));
+
+        let mut decompressor =
+            Decompressor::new(&stream, CompressionKind::Zlib, vec![]).with_max_chunk_size(1000);
+        let mut pointers = vec![];
+        let mut result = vec![];
+        loop {
+            let chunk = decompressor.fill_buf().unwrap();
+            if chunk.is_empty() {
+                break;
+            }
+            pointers.push(chunk.as_ptr());
+            result.extend_from_slice(chunk);
+            let length = chunk.len();
+            decompressor.consume(length);
+        }
+        assert_eq!(result.len(), 2010);
+        assert_eq!(&result[995..1005], &[1, 1, 1, 1, 1, 2, 2, 2, 2, 2]);
+        // all chunks were decompressed to the same, pre-sized, region
+        assert_eq!(pointers.len(), 3);
+        assert!(pointers.iter().all(|pointer| *pointer == pointers[0]));
+        assert!(decompressor.into_inner().capacity() > 1000);
+    }
+
+    #[test]
+    fn truncated_deflate() {
+        let mut stream = compressed_chunk(&(0..=255).collect::<Vec<u8>>());
+        // shorten the chunk by one byte, both in the header and the data
+        let length = ((stream.len() as u32 - 4) << 1).to_le_bytes();
+        stream[..3].copy_from_slice(&length[..3]);
+        stream.pop();
+
+        let mut decompressor = Decompressor::new(&stream, CompressionKind::Zlib, vec![]);
+        let error: Error = decompressor.read_to_end(&mut vec![]).unwrap_err().into();
+        assert!(matches!(error.unlocated(), Error::Decompression(_)));
+    }
+
+    #[test]
+    fn errors_are_located() {
+        // a valid original chunk followed by an invalid deflate chunk
+        let stream = [0b11, 0, 0, 1, 0b100, 0, 0, 0xff, 0xff];
+        let location = Location {
+            column: Some(1),
+            offset: Some(100),
+            ..Default::default()
+        };
+        let mut decompressor =
+            Decompressor::new(&stream, CompressionKind::Zlib, vec![]).located(location);
+        let error: Error = decompressor.read_to_end(&mut vec![]).unwrap_err().into();
+
+        assert!(matches!(error.unlocated(), Error::Decompression(_)));
+        let location = error.location().unwrap();
+        assert_eq!(location.column, Some(1));
+        assert_eq!(location.offset, Some(104));
+    }
 }
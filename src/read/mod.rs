@@ -7,23 +7,110 @@
 //! 4. Attach an Iterator to the region
 
 use std::io::{Read, Seek, SeekFrom};
+use std::sync::{Arc, OnceLock};
 
 use prost::Message;
 
-use crate::error::Error;
+use crate::error::{Error, Location};
 use crate::proto::stream::Kind;
-use crate::proto::{CompressionKind, Footer, Metadata, PostScript, StripeFooter};
+use crate::proto::{Footer, Metadata, PostScript, StripeFooter, StripeInformation};
+use crate::version::{validate_version, WriterVersion, MAGIC};
 
 mod column;
 pub mod decode;
 pub mod decompress;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(any(feature = "arrow", feature = "serde"))]
+pub(crate) mod streams;
+pub(crate) mod types;
 pub use column::Column;
 
 const DEFAULT_FOOTER_SIZE: u64 = 16 * 1024;
+/// The maximum size of the postscript and its length, whose length is stored in a single byte
+const MAX_POSTSCRIPT_SIZE: u64 = 256;
+
+/// Seconds between the unix epoch and ORC's epoch (of timestamps), 2015-01-01 00:00:00
+pub(crate) const ORC_EPOCH: i64 = 1_420_070_400;
+
+/// Limits on the memory that reading a file may require.
+///
+/// ORC files declare the lengths of their regions (tail, streams, chunks, dictionaries)
+/// and their number of rows, which a malformed file may set arbitrarily large. These limits bound the allocations
+/// derived from them; exceeding any of them results in [`Error::LimitExceeded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum size of the file's tail (postscript, footer and metadata), in bytes
+    pub max_tail_size: u64,
+    /// The maximum size of the streams of a column in a stripe and of a stripe's footer, in bytes
+    pub max_stream_size: u64,
+    /// The maximum size of a chunk, compressed or decompressed, in bytes
+    pub max_chunk_size: usize,
+    /// The maximum number of entries of a column's dictionary
+    pub max_dictionary_size: usize,
+    /// The maximum number of values of a column in a stripe, i.e. the stripe's number of rows
+    /// or, for the children of lists and maps, their number of items
+    pub max_values: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_tail_size: 64 * 1024 * 1024,
+            max_stream_size: 1024 * 1024 * 1024,
+            max_chunk_size: decompress::DEFAULT_MAX_CHUNK_SIZE,
+            max_dictionary_size: 64 * 1024 * 1024,
+            max_values: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl Limits {
+    /// Errors when `values` (the declared number of values of a column) exceeds
+    /// [`Limits::max_values`], before anything is allocated from it.
+    pub(crate) fn check_values(&self, values: u64) -> Result<usize, Error> {
+        usize::try_from(values)
+            .ok()
+            .filter(|values| *values <= self.max_values)
+            .ok_or(Error::LimitExceeded(
+                "a column has more values than the maximum number of values",
+            ))
+    }
+}
+
+/// Options to read the file's metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// The number of bytes read from the end of the file on the first read.
+    /// When the tail (postscript, footer and metadata) is larger than this,
+    /// a second read is performed for the remaining bytes.
+    /// Values smaller than 256 bytes (the maximum size of the postscript and its length)
+    /// are rounded up to 256 bytes, so that the first read always contains the postscript.
+    pub initial_tail_size: u64,
+    /// Whether files without magic, neither at the end of the postscript nor at the
+    /// beginning of the file, are accepted. Only very old writers produce such files.
+    ///
+    /// This also accepts files of writer versions unknown to this crate, which are read as
+    /// [`WriterVersion::Future`], i.e. assuming they contain the fixes of every known
+    /// version. Otherwise, such files error with [`Error::UnknownWriterVersion`].
+    pub allow_headerless: bool,
+    /// The [`Limits`] used when reading the file.
+    pub limits: Limits,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            initial_tail_size: DEFAULT_FOOTER_SIZE,
+            allow_headerless: false,
+            limits: Limits::default(),
+        }
+    }
+}
 
 // see (unstable) Seek::stream_len
 fn stream_len(seek: &mut impl Seek) -> std::result::Result<u64, std::io::Error> {
-    let old_pos = seek.seek(SeekFrom::Current(0))?;
+    let old_pos = seek.stream_position()?;
     let len = seek.seek(SeekFrom::End(0))?;
 
     // Avoid seeking a third time when we were already at the end of the
@@ -36,55 +123,269 @@ fn stream_len(seek: &mut impl Seek) -> std::result::Result<u64, std::io::Error>
 }
 
 /// The file's metadata.
+///
+/// The file's [`Metadata`] (the statistics of every stripe) is only deserialized on the first
+/// call to [`FileMetadata::metadata`], since it is often large and not always needed.
 #[derive(Debug)]
 pub struct FileMetadata {
     pub postscript: PostScript,
     pub footer: Footer,
-    pub metadata: Metadata,
+    /// the serialized (and possibly compressed) [`Metadata`]
+    serialized_metadata: Vec<u8>,
+    metadata: OnceLock<Metadata>,
+    writer_version: WriterVersion,
+    limits: Limits,
+}
+
+impl FileMetadata {
+    /// Returns the file's [`Metadata`], deserializing it on the first call.
+    pub fn metadata(&self) -> Result<&Metadata, Error> {
+        if let Some(metadata) = self.metadata.get() {
+            return Ok(metadata);
+        }
+        let metadata =
+            deserialize_footer_metadata(&self.serialized_metadata, &self.postscript, &self.limits)?;
+        Ok(self.metadata.get_or_init(|| metadata))
+    }
+
+    /// Returns the version of the writer of this file.
+    pub fn writer_version(&self) -> WriterVersion {
+        self.writer_version
+    }
+
+    /// Returns the [`Limits`] used to read this file.
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Returns the maximum size of a (decompressed) chunk of this file, i.e. its compression
+    /// block size bounded by [`Limits::max_chunk_size`].
+    pub fn max_chunk_size(&self) -> usize {
+        max_chunk_size(&self.postscript, &self.limits)
+    }
+
+    /// Returns the serialized (and possibly compressed) [`Metadata`] of this file.
+    pub fn serialized_metadata(&self) -> &[u8] {
+        &self.serialized_metadata
+    }
 }
 
+/// Reads the file's metadata with the default [`ReadOptions`].
+/// See [`read_metadata_with_options`].
 pub fn read_metadata<R>(reader: &mut R) -> Result<FileMetadata, Error>
+where
+    R: Read + Seek,
+{
+    read_metadata_with_options(reader, &ReadOptions::default())
+}
+
+/// Reads and deserializes the file's metadata.
+/// # Implementation
+/// This function performs one read of [`ReadOptions::initial_tail_size`] bytes and a second
+/// read only when the tail is larger than that. See [`read_tail`] for the additional read
+/// of files without magic in their postscript.
+pub fn read_metadata_with_options<R>(
+    reader: &mut R,
+    options: &ReadOptions,
+) -> Result<FileMetadata, Error>
+where
+    R: Read + Seek,
+{
+    let tail = read_tail(reader, options)?;
+    deserialize_tail_with_options(&tail, options)
+}
+
+/// Reads the file's tail, i.e. the serialized metadata, footer, postscript and
+/// postscript length, in this order, from `reader`.
+///
+/// The tail can be cached and deserialized via [`deserialize_tail`] without
+/// re-reading the file.
+/// # Implementation
+/// This function performs one read of [`ReadOptions::initial_tail_size`] bytes and a second
+/// read only when the tail is larger than that. When the postscript has no magic (Hive 0.11)
+/// and [`ReadOptions::allow_headerless`] is false, the magic at the beginning of the file is
+/// checked, which requires a third read unless the whole file was already read, i.e. this
+/// function performs at most three reads.
+/// # Errors
+/// This function errors when the tail is larger than [`Limits::max_tail_size`].
+pub fn read_tail<R>(reader: &mut R, options: &ReadOptions) -> Result<Vec<u8>, Error>
 where
     R: Read + Seek,
 {
     let file_len = stream_len(reader)?;
+    if file_len == 0 {
+        return Err(Error::OutOfSpec("the file is empty"));
+    }
 
-    // initial read of the footer
-    let footer_len = if file_len < DEFAULT_FOOTER_SIZE {
-        file_len
-    } else {
-        DEFAULT_FOOTER_SIZE
-    };
+    // initial read of the tail, which always contains the postscript
+    let tail_len = options
+        .initial_tail_size
+        .max(MAX_POSTSCRIPT_SIZE)
+        .min(file_len);
 
-    reader.seek(SeekFrom::End(-(footer_len as i64)))?;
-    let mut tail_bytes = Vec::with_capacity(footer_len as usize);
-    reader.take(footer_len).read_to_end(&mut tail_bytes)?;
+    reader.seek(SeekFrom::End(-(tail_len as i64)))?;
+    let mut tail_bytes = Vec::with_capacity(tail_len as usize);
+    reader.take(tail_len).read_to_end(&mut tail_bytes)?;
 
     // The final byte of the file contains the serialized length of the Postscript,
     // which must be less than 256 bytes.
-    let postscript_len = tail_bytes[tail_bytes.len() - 1] as usize;
-    tail_bytes.truncate(tail_bytes.len() - 1);
+    let postscript_len = *tail_bytes
+        .last()
+        .ok_or(Error::OutOfSpec("could not read the postscript length"))?;
+    let required = 1 + postscript_len as u64;
+    if required > tail_bytes.len() as u64 {
+        return Err(Error::OutOfSpec("the postscript is larger than the file"));
+    }
+
+    let (postscript, _) = split_postscript(&tail_bytes)?;
+    if !has_magic(&postscript)? && !options.allow_headerless {
+        validate_header(reader, &tail_bytes, file_len)?;
+    }
 
-    // next is the postscript
-    let postscript = PostScript::decode(&tail_bytes[tail_bytes.len() - postscript_len..])?;
-    tail_bytes.truncate(tail_bytes.len() - postscript_len);
+    let required = required
+        .checked_add(postscript.footer_length())
+        .and_then(|required| required.checked_add(postscript.metadata_length()))
+        .ok_or(Error::OutOfSpec("the footer and metadata lengths overflow"))?;
+    if required > options.limits.max_tail_size {
+        return Err(Error::LimitExceeded(
+            "the file's tail is larger than the maximum tail size",
+        ));
+    }
+    read_tail_prefix(reader, &mut tail_bytes, required, file_len)?;
 
-    // next is the footer
-    let footer_length = postscript.footer_length.ok_or(Error::OutOfSpec)? as usize; // todo: throw error
+    // remove everything before the metadata
+    tail_bytes.drain(..tail_bytes.len() - required as usize);
+    Ok(tail_bytes)
+}
+
+/// Ensures that `tail_bytes` contains the last `required` bytes of the file,
+/// reading the missing bytes when needed.
+fn read_tail_prefix<R>(
+    reader: &mut R,
+    tail_bytes: &mut Vec<u8>,
+    required: u64,
+    file_len: u64,
+) -> Result<(), Error>
+where
+    R: Read + Seek,
+{
+    if required > file_len {
+        return Err(Error::OutOfSpec("the file's tail is larger than the file"));
+    }
+    let existing = tail_bytes.len() as u64;
+    if required <= existing {
+        return Ok(());
+    }
+    let additional = required - existing;
 
-    let footer = &tail_bytes[tail_bytes.len() - footer_length..];
-    let footer = deserialize_footer(footer, postscript.compression())?;
-    tail_bytes.truncate(tail_bytes.len() - footer_length);
+    reader.seek(SeekFrom::End(-(required as i64)))?;
+    let mut bytes = Vec::with_capacity(required as usize);
+    reader.take(additional).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != additional {
+        return Err(Error::OutOfSpec("could not read the file's tail"));
+    }
+    bytes.extend_from_slice(tail_bytes);
+    *tail_bytes = bytes;
+    Ok(())
+}
+
+/// Splits the tail into the [`PostScript`] and the remaining bytes before it.
+fn split_postscript(tail: &[u8]) -> Result<(PostScript, &[u8]), Error> {
+    let (postscript_len, tail) = tail.split_last().ok_or(Error::OutOfSpec(
+        "the tail must contain the postscript length",
+    ))?;
+    let postscript_len = *postscript_len as usize;
+
+    let start = tail
+        .len()
+        .checked_sub(postscript_len)
+        .ok_or(Error::OutOfSpec("the postscript is larger than the tail"))?;
+    let (tail, postscript) = tail.split_at(start);
+    Ok((PostScript::decode(postscript)?, tail))
+}
+
+/// Returns whether the postscript ends with the magic, erroring if it ends with something else.
+fn has_magic(postscript: &PostScript) -> Result<bool, Error> {
+    match postscript.magic.as_deref() {
+        Some(MAGIC) => Ok(true),
+        Some(_) => Err(Error::NotOrc),
+        None => Ok(false),
+    }
+}
+
+/// Validates that the file starts with the magic, reading it unless it is part of `tail_bytes`.
+fn validate_header<R>(reader: &mut R, tail_bytes: &[u8], file_len: u64) -> Result<(), Error>
+where
+    R: Read + Seek,
+{
+    let mut header = [0u8; 3];
+    if tail_bytes.len() as u64 == file_len {
+        header.copy_from_slice(tail_bytes.get(..3).ok_or(Error::NotOrc)?);
+    } else {
+        reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut header)?;
+    }
+    if header == MAGIC.as_bytes() {
+        Ok(())
+    } else {
+        Err(Error::NotOrc)
+    }
+}
+
+/// Deserializes the file's tail (as returned by [`read_tail`]) into [`FileMetadata`]
+/// with the default [`ReadOptions`]. See [`deserialize_tail_with_options`].
+pub fn deserialize_tail(tail: &[u8]) -> Result<FileMetadata, Error> {
+    deserialize_tail_with_options(tail, &ReadOptions::default())
+}
+
+/// Deserializes the file's tail (as returned by [`read_tail`]) into [`FileMetadata`].
+/// The [`Limits`] of `options` are used for this and all subsequent reads of the file.
+/// # Errors
+/// This function errors when the postscript's magic is not "ORC", when the file's version
+/// is not supported, and when its writer version is unknown (unless
+/// [`ReadOptions::allow_headerless`]).
+pub fn deserialize_tail_with_options(
+    tail: &[u8],
+    options: &ReadOptions,
+) -> Result<FileMetadata, Error> {
+    let limits = options.limits;
+    let (postscript, tail) = split_postscript(tail)?;
+    has_magic(&postscript)?;
+    validate_version(&postscript.version)?;
+    let writer_version = match postscript.writer_version().into() {
+        WriterVersion::Future(version) if !options.allow_headerless => {
+            return Err(Error::UnknownWriterVersion(version))
+        }
+        writer_version => writer_version,
+    };
+
+    // next is the footer
+    let footer_length = postscript.footer_length.ok_or(Error::OutOfSpec(
+        "the postscript must contain the footer length",
+    ))? as usize;
+    let start = tail
+        .len()
+        .checked_sub(footer_length)
+        .ok_or(Error::OutOfSpec("the footer is larger than the tail"))?;
+    let (tail, footer) = tail.split_at(start);
+    let footer = deserialize_footer(footer, &postscript, &limits)?;
+    types::validate_types(&footer.types)?;
 
     // finally the metadata
-    let metadata_length = postscript.metadata_length.ok_or(Error::OutOfSpec)? as usize; // todo: throw error
-    let metadata = &tail_bytes[tail_bytes.len() - metadata_length..];
-    let metadata = deserialize_footer_metadata(metadata, postscript.compression())?;
+    let metadata_length = postscript.metadata_length() as usize;
+    let start = tail
+        .len()
+        .checked_sub(metadata_length)
+        .ok_or(Error::OutOfSpec("the metadata is larger than the tail"))?;
+    let serialized_metadata = tail[start..].to_vec();
 
     Ok(FileMetadata {
         postscript,
         footer,
-        metadata,
+        serialized_metadata,
+        metadata: OnceLock::new(),
+        writer_version,
+        limits,
     })
 }
 
@@ -98,86 +399,572 @@ pub fn read_stripe_footer<R: Read + Seek>(
     stripe: usize,
     scratch: &mut Vec<u8>,
 ) -> Result<StripeFooter, Error> {
-    let stripe = &metadata.footer.stripes[stripe];
+    let information = get_stripe(metadata, stripe)?;
+    let location = Location {
+        stripe: Some(stripe),
+        offset: Some(information.offset()),
+        ..Default::default()
+    };
+    read_stripe_footer_impl(reader, metadata, information, scratch)
+        .map_err(|error| error.at(location))
+}
 
-    let start = stripe.offset() + stripe.index_length() + stripe.data_length();
+fn read_stripe_footer_impl<R: Read + Seek>(
+    reader: &mut R,
+    metadata: &FileMetadata,
+    stripe: &StripeInformation,
+    scratch: &mut Vec<u8>,
+) -> Result<StripeFooter, Error> {
+    let start = stripe
+        .offset()
+        .checked_add(stripe.index_length())
+        .and_then(|start| start.checked_add(stripe.data_length()))
+        .ok_or(Error::OutOfSpec("the stripe's footer offset overflows"))?;
     let len = stripe.footer_length();
+    if len > metadata.limits.max_stream_size {
+        return Err(Error::LimitExceeded(
+            "the stripe's footer is larger than the maximum stream size",
+        ));
+    }
     reader.seek(SeekFrom::Start(start))?;
 
     scratch.clear();
-    scratch.reserve(len as usize);
     reader.take(len).read_to_end(scratch)?;
-    deserialize_stripe_footer(scratch, metadata.postscript.compression())
+    if scratch.len() as u64 != len {
+        return Err(Error::OutOfSpec("could not read the stripe's footer"));
+    }
+    deserialize_stripe_footer(scratch, &metadata.postscript, &metadata.limits)
+}
+
+/// Returns the [`StripeInformation`] of `stripe`, validating its number of rows
+/// against [`Limits::max_values`].
+pub(crate) fn get_stripe(
+    metadata: &FileMetadata,
+    stripe: usize,
+) -> Result<&StripeInformation, Error> {
+    let information = metadata
+        .footer
+        .stripes
+        .get(stripe)
+        .ok_or(Error::InvalidStripe(stripe))?;
+    metadata
+        .limits
+        .check_values(information.number_of_rows())
+        .map_err(|error| {
+            error.at(Location {
+                stripe: Some(stripe),
+                ..Default::default()
+            })
+        })?;
+    Ok(information)
+}
+
+/// Whether streams of `kind` belong to the index region of a stripe, before its data
+pub(crate) fn is_index(kind: Kind) -> bool {
+    matches!(
+        kind,
+        Kind::RowIndex | Kind::BloomFilter | Kind::BloomFilterUtf8
+    )
+}
+
+/// Returns the start (relative to the stripe's offset) and length of the region
+/// containing all non-index streams of `column`.
+///
+/// This also validates that the stripe's footer contains the encoding of `column` and that
+/// the column is within `limits`.
+fn column_range(footer: &StripeFooter, column: u32, limits: &Limits) -> Result<(u64, u64), Error> {
+    let encoding = footer
+        .columns
+        .get(column as usize)
+        .ok_or(Error::InvalidColumn(column))?;
+    if encoding.dictionary_size() as usize > limits.max_dictionary_size {
+        return Err(Error::LimitExceeded(
+            "the column's dictionary is larger than the maximum dictionary size",
+        ));
+    }
+    let overflow = || Error::OutOfSpec("the length of the stripe's streams overflows");
+
+    let mut start = 0u64; // the start of the stream
+    let mut range = None;
+    for stream in &footer.streams {
+        if stream.column() == column && !is_index(stream.kind()) {
+            let (_, length) = range.get_or_insert((start, 0u64));
+            *length = length.checked_add(stream.length()).ok_or_else(overflow)?;
+        }
+        start = start.checked_add(stream.length()).ok_or_else(overflow)?;
+    }
+
+    // columns without streams (e.g. structs without nulls) are empty
+    let (start, length) = range.unwrap_or_default();
+    if length > limits.max_stream_size {
+        return Err(Error::LimitExceeded(
+            "the column's streams are larger than the maximum stream size",
+        ));
+    }
+    Ok((start, length))
 }
 
 /// Reads `column` from the stripe into a [`Column`].
 /// `scratch` becomes owned by [`Column`], which you can recover via `into_inner`.
+///
+/// `footer` is either owned or an [`Arc`] shared with the other columns of the stripe,
+/// which avoids copying it for every column.
 /// # Implementation
 /// This function is guaranteed to perform exactly one seek and one read to `reader`.
 pub fn read_stripe_column<R: Read + Seek>(
     reader: &mut R,
     metadata: &FileMetadata,
     stripe: usize,
-    footer: StripeFooter,
+    footer: impl Into<Arc<StripeFooter>>,
     column: u32,
-    mut scratch: Vec<u8>,
+    scratch: Vec<u8>,
 ) -> Result<Column, Error> {
-    let stripe = &metadata.footer.stripes[stripe];
-
-    let mut start = 0; // the start of the stream
-
-    let start = footer
-        .streams
-        .iter()
-        .map(|stream| {
-            start += stream.length();
-            (start, stream)
-        })
-        .find(|(_, stream)| stream.column() == column && stream.kind() != Kind::RowIndex)
-        .map(|(start, stream)| start - stream.length())
-        .ok_or(Error::InvalidColumn(column))?;
+    let information = get_stripe(metadata, stripe)?;
+    let location = Location {
+        stripe: Some(stripe),
+        column: Some(column),
+        offset: Some(information.offset()),
+        ..Default::default()
+    };
+    read_stripe_column_impl(
+        reader,
+        metadata,
+        (stripe, information),
+        footer.into(),
+        column,
+        scratch,
+    )
+    .map_err(|error| error.at(location))
+}
 
-    let length = footer
-        .streams
-        .iter()
-        .filter(|stream| stream.column() == column && stream.kind() != Kind::RowIndex)
-        .fold(0, |acc, stream| acc + stream.length());
+fn read_stripe_column_impl<R: Read + Seek>(
+    reader: &mut R,
+    metadata: &FileMetadata,
+    (stripe_index, stripe): (usize, &StripeInformation),
+    footer: Arc<StripeFooter>,
+    column: u32,
+    mut scratch: Vec<u8>,
+) -> Result<Column, Error> {
+    let (start, length) = column_range(&footer, column, &metadata.limits)?;
 
-    let start = stripe.offset() + start;
+    let start = stripe
+        .offset()
+        .checked_add(start)
+        .ok_or(Error::OutOfSpec("the column's offset overflows"))?;
     reader.seek(SeekFrom::Start(start))?;
 
     scratch.clear();
-    scratch.reserve(length as usize);
     reader.take(length).read_to_end(&mut scratch)?;
+    if scratch.len() as u64 != length {
+        return Err(Error::OutOfSpec("could not read the column's streams"));
+    }
     Ok(Column::new(
         scratch,
+        metadata,
+        stripe_index,
+        start,
         column,
         stripe.number_of_rows(),
         footer,
-        metadata.postscript.compression(),
     ))
 }
 
-fn deserialize_footer(bytes: &[u8], compression: CompressionKind) -> Result<Footer, Error> {
-    let mut buffer = vec![];
-    decompress::Decompressor::new(bytes, compression, vec![]).read_to_end(&mut buffer)?;
+/// Returns `column` from the stripe as a [`Column`] borrowing from `data`, where `data`
+/// is the whole file (e.g. a file read to memory or memory-mapped).
+/// # Implementation
+/// Contrarily to [`read_stripe_column`], this function performs no copy of the column's
+/// streams. The chunks of uncompressed files and chunks stored as original are returned as
+/// slices of `data` by [`decompress::Decompressor::next_chunk`] of
+/// [`Column::get_stream_slice`]. Note that the decoders in [`decode`] consume [`Read`] and
+/// thus still copy the bytes they decode.
+pub fn slice_stripe_column<'a>(
+    data: &'a [u8],
+    metadata: &FileMetadata,
+    stripe: usize,
+    footer: impl Into<Arc<StripeFooter>>,
+    column: u32,
+) -> Result<Column<&'a [u8]>, Error> {
+    let footer = footer.into();
+    let stripe_index = stripe;
+    let stripe = get_stripe(metadata, stripe)?;
+    let location = Location {
+        stripe: Some(stripe_index),
+        column: Some(column),
+        offset: Some(stripe.offset()),
+        ..Default::default()
+    };
+
+    let (start, length) =
+        column_range(&footer, column, &metadata.limits).map_err(|error| error.at(location))?;
+
+    let outside = || Error::OutOfSpec("the column's streams are outside of the file").at(location);
+    let start = stripe.offset().checked_add(start).ok_or_else(outside)?;
+    let data = start
+        .checked_add(length)
+        .and_then(|end| data.get(start as usize..end as usize))
+        .ok_or_else(outside)?;
+
+    Ok(Column::new(
+        data,
+        metadata,
+        stripe_index,
+        start,
+        column,
+        stripe.number_of_rows(),
+        footer,
+    ))
+}
+
+fn deserialize_footer(
+    bytes: &[u8],
+    postscript: &PostScript,
+    limits: &Limits,
+) -> Result<Footer, Error> {
+    let buffer = decompress(bytes, postscript, limits.max_tail_size, limits)?;
     Ok(Footer::decode(&*buffer)?)
 }
 
 fn deserialize_footer_metadata(
     bytes: &[u8],
-    compression: CompressionKind,
+    postscript: &PostScript,
+    limits: &Limits,
 ) -> Result<Metadata, Error> {
-    let mut buffer = vec![];
-    decompress::Decompressor::new(bytes, compression, vec![]).read_to_end(&mut buffer)?;
+    let buffer = decompress(bytes, postscript, limits.max_tail_size, limits)?;
     Ok(Metadata::decode(&*buffer)?)
 }
 
 fn deserialize_stripe_footer(
     bytes: &[u8],
-    compression: CompressionKind,
+    postscript: &PostScript,
+    limits: &Limits,
 ) -> Result<StripeFooter, Error> {
-    let mut buffer = vec![];
-    decompress::Decompressor::new(bytes, compression, vec![]).read_to_end(&mut buffer)?;
+    let buffer = decompress(bytes, postscript, limits.max_stream_size, limits)?;
     Ok(StripeFooter::decode(&*buffer)?)
 }
+
+/// Returns the maximum size of a chunk of the file: its compression block size,
+/// bounded by [`Limits::max_chunk_size`].
+fn max_chunk_size(postscript: &PostScript, limits: &Limits) -> usize {
+    postscript
+        .compression_block_size
+        .and_then(|size| usize::try_from(size).ok())
+        .map_or(limits.max_chunk_size, |size| {
+            size.min(limits.max_chunk_size)
+        })
+}
+
+/// Decompresses `bytes`, erroring when the result is larger than `max_size`.
+fn decompress(
+    bytes: &[u8],
+    postscript: &PostScript,
+    max_size: u64,
+    limits: &Limits,
+) -> Result<Vec<u8>, Error> {
+    let mut buffer = vec![];
+    decompress::Decompressor::new(bytes, postscript.compression(), vec![])
+        .with_max_chunk_size(max_chunk_size(postscript, limits))
+        .take(max_size.saturating_add(1))
+        .read_to_end(&mut buffer)?;
+    if buffer.len() as u64 > max_size {
+        return Err(Error::LimitExceeded(
+            "a decompressed region is larger than its maximum size",
+        ));
+    }
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::proto::r#type::Kind as TypeKind;
+    use crate::proto::{
+        ColumnStatistics, CompressionKind, StripeInformation, StripeStatistics, UserMetadataItem,
+    };
+    use crate::test_util::{self, type_};
+
+    use super::*;
+
+    /// Returns a valid file whose footer has `size` bytes of user metadata
+    fn file_with_footer(size: usize) -> Vec<u8> {
+        new_file(
+            size,
+            PostScript {
+                version: vec![0, 12],
+                writer_version: Some(1),
+                magic: Some("ORC".to_string()),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Returns an (uncompressed) file whose footer has `size` bytes of user metadata and
+    /// declares a single empty stripe, with its statistics in the metadata
+    fn new_file(size: usize, postscript: PostScript) -> Vec<u8> {
+        let footer = Footer {
+            stripes: vec![StripeInformation::default()],
+            metadata: vec![UserMetadataItem {
+                name: Some("a".to_string()),
+                value: Some(vec![1; size]),
+            }],
+            ..Default::default()
+        };
+        let footer = footer.encode_to_vec();
+        let metadata = Metadata {
+            stripe_stats: vec![StripeStatistics {
+                col_stats: vec![ColumnStatistics {
+                    number_of_values: Some(1),
+                    ..Default::default()
+                }],
+            }],
+        }
+        .encode_to_vec();
+        let postscript = PostScript {
+            footer_length: Some(footer.len() as u64),
+            compression: Some(CompressionKind::None as i32),
+            metadata_length: Some(metadata.len() as u64),
+            ..postscript
+        }
+        .encode_to_vec();
+
+        let mut file = b"ORC".to_vec();
+        file.extend(metadata);
+        file.extend(footer);
+        file.extend_from_slice(&postscript);
+        file.push(postscript.len() as u8);
+        file
+    }
+
+    #[test]
+    fn tail_larger_than_initial_read() {
+        let file = file_with_footer(100_000);
+
+        let options = ReadOptions {
+            initial_tail_size: 10,
+            ..Default::default()
+        };
+        let tail = read_tail(&mut Cursor::new(&file), &options).unwrap();
+        assert_eq!(tail.as_slice(), &file[3..]);
+
+        let metadata = deserialize_tail(&tail).unwrap();
+        assert_eq!(
+            metadata.footer.metadata[0].value.as_ref().unwrap().len(),
+            100_000
+        );
+        assert_eq!(metadata.footer.stripes.len(), 1);
+    }
+
+    /// A reader counting the reads of the file's tail, i.e. the seeks from its end
+    struct CountingReader<'a> {
+        inner: Cursor<&'a [u8]>,
+        reads: usize,
+    }
+
+    impl Read for CountingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.inner.read(buf)
+        }
+    }
+
+    impl Seek for CountingReader<'_> {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            if matches!(pos, SeekFrom::End(offset) if offset < 0) {
+                self.reads += 1;
+            }
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn tail_is_read_at_most_twice() {
+        let file = file_with_footer(100_000);
+        for initial_tail_size in [0, 10, 1000, 1_000_000] {
+            let mut reader = CountingReader {
+                inner: Cursor::new(&file),
+                reads: 0,
+            };
+            let options = ReadOptions {
+                initial_tail_size,
+                ..Default::default()
+            };
+            let tail = read_tail(&mut reader, &options).unwrap();
+            assert_eq!(tail.as_slice(), &file[3..]);
+            assert!(reader.reads <= 2);
+        }
+    }
+
+    #[test]
+    fn tail_smaller_than_initial_read() {
+        let file = file_with_footer(10);
+
+        let tail = read_tail(&mut Cursor::new(&file), &ReadOptions::default()).unwrap();
+        assert_eq!(tail.as_slice(), &file[3..]);
+
+        let metadata = read_metadata(&mut Cursor::new(&file)).unwrap();
+        assert_eq!(
+            metadata.footer.metadata[0].value.as_ref().unwrap().len(),
+            10
+        );
+    }
+
+    #[test]
+    fn tail_out_of_file() {
+        let mut file = file_with_footer(10);
+        // remove the header and part of the footer
+        file.drain(..10);
+
+        assert!(read_metadata(&mut Cursor::new(&file)).is_err());
+        assert!(read_metadata(&mut Cursor::new(&[])).is_err());
+    }
+
+    #[test]
+    fn tail_larger_than_limit() {
+        let file = file_with_footer(100_000);
+
+        let mut options = ReadOptions::default();
+        options.limits.max_tail_size = 100_000;
+        let error = read_metadata_with_options(&mut Cursor::new(&file), &options).unwrap_err();
+        assert!(matches!(error, Error::LimitExceeded(_)));
+
+        options.limits.max_tail_size = file.len() as u64;
+        let metadata = read_metadata_with_options(&mut Cursor::new(&file), &options).unwrap();
+        assert_eq!(metadata.limits(), &options.limits);
+    }
+
+    #[test]
+    fn writer_version() {
+        let file = file_with_footer(10);
+
+        let metadata = read_metadata(&mut Cursor::new(&file)).unwrap();
+        assert_eq!(metadata.writer_version(), WriterVersion::Hive8732);
+    }
+
+    #[test]
+    fn invalid_magic() {
+        let file = new_file(
+            10,
+            PostScript {
+                magic: Some("ABC".to_string()),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(
+            read_metadata(&mut Cursor::new(&file)),
+            Err(Error::NotOrc)
+        ));
+    }
+
+    #[test]
+    fn headerless() {
+        // no magic in the postscript; the header is checked
+        let mut file = new_file(10, PostScript::default());
+        assert!(read_metadata(&mut Cursor::new(&file)).is_ok());
+
+        file[..3].copy_from_slice(b"ABC");
+        assert!(matches!(
+            read_metadata(&mut Cursor::new(&file)),
+            Err(Error::NotOrc)
+        ));
+        let options = ReadOptions {
+            allow_headerless: true,
+            ..Default::default()
+        };
+        assert!(read_metadata_with_options(&mut Cursor::new(&file), &options).is_ok());
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let file = new_file(
+            10,
+            PostScript {
+                version: vec![1, 0],
+                magic: Some("ORC".to_string()),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(
+            read_metadata(&mut Cursor::new(&file)),
+            Err(Error::UnsupportedVersion(1, 0))
+        ));
+
+        let file = new_file(
+            10,
+            PostScript {
+                writer_version: Some(1000),
+                magic: Some("ORC".to_string()),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(
+            read_metadata(&mut Cursor::new(&file)),
+            Err(Error::UnknownWriterVersion(1000))
+        ));
+        // files of newer writers are only read when asked for
+        let options = ReadOptions {
+            allow_headerless: true,
+            ..Default::default()
+        };
+        let metadata = read_metadata_with_options(&mut Cursor::new(&file), &options).unwrap();
+        assert_eq!(metadata.writer_version(), WriterVersion::Future(1000));
+    }
+
+    #[test]
+    fn slice_is_zero_copy() -> Result<(), Error> {
+        use crate::read::decompress::Chunk;
+        use crate::write::column::ColumnWriter;
+        use crate::write::{OrcWriter, WriteOptions};
+
+        // bytes that deflate can't shrink, so that their chunks are stored as original
+        let mut state = 1u64;
+        let value = (0..1000)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (state >> 56) as u8
+            })
+            .collect::<Vec<_>>();
+        for compression in [CompressionKind::None, CompressionKind::Zlib] {
+            let options = WriteOptions {
+                compression,
+                ..Default::default()
+            };
+            let types = test_util::struct_(&[("a", TypeKind::Binary)]);
+            let mut writer = OrcWriter::try_new(vec![], types, options)?;
+            let [ColumnWriter::Binary(column)] = writer.columns_mut() else {
+                unreachable!()
+            };
+            column.write(Some(&value));
+            writer.write_rows(1)?;
+            let file = writer.finish()?;
+
+            let mut reader = Cursor::new(file.as_slice());
+            let metadata = read_metadata(&mut reader)?;
+            let footer = read_stripe_footer(&mut reader, &metadata, 0, &mut vec![])?;
+            let column = slice_stripe_column(&file, &metadata, 0, footer, 1)?;
+            let mut stream = column.get_stream_slice(Kind::Data, vec![])?;
+            let Some(Chunk::Borrowed(chunk)) = stream.next_chunk()? else {
+                panic!("the chunk must be borrowed")
+            };
+            assert!(stream.next_chunk()?.is_none());
+            // the decoded bytes point into the file and outlive the column
+            drop(column);
+            assert_eq!(chunk, value);
+            let (file, chunk) = (file.as_ptr_range(), chunk.as_ptr_range());
+            assert!(file.start <= chunk.start && chunk.end <= file.end);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn types_must_be_a_tree() {
+        // every struct has its next type twice as child: recursing over it is exponential
+        let mut types = (0..40u32)
+            .map(|column| type_(TypeKind::Struct, vec![column + 1; 2], vec!["a", "b"]))
+            .collect::<Vec<_>>();
+        types.push(type_(TypeKind::Int, vec![], vec![]));
+        let file = test_util::file(types, &[vec![vec![]; 41]], 1, &[]);
+        assert!(matches!(
+            read_metadata(&mut Cursor::new(&file)),
+            Err(Error::OutOfSpec(_))
+        ));
+    }
+}
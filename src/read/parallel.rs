@@ -0,0 +1,217 @@
+//! Parallel decompression and decoding of stripes (requires the `rayon` feature).
+//!
+//! Reading a stripe is composed by I/O (reading its footer and columns) and CPU
+//! (decompressing and decoding its columns). [`StripeIterator`] performs the I/O sequentially,
+//! on the thread iterating it, and decodes the columns of up to `max_stripes_in_flight` stripes
+//! in parallel, on [`rayon`]'s thread pool.
+use std::collections::VecDeque;
+use std::io::{Read, Seek};
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::error::Error;
+
+use super::{read_stripe_column, read_stripe_footer, Column, FileMetadata};
+
+/// An [`Iterator`] of the decoded columns of every stripe of a file, in stripe order.
+///
+/// Each item contains, for a stripe, the result of `decode` of each column of the projection,
+/// in the order of the projection. At most `max_stripes_in_flight` stripes are in memory at
+/// any given time. The iterator stops after the first error reading the file.
+pub struct StripeIterator<'a, R, T, F> {
+    reader: &'a mut R,
+    metadata: &'a FileMetadata,
+    projection: &'a [u32],
+    decode: F,
+    max_stripes_in_flight: usize,
+    next_stripe: usize,
+    decoded: VecDeque<Result<Vec<T>, Error>>,
+    finished: bool,
+}
+
+/// Returns a [`StripeIterator`] decoding the columns `projection` of every stripe of the file
+/// with `decode`.
+///
+/// `decode` is called in parallel, once per stripe and column, and is responsible for
+/// decompressing and decoding the [`Column`], e.g. via [`Column::get_stream`].
+/// # Panics
+/// This function panics if `max_stripes_in_flight` is zero.
+pub fn read_stripes<'a, R, T, F>(
+    reader: &'a mut R,
+    metadata: &'a FileMetadata,
+    projection: &'a [u32],
+    max_stripes_in_flight: usize,
+    decode: F,
+) -> StripeIterator<'a, R, T, F>
+where
+    R: Read + Seek,
+    T: Send,
+    F: Fn(Column) -> Result<T, Error> + Sync,
+{
+    assert!(
+        max_stripes_in_flight > 0,
+        "at least one stripe must be in flight"
+    );
+    StripeIterator {
+        reader,
+        metadata,
+        projection,
+        decode,
+        max_stripes_in_flight,
+        next_stripe: 0,
+        decoded: VecDeque::with_capacity(max_stripes_in_flight),
+        finished: false,
+    }
+}
+
+impl<'a, R, T, F> StripeIterator<'a, R, T, F>
+where
+    R: Read + Seek,
+    T: Send,
+    F: Fn(Column) -> Result<T, Error> + Sync,
+{
+    /// Reads the columns of `stripe`
+    fn read_stripe(&mut self, stripe: usize) -> Result<Vec<Column>, Error> {
+        let footer = Arc::new(read_stripe_footer(
+            self.reader,
+            self.metadata,
+            stripe,
+            &mut vec![],
+        )?);
+        self.projection
+            .iter()
+            .map(|column| {
+                read_stripe_column(
+                    self.reader,
+                    self.metadata,
+                    stripe,
+                    Arc::clone(&footer),
+                    *column,
+                    vec![],
+                )
+            })
+            .collect()
+    }
+
+    /// Reads the next window of stripes and decodes them in parallel
+    fn next_window(&mut self) {
+        let number_of_stripes = self.metadata.footer.stripes.len();
+        let end = (self.next_stripe + self.max_stripes_in_flight).min(number_of_stripes);
+
+        let mut stripes = Vec::with_capacity(end - self.next_stripe);
+        let mut error = None;
+        for stripe in self.next_stripe..end {
+            match self.read_stripe(stripe) {
+                Ok(columns) => stripes.push(columns),
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+        self.next_stripe = end;
+
+        let decode = &self.decode;
+        let decoded = stripes
+            .into_par_iter()
+            .map(|columns| columns.into_par_iter().map(decode).collect())
+            .collect::<Vec<_>>();
+        self.decoded.extend(decoded);
+
+        if let Some(error) = error {
+            self.decoded.push_back(Err(error));
+            self.finished = true;
+        }
+        if self.next_stripe == number_of_stripes {
+            self.finished = true;
+        }
+    }
+}
+
+impl<'a, R, T, F> Iterator for StripeIterator<'a, R, T, F>
+where
+    R: Read + Seek,
+    T: Send,
+    F: Fn(Column) -> Result<T, Error> + Sync,
+{
+    type Item = Result<Vec<T>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.decoded.is_empty() && !self.finished {
+            self.next_window();
+        }
+        self.decoded.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::proto::stream::Kind;
+    use crate::read::read_metadata;
+    use crate::test_util::{self, Streams};
+
+    use super::*;
+
+    /// Returns an uncompressed file with `stripes` stripes, whose column `c` of stripe `s`
+    /// has a data stream with the bytes `[s, c]`.
+    fn file(stripes: u8) -> Vec<u8> {
+        let stripes = (0..stripes)
+            .map(|stripe| {
+                (0..2u8)
+                    .map(|column| vec![(Kind::Data, vec![stripe, column])])
+                    .collect()
+            })
+            .collect::<Vec<Streams>>();
+        test_util::file(vec![], &stripes, 2, &[])
+    }
+
+    fn decode(column: Column) -> Result<Vec<u8>, Error> {
+        let mut data = vec![];
+        column
+            .get_stream(Kind::Data, vec![])?
+            .read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    #[test]
+    fn in_order() -> Result<(), Error> {
+        let file = file(10);
+        let mut reader = Cursor::new(&file);
+        let metadata = read_metadata(&mut reader)?;
+
+        for max_stripes_in_flight in [1, 3, 10, 20] {
+            let stripes = read_stripes(
+                &mut reader,
+                &metadata,
+                &[1, 0],
+                max_stripes_in_flight,
+                decode,
+            )
+            .collect::<Result<Vec<_>, Error>>()?;
+
+            let expected = (0..10)
+                .map(|stripe| vec![vec![stripe, 1], vec![stripe, 0]])
+                .collect::<Vec<_>>();
+            assert_eq!(stripes, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn stops_on_error() -> Result<(), Error> {
+        let file = file(10);
+        let mut reader = Cursor::new(&file);
+        let metadata = read_metadata(&mut reader)?;
+
+        let stripes = read_stripes(&mut reader, &metadata, &[2], 3, decode).collect::<Vec<_>>();
+        assert_eq!(stripes.len(), 1);
+        assert!(matches!(
+            stripes[0].as_ref().unwrap_err().unlocated(),
+            Error::InvalidColumn(2)
+        ));
+        Ok(())
+    }
+}
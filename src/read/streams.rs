@@ -0,0 +1,109 @@
+//! Decoding of whole streams of a [`Column`], shared by the deserializers of the crate's features.
+use crate::error::Error;
+use crate::proto::column_encoding::Kind as ColumnEncodingKind;
+use crate::proto::r#type::Kind as TypeKind;
+use crate::proto::stream::Kind;
+
+use super::decode::{SignedRleV2Iter, UnsignedRleV2Iter};
+use super::{Column, ORC_EPOCH};
+
+/// The names of the timezones equal to UTC
+const UTC: [&str; 8] = [
+    "UTC", "GMT", "Etc/UTC", "Etc/GMT", "UCT", "Etc/UCT", "Zulu", "Etc/Zulu",
+];
+
+/// Errors unless `column` is encoded with RLE v2
+pub(crate) fn check_v2(column: &Column) -> Result<(), Error> {
+    match column.encoding().kind() {
+        ColumnEncodingKind::DirectV2 | ColumnEncodingKind::DictionaryV2 => Ok(()),
+        ColumnEncodingKind::Direct | ColumnEncodingKind::Dictionary => {
+            Err(Error::NotYetImplemented("RLE v1 decoding"))
+        }
+    }
+}
+
+/// Returns `length` signed integers of the stream `kind` of `column`
+pub(crate) fn signed(column: &Column, kind: Kind, length: usize) -> Result<Vec<i64>, Error> {
+    check_v2(column)?;
+    let mut values = vec![];
+    SignedRleV2Iter::new(column.get_stream(kind, vec![])?, length, vec![])
+        .extend_into(&mut values)?;
+    Ok(values)
+}
+
+/// Returns `length` unsigned integers of the stream `kind` of `column`
+pub(crate) fn unsigned(column: &Column, kind: Kind, length: usize) -> Result<Vec<u64>, Error> {
+    check_v2(column)?;
+    let mut values = vec![];
+    UnsignedRleV2Iter::new(column.get_stream(kind, vec![])?, length, vec![])
+        .extend_into(&mut values)?;
+    Ok(values)
+}
+
+/// Decodes the nanoseconds of a timestamp, whose 3 least significant bits encode the number
+/// of trailing zeros (minus one) that were removed.
+pub(crate) fn nanoseconds(encoded: u64) -> Result<i64, Error> {
+    let zeros = (encoded & 7) as u32;
+    let value = encoded >> 3;
+    let value = if zeros == 0 {
+        Some(value)
+    } else {
+        value.checked_mul(10u64.pow(zeros + 1))
+    };
+    value
+        .filter(|value| *value < 1_000_000_000)
+        .map(|value| value as i64)
+        .ok_or(Error::OutOfSpec(
+            "the nanoseconds of a timestamp must be below one second",
+        ))
+}
+
+/// Returns `length` timestamps of `column` of type `kind`, in nanoseconds since the unix epoch.
+///
+/// The timestamps of [`TypeKind::Timestamp`] are stored relative to 2015-01-01 in the writer's
+/// timezone, which are only supported when it is UTC (or not declared, as by old writers).
+pub(crate) fn timestamps(
+    column: &Column,
+    kind: TypeKind,
+    length: usize,
+) -> Result<Vec<i64>, Error> {
+    if kind == TypeKind::Timestamp
+        && column
+            .writer_timezone()
+            .is_some_and(|timezone| !UTC.contains(&timezone))
+    {
+        return Err(Error::NotYetImplemented(
+            "timestamps written in a timezone other than UTC",
+        ));
+    }
+    let seconds = signed(column, Kind::Data, length)?;
+    let nanoseconds = unsigned(column, Kind::Secondary, length)?;
+    let overflow = || Error::OutOfSpec("a timestamp is out of the range of nanoseconds");
+    seconds
+        .into_iter()
+        .zip(nanoseconds)
+        .map(|(seconds, nanoseconds)| {
+            let nanoseconds = self::nanoseconds(nanoseconds)?;
+            let mut seconds = seconds.checked_add(ORC_EPOCH).ok_or_else(overflow)?;
+            // writers truncate negative timestamps' milliseconds towards zero
+            if seconds < 0 && nanoseconds > 999_999 {
+                seconds -= 1;
+            }
+            seconds
+                .checked_mul(1_000_000_000)
+                .and_then(|x| x.checked_add(nanoseconds))
+                .ok_or_else(overflow)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn nanoseconds() {
+        assert_eq!(super::nanoseconds(1 << 3).unwrap(), 1);
+        assert_eq!(super::nanoseconds((5 << 3) | 7).unwrap(), 500_000_000);
+        assert_eq!(super::nanoseconds((12 << 3) | 1).unwrap(), 1200);
+        assert!(super::nanoseconds((10 << 3) | 7).is_err());
+    }
+}
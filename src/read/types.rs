@@ -0,0 +1,105 @@
+//! Navigation of the file's types, shared by the readers and writers of the crate.
+use crate::error::Error;
+use crate::proto::{r#type::Kind, Type};
+
+/// Returns the [`Type`] of `column`
+pub(crate) fn get_type(types: &[Type], column: u32) -> Result<&Type, Error> {
+    types
+        .get(column as usize)
+        .ok_or(Error::InvalidColumn(column))
+}
+
+/// Returns the children of `column`, validating that they are after `column`
+/// (types are stored in pre-order), so that recursing over them terminates.
+pub(crate) fn children(types: &[Type], column: u32) -> Result<&[u32], Error> {
+    let subtypes = &get_type(types, column)?.subtypes;
+    if subtypes
+        .iter()
+        .any(|child| *child <= column || *child as usize >= types.len())
+    {
+        return Err(Error::OutOfSpec("the children of a type must be after it"));
+    }
+    Ok(subtypes)
+}
+
+/// Validates that `types` form a tree stored in pre-order: every type but the root is a
+/// child of exactly one type, and the children of a type are strictly increasing, each
+/// starting right after the subtree of the previous one. Recursing over the types from
+/// the root then visits every type once.
+pub(crate) fn validate_types(types: &[Type]) -> Result<(), Error> {
+    let error = || Error::OutOfSpec("the types must form a tree in pre-order");
+    let mut parents = vec![0u32; types.len()];
+    // the end (exclusive) of the subtree of each type, known for the types after it
+    let mut ends = vec![0; types.len()];
+    for (column, type_) in types.iter().enumerate().rev() {
+        let mut end = column + 1;
+        for child in &type_.subtypes {
+            let child = *child as usize;
+            if child != end || child >= types.len() {
+                return Err(error());
+            }
+            parents[child] += 1;
+            end = ends[child];
+        }
+        ends[column] = end;
+    }
+    let is_tree = ends.first().is_none_or(|end| *end == types.len())
+        && parents.iter().skip(1).all(|parents| *parents == 1);
+    if is_tree {
+        Ok(())
+    } else {
+        Err(error())
+    }
+}
+
+/// Returns the top-level columns of the file, i.e. the children of its root struct, in order.
+pub fn top_level_columns(types: &[Type]) -> Result<&[u32], Error> {
+    let root = get_type(types, 0)?;
+    if root.kind() != Kind::Struct {
+        return Err(Error::OutOfSpec("the root type must be a struct"));
+    }
+    children(types, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::type_;
+
+    use super::*;
+
+    #[test]
+    fn tree() {
+        let types = vec![
+            type_(Kind::Struct, vec![1, 2, 4], vec!["a", "b", "c"]),
+            type_(Kind::Int, vec![], vec![]),
+            type_(Kind::List, vec![3], vec![]),
+            type_(Kind::Int, vec![], vec![]),
+            type_(Kind::Map, vec![5, 6], vec![]),
+            type_(Kind::String, vec![], vec![]),
+            type_(Kind::Int, vec![], vec![]),
+        ];
+        assert!(validate_types(&types).is_ok());
+        assert!(validate_types(&[]).is_ok());
+
+        let invalid = |subtypes: &[&[u32]]| {
+            let types = subtypes
+                .iter()
+                .map(|subtypes| type_(Kind::Struct, subtypes.to_vec(), vec![]))
+                .collect::<Vec<_>>();
+            validate_types(&types).is_err()
+        };
+        // a type referenced twice (a DAG)
+        assert!(invalid(&[&[1, 1], &[2, 2], &[]]));
+        // a child before its parent
+        assert!(invalid(&[&[1], &[0]]));
+        // children out of order or inside the subtree of a previous child
+        assert!(invalid(&[&[2, 1], &[], &[]]));
+        assert!(invalid(&[&[1, 2], &[2], &[]]));
+        // children skipping a type
+        assert!(invalid(&[&[2], &[], &[]]));
+        // a child that does not exist
+        assert!(invalid(&[&[1]]));
+        // a type that is not a descendant of the root
+        assert!(invalid(&[&[1], &[], &[]]));
+    }
+}
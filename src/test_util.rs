@@ -0,0 +1,166 @@
+//! Encoders of synthetic (uncompressed) ORC files and builders of their types, used by the
+//! tests of the crate.
+use prost::Message;
+
+use crate::proto::column_encoding::Kind as ColumnEncodingKind;
+use crate::proto::r#type::Kind as TypeKind;
+use crate::proto::stream::Kind;
+use crate::proto::{
+    ColumnEncoding, CompressionKind, Footer, PostScript, Stream, StripeFooter, StripeInformation,
+    Type,
+};
+
+/// The streams of each column of a stripe
+pub type Streams = Vec<Vec<(Kind, Vec<u8>)>>;
+
+/// Encodes `values` as a single RLE v2 direct run of 64 bits
+pub fn uints(values: &[u64]) -> Vec<u8> {
+    let length = values.len() - 1;
+    let mut bytes = vec![0x40 | (31 << 1) | (length >> 8) as u8, length as u8];
+    bytes.extend(values.iter().flat_map(|x| x.to_be_bytes()));
+    bytes
+}
+
+pub fn ints(values: &[i64]) -> Vec<u8> {
+    let values = values
+        .iter()
+        .map(|x| ((x << 1) ^ (x >> 63)) as u64)
+        .collect::<Vec<_>>();
+    uints(&values)
+}
+
+/// Encodes `values` as byte run-length literals
+pub fn bytes(values: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![(-(values.len() as i8)) as u8];
+    bytes.extend(values);
+    bytes
+}
+
+pub fn bools(values: &[bool]) -> Vec<u8> {
+    let packed = values
+        .chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |acc, (i, x)| acc | ((*x as u8) << (7 - i)))
+        })
+        .collect::<Vec<_>>();
+    bytes(&packed)
+}
+
+pub fn type_(kind: TypeKind, subtypes: Vec<u32>, field_names: Vec<&str>) -> Type {
+    Type {
+        kind: Some(kind as i32),
+        subtypes,
+        field_names: field_names.into_iter().map(|x| x.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+/// Returns the types of a struct whose fields (name and kind) are all primitive
+pub fn struct_(fields: &[(&str, TypeKind)]) -> Vec<Type> {
+    let (names, kinds): (Vec<_>, Vec<_>) = fields.iter().copied().unzip();
+    let mut types = vec![type_(
+        TypeKind::Struct,
+        (1..=fields.len() as u32).collect(),
+        names,
+    )];
+    types.extend(kinds.into_iter().map(|kind| type_(kind, vec![], vec![])));
+    types
+}
+
+/// Returns a decimal type of `precision` and `scale`
+pub fn decimal(precision: u32, scale: u32) -> Type {
+    Type {
+        precision: Some(precision),
+        scale: Some(scale),
+        ..type_(TypeKind::Decimal, vec![], vec![])
+    }
+}
+
+/// Returns an uncompressed file of `types` with one stripe of `rows` rows per item of `stripes`.
+/// The columns in `dictionaries` (column and dictionary size) are dictionary-encoded.
+pub fn file(
+    types: Vec<Type>,
+    stripes: &[Streams],
+    rows: u64,
+    dictionaries: &[(usize, u32)],
+) -> Vec<u8> {
+    file_with_timezone(types, stripes, rows, dictionaries, None)
+}
+
+/// Returns the same file as [`file`], whose stripes declare `writer_timezone`.
+pub fn file_with_timezone(
+    types: Vec<Type>,
+    stripes: &[Streams],
+    rows: u64,
+    dictionaries: &[(usize, u32)],
+    writer_timezone: Option<&str>,
+) -> Vec<u8> {
+    let mut file = b"ORC".to_vec();
+    let mut information = vec![];
+    for stripe in stripes {
+        let offset = file.len() as u64;
+        let mut streams = vec![];
+        let mut columns = vec![];
+        for (column, column_streams) in stripe.iter().enumerate() {
+            for (kind, data) in column_streams {
+                file.extend(data);
+                streams.push(Stream {
+                    kind: Some(*kind as i32),
+                    column: Some(column as u32),
+                    length: Some(data.len() as u64),
+                });
+            }
+            let dictionary_size = dictionaries
+                .iter()
+                .find(|(dictionary, _)| *dictionary == column)
+                .map(|(_, size)| *size);
+            let kind = if dictionary_size.is_some() {
+                ColumnEncodingKind::DictionaryV2
+            } else {
+                ColumnEncodingKind::DirectV2
+            };
+            columns.push(ColumnEncoding {
+                kind: Some(kind as i32),
+                dictionary_size,
+            });
+        }
+        let data_length = file.len() as u64 - offset;
+        let footer = StripeFooter {
+            streams,
+            columns,
+            writer_timezone: writer_timezone.map(|x| x.to_string()),
+        }
+        .encode_to_vec();
+        file.extend(&footer);
+        information.push(StripeInformation {
+            offset: Some(offset),
+            index_length: Some(0),
+            data_length: Some(data_length),
+            footer_length: Some(footer.len() as u64),
+            number_of_rows: Some(rows),
+        });
+    }
+    let footer = Footer {
+        stripes: information,
+        types,
+        ..Default::default()
+    }
+    .encode_to_vec();
+    file.extend(&footer);
+    let postscript = PostScript {
+        footer_length: Some(footer.len() as u64),
+        compression: Some(CompressionKind::None as i32),
+        version: vec![0, 12],
+        metadata_length: Some(0),
+        writer_version: Some(1),
+        magic: Some("ORC".to_string()),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    file.extend(&postscript);
+    file.push(postscript.len() as u8);
+    file
+}
@@ -0,0 +1,132 @@
+//! Contains [`WriterVersion`], the magic and the versions of the file format supported by this crate.
+use crate::error::Error;
+
+/// The magic of ORC files, present at the beginning of the file and at the end of the postscript.
+pub const MAGIC: &str = "ORC";
+
+/// The versions (`[major, minor]`) of the file format that this crate can read:
+/// Hive 0.11 and Hive 0.12 (also known as ORC v1).
+pub const SUPPORTED_VERSIONS: [[u32; 2]; 2] = [[0, 11], [0, 12]];
+
+/// The version of the writer of a file, identifying which bugs were fixed on it,
+/// so that readers can apply writer-specific fixes.
+///
+/// Versions are ordered, i.e. a file written with a given version contains all fixes of
+/// the previous versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum WriterVersion {
+    /// The original writer (or missing)
+    Original,
+    /// Fixed stripe and file level statistics of strings (HIVE-8732)
+    Hive8732,
+    /// Uses the real column names in the schema (HIVE-4243)
+    Hive4243,
+    /// Vectorized writer (HIVE-12055)
+    Hive12055,
+    /// Fixed the present stream of decimals (HIVE-13083)
+    Hive13083,
+    /// Bloom filters of strings are encoded using UTF8 (ORC-101)
+    Orc101,
+    /// Timestamp statistics are stored in UTC (ORC-135)
+    Orc135,
+    /// Fixed the statistics of decimal64 (ORC-517)
+    Orc517,
+    /// Trimmed the minimum and maximum of long strings in statistics (ORC-203)
+    Orc203,
+    /// Timestamp statistics include nanoseconds (ORC-14)
+    Orc14,
+    /// A version newer than those known by this crate, which contains all their fixes.
+    ///
+    /// Its value is always larger than 9 (the value of [`WriterVersion::Orc14`]); use
+    /// [`WriterVersion::from`] to construct it, since e.g. `Future(5)` would neither be
+    /// equal to [`WriterVersion::Orc101`] nor ordered with the known versions.
+    ///
+    /// Reading files of such versions errors unless
+    /// [`ReadOptions::allow_headerless`](crate::read::ReadOptions::allow_headerless).
+    Future(u32),
+}
+
+impl From<u32> for WriterVersion {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => Self::Original,
+            1 => Self::Hive8732,
+            2 => Self::Hive4243,
+            3 => Self::Hive12055,
+            4 => Self::Hive13083,
+            5 => Self::Orc101,
+            6 => Self::Orc135,
+            7 => Self::Orc517,
+            8 => Self::Orc203,
+            9 => Self::Orc14,
+            other => Self::Future(other),
+        }
+    }
+}
+
+impl From<WriterVersion> for u32 {
+    fn from(version: WriterVersion) -> Self {
+        match version {
+            WriterVersion::Original => 0,
+            WriterVersion::Hive8732 => 1,
+            WriterVersion::Hive4243 => 2,
+            WriterVersion::Hive12055 => 3,
+            WriterVersion::Hive13083 => 4,
+            WriterVersion::Orc101 => 5,
+            WriterVersion::Orc135 => 6,
+            WriterVersion::Orc517 => 7,
+            WriterVersion::Orc203 => 8,
+            WriterVersion::Orc14 => 9,
+            WriterVersion::Future(version) => version,
+        }
+    }
+}
+
+/// Validates that the file version `version` (as declared in the postscript) is supported.
+/// Files without a version are assumed to be Hive 0.11.
+pub(crate) fn validate_version(version: &[u32]) -> Result<(), Error> {
+    let (major, minor) = match version {
+        [] => return Ok(()),
+        [major] => (*major, 0),
+        [major, minor, ..] => (*major, *minor),
+    };
+    if SUPPORTED_VERSIONS.contains(&[major, minor]) {
+        Ok(())
+    } else {
+        Err(Error::UnsupportedVersion(major, minor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writer_version() {
+        for version in 0..12 {
+            assert_eq!(u32::from(WriterVersion::from(version)), version);
+        }
+        for version in 0..10 {
+            assert!(!matches!(
+                WriterVersion::from(version),
+                WriterVersion::Future(_)
+            ));
+        }
+        assert_eq!(WriterVersion::from(10), WriterVersion::Future(10));
+        assert_eq!(
+            WriterVersion::from(u32::MAX),
+            WriterVersion::Future(u32::MAX)
+        );
+        assert!(WriterVersion::Hive8732 < WriterVersion::Orc101);
+        assert!(WriterVersion::Orc14 < WriterVersion::Future(10));
+    }
+
+    #[test]
+    fn version() {
+        assert!(validate_version(&[]).is_ok());
+        assert!(validate_version(&[0, 11]).is_ok());
+        assert!(validate_version(&[0, 12]).is_ok());
+        assert!(validate_version(&[0, 13]).is_err());
+        assert!(validate_version(&[1, 0]).is_err());
+    }
+}
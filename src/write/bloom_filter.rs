@@ -0,0 +1,227 @@
+//! Contains [`BloomFilter`], the bloom filter of the values of a row group, computed the
+//! way the reference implementation (Java's `org.apache.orc.util.BloomFilter`) does so that
+//! readers can test values against it.
+use crate::proto;
+
+/// The seed of the hash of bytes
+const SEED: u64 = 104729;
+const C1: u64 = 0x87c3_7b91_1142_53d5;
+const C2: u64 = 0x4cf5_ad43_2745_937f;
+const R1: u32 = 31;
+const R2: u32 = 27;
+const M: u64 = 5;
+const N1: u64 = 0x52dc_e729;
+
+fn fmix64(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+fn mix(k: u64) -> u64 {
+    k.wrapping_mul(C1).rotate_left(R1).wrapping_mul(C2)
+}
+
+/// The 64 bits hash of bytes of the reference implementation (Java's `Murmur3.hash64`),
+/// which differs from the first half of Murmur3's x64 128 bits hash.
+fn murmur3_64(data: &[u8]) -> u64 {
+    let mut hash = SEED;
+    let mut blocks = data.chunks_exact(8);
+    for block in blocks.by_ref() {
+        let k = u64::from_le_bytes(block.try_into().unwrap_or_default());
+        hash ^= mix(k);
+        hash = hash.rotate_left(R2).wrapping_mul(M).wrapping_add(N1);
+    }
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let k = tail
+            .iter()
+            .enumerate()
+            .fold(0u64, |k, (i, byte)| k ^ (*byte as u64) << (8 * i));
+        hash ^= mix(k);
+    }
+    hash ^= data.len() as u64;
+    fmix64(hash)
+}
+
+/// Thomas Wang's hash of integers, used by the reference implementation for integers,
+/// floats and timestamps
+fn long_hash(key: i64) -> u64 {
+    let mut key = (!key).wrapping_add(key << 21);
+    key ^= key >> 24;
+    key = key.wrapping_add(key << 3).wrapping_add(key << 8);
+    key ^= key >> 14;
+    key = key.wrapping_add(key << 2).wrapping_add(key << 4);
+    key ^= key >> 28;
+    key.wrapping_add(key << 31) as u64
+}
+
+/// A bloom filter of the values of a row group
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BloomFilter {
+    bits: Vec<u64>,
+    hash_functions: u32,
+}
+
+impl BloomFilter {
+    /// Returns an empty bloom filter sized for `expected_entries` values with a false positive
+    /// probability of `fpp`, which must be between 0 and 1.
+    pub fn new(expected_entries: u64, fpp: f64) -> Self {
+        let entries = expected_entries.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-entries * fpp.ln() / (ln2 * ln2)) as u64;
+        // a multiple of 64, as the reference implementation does
+        let bits = bits + (64 - bits % 64);
+        let hash_functions = ((bits as f64 / entries * ln2).round() as u32).max(1);
+        Self {
+            bits: vec![0; bits as usize / 64],
+            hash_functions,
+        }
+    }
+
+    fn add_hash(&mut self, hash: u64) {
+        let number_of_bits = self.bits.len() as i32 * 64;
+        let hash1 = hash as i32;
+        let hash2 = (hash >> 32) as i32;
+        for i in 1..=self.hash_functions as i32 {
+            let mut combined = hash1.wrapping_add(i.wrapping_mul(hash2));
+            if combined < 0 {
+                combined = !combined;
+            }
+            let position = (combined % number_of_bits) as usize;
+            self.bits[position / 64] |= 1 << (position % 64);
+        }
+    }
+
+    /// Adds bytes, e.g. strings, binaries and decimals (as strings)
+    pub fn add_bytes(&mut self, value: &[u8]) {
+        self.add_hash(murmur3_64(value))
+    }
+
+    /// Adds an integer, e.g. integers, dates and timestamps (in milliseconds)
+    pub fn add_long(&mut self, value: i64) {
+        self.add_hash(long_hash(value))
+    }
+
+    /// Adds a double (or a float, as a double)
+    pub fn add_double(&mut self, value: f64) {
+        // NaNs are hashed as the canonical NaN, as Java's `Double.doubleToLongBits` does
+        let value = if value.is_nan() { f64::NAN } else { value };
+        self.add_long(value.to_bits() as i64)
+    }
+
+    /// The size of the bloom filter in bytes
+    pub fn size(&self) -> usize {
+        self.bits.len() * 8
+    }
+
+    /// Removes all values
+    pub fn reset(&mut self) {
+        self.bits.iter_mut().for_each(|bits| *bits = 0);
+    }
+
+    /// Returns the bloom filter as stored in a [`proto::stream::Kind::BloomFilterUtf8`]
+    /// stream when `utf8`, and in a [`proto::stream::Kind::BloomFilter`] stream otherwise
+    pub fn to_proto(&self, utf8: bool) -> proto::BloomFilter {
+        let mut filter = proto::BloomFilter {
+            num_hash_functions: Some(self.hash_functions),
+            ..Default::default()
+        };
+        if utf8 {
+            let bitset = self.bits.iter().flat_map(|bits| bits.to_le_bytes());
+            filter.utf8bitset = Some(bitset.collect());
+        } else {
+            filter.bitset = self.bits.clone();
+        }
+        filter
+    }
+
+    #[cfg(test)]
+    fn contains_hash(&self, hash: u64) -> bool {
+        let mut other = Self {
+            bits: vec![0; self.bits.len()],
+            hash_functions: self.hash_functions,
+        };
+        other.add_hash(hash);
+        self.bits
+            .iter()
+            .zip(other.bits)
+            .all(|(bits, other)| bits & other == other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        // the sizes of the reference implementation for 10_000 entries
+        let filter = BloomFilter::new(10_000, 0.05);
+        assert_eq!(filter.bits.len() * 64, 62_400);
+        assert_eq!(filter.hash_functions, 4);
+        let filter = BloomFilter::new(0, 0.5);
+        assert_eq!((filter.bits.len(), filter.hash_functions), (1, 44));
+    }
+
+    #[test]
+    fn hashes() {
+        assert_eq!(long_hash(0), 0);
+        assert_eq!(murmur3_64(b""), fmix64(SEED));
+        // the tail is mixed as a little endian integer
+        assert_eq!(murmur3_64(&[0, 1]), fmix64(SEED ^ mix(1 << 8) ^ 2));
+        let block = murmur3_64(&[1, 0, 0, 0, 0, 0, 0, 0]);
+        let hash = (SEED ^ mix(1))
+            .rotate_left(R2)
+            .wrapping_mul(M)
+            .wrapping_add(N1);
+        assert_eq!(block, fmix64(hash ^ 8));
+    }
+
+    #[test]
+    fn false_positives() {
+        let mut filter = BloomFilter::new(1_000, 0.05);
+        (0..1_000).for_each(|value| filter.add_long(value));
+        (0..1_000).for_each(|value| filter.add_bytes(value.to_string().as_bytes()));
+        filter.add_double(f64::NAN);
+
+        assert!((0..1_000).all(|value| filter.contains_hash(long_hash(value))));
+        assert!(
+            (0..1_000).all(|value| filter.contains_hash(murmur3_64(value.to_string().as_bytes())))
+        );
+        assert!(filter.contains_hash(long_hash(f64::NAN.to_bits() as i64)));
+
+        // 2_000 values in a filter of 1_000 have many false positives, but not all
+        let positives = (1_000..11_000)
+            .filter(|value| filter.contains_hash(long_hash(*value)))
+            .count();
+        assert!(positives < 10_000);
+
+        let mut filter = BloomFilter::new(1_000, 0.05);
+        (0..1_000).for_each(|value| filter.add_long(value));
+        let positives = (1_000..11_000)
+            .filter(|value| filter.contains_hash(long_hash(*value)))
+            .count();
+        assert!(positives < 1_000, "{positives}");
+
+        filter.reset();
+        assert!(!filter.contains_hash(long_hash(0)));
+    }
+
+    #[test]
+    fn proto() {
+        let mut filter = BloomFilter::new(10, 0.05);
+        filter.add_long(1);
+        let utf8 = filter.to_proto(true);
+        let original = filter.to_proto(false);
+        assert_eq!(utf8.num_hash_functions, original.num_hash_functions);
+        let bitset = original
+            .bitset
+            .iter()
+            .flat_map(|bits| bits.to_le_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(utf8.utf8bitset, Some(bitset));
+    }
+}
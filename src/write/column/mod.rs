@@ -0,0 +1,1351 @@
+//! Contains [`ColumnWriter`], which encodes the values of a column to its streams.
+//!
+//! Values are written row by row to the writer of their type. Every `row_index_stride`
+//! rows the caller finishes a row group ([`ColumnWriter::finish_row_group`]), and once a
+//! stripe is complete it finishes the stripe ([`ColumnWriter::finish_stripe`]), which
+//! adds the column's streams, encoding and statistics to an [`EncodedStripe`].
+//!
+//! When the file has a row index, the stripe also contains the column's [`Kind::RowIndex`]
+//! stream, with the positions of its streams and the statistics of every row group, and
+//! the bloom filters of its row groups when [`WriteOptions::bloom_filter_columns`]
+//! contains it.
+use std::io::Write;
+
+use prost::Message;
+
+use crate::error::Error;
+use crate::proto::column_encoding::Kind as ColumnEncodingKind;
+use crate::proto::r#type::Kind as TypeKind;
+use crate::proto::stream::Kind;
+use crate::proto::{
+    BloomFilterIndex, ColumnEncoding, ColumnStatistics, CompressionKind, RowIndex, RowIndexEntry,
+    Type,
+};
+use crate::read::types::{children, get_type};
+
+use super::bloom_filter::BloomFilter;
+use super::encode::{
+    BooleanRleEncoder, ByteRleEncoder, DecimalEncoder, SignedRleV2Encoder, UnsignedRleV2Encoder,
+};
+use super::statistics::{Statistics, Values};
+use super::{compress, BloomFilterVersion, Compressor, EncodedStream, EncodedStripe, WriteOptions};
+
+mod nested;
+mod primitive;
+mod string;
+
+pub use nested::{ListWriter, MapWriter, StructWriter, UnionWriter};
+pub use primitive::{
+    BooleanWriter, ByteWriter, DateWriter, DecimalWriter, FloatWriter, IntegerWriter,
+    TimestampWriter,
+};
+pub use string::{BinaryWriter, StringWriter};
+
+/// An encoder of the values of a stream
+trait Encoder: std::fmt::Debug + Default {
+    /// Takes the bytes written so far
+    fn take(&mut self) -> Vec<u8>;
+    /// Writes the buffered values
+    fn flush(&mut self);
+    /// Adds the position of the next value among the buffered values to `positions`
+    fn record(&self, positions: &mut Vec<u64>);
+    /// The number of bytes written and not taken so far
+    fn len(&self) -> usize;
+}
+
+macro_rules! encoder {
+    ($($encoder:ty => |$self:ident, $positions:ident| $record:expr),* $(,)?) => {
+        $(
+            impl Encoder for $encoder {
+                fn take(&mut self) -> Vec<u8> {
+                    self.take()
+                }
+
+                fn flush(&mut self) {
+                    self.flush()
+                }
+
+                fn record(&self, $positions: &mut Vec<u64>) {
+                    let $self = self;
+                    $record
+                }
+
+                fn len(&self) -> usize {
+                    self.output().len()
+                }
+            }
+        )*
+    };
+}
+
+encoder!(
+    // the number of buffered bytes and of bits of the current byte
+    BooleanRleEncoder => |encoder, positions| {
+        let buffered = encoder.buffered() as u64;
+        positions.extend([buffered / 8, buffered % 8])
+    },
+    ByteRleEncoder => |encoder, positions| positions.push(encoder.buffered() as u64),
+    DecimalEncoder => |_encoder, _positions| {},
+    SignedRleV2Encoder => |encoder, positions| positions.push(encoder.buffered() as u64),
+    UnsignedRleV2Encoder => |encoder, positions| positions.push(encoder.buffered() as u64),
+);
+
+/// Streams of bytes written as is, e.g. of floats
+impl Encoder for Vec<u8> {
+    fn take(&mut self) -> Vec<u8> {
+        std::mem::take(self)
+    }
+
+    fn flush(&mut self) {}
+
+    fn record(&self, _: &mut Vec<u64>) {}
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+}
+
+/// A stream being written: its values are encoded and then compressed.
+#[derive(Debug)]
+struct StreamWriter<E: Encoder> {
+    kind: Kind,
+    encoder: E,
+    compressor: Compressor,
+}
+
+impl<E: Encoder> StreamWriter<E> {
+    fn try_new(kind: Kind, options: &WriteOptions) -> Result<Self, Error> {
+        Ok(Self {
+            kind,
+            encoder: E::default(),
+            compressor: Compressor::try_new(options.compression, options.compression_block_size)?,
+        })
+    }
+
+    /// Moves the bytes written by the encoder to the compressor
+    fn sync(&mut self) -> Result<(), Error> {
+        let data = self.encoder.take();
+        self.compressor.write_all(&data)?;
+        Ok(())
+    }
+
+    /// Adds the position of the next value of the stream to `positions`: the position of
+    /// the compressor followed by the position among the values buffered by the encoder.
+    fn record(&mut self, positions: &mut Vec<u64>) -> Result<(), Error> {
+        self.sync()?;
+        let (chunk, offset) = self.compressor.position();
+        positions.push(chunk);
+        positions.extend(offset);
+        self.encoder.record(positions);
+        Ok(())
+    }
+
+    /// The estimated size of the stream in memory: its compressed chunks, the chunk being
+    /// compressed and the bytes written by the encoder
+    fn estimated_size(&self) -> u64 {
+        let (output, chunk) = self.compressor.position();
+        output + chunk.unwrap_or(0) + self.encoder.len() as u64
+    }
+
+    /// Returns the (encoded and compressed) stream, leaving the writer empty
+    fn take(&mut self) -> Result<Vec<u8>, Error> {
+        self.encoder.flush();
+        self.sync()?;
+        self.compressor.take()
+    }
+
+    /// Finishes the stream of `column` of the stripe, adding it to `streams`
+    fn finish(&mut self, column: u32, streams: &mut Vec<EncodedStream>) -> Result<(), Error> {
+        streams.push(EncodedStream {
+            kind: self.kind,
+            column,
+            data: self.take()?,
+        });
+        Ok(())
+    }
+}
+
+/// The row index of a column and its bloom filters, for files with a row index
+#[derive(Debug)]
+struct Index {
+    /// the positions of the streams at the start of each row group of the current stripe
+    positions: Vec<Vec<u64>>,
+    /// the bloom filter of the current row group, when the column has bloom filters
+    bloom_filter: Option<BloomFilter>,
+    /// the bloom filters of the finished row groups of the current stripe
+    bloom_filters: Vec<BloomFilter>,
+    bloom_filter_version: BloomFilterVersion,
+    compression: CompressionKind,
+    compression_block_size: u64,
+}
+
+impl Index {
+    fn try_new(column: u32, options: &WriteOptions) -> Result<Option<Self>, Error> {
+        if options.row_index_stride == 0 {
+            return Ok(None);
+        }
+        let bloom_filter = if options.bloom_filter_columns.contains(&column) {
+            let fpp = options.bloom_filter_fpp;
+            if !(fpp > 0.0 && fpp < 1.0) {
+                return Err(Error::OutOfSpec(
+                    "the false positive probability of bloom filters must be between 0 and 1",
+                ));
+            }
+            Some(BloomFilter::new(options.row_index_stride as u64, fpp))
+        } else {
+            None
+        };
+        Ok(Some(Self {
+            positions: vec![],
+            bloom_filter,
+            bloom_filters: vec![],
+            bloom_filter_version: options.bloom_filter_version,
+            compression: options.compression,
+            compression_block_size: options.compression_block_size,
+        }))
+    }
+
+    /// The estimated size of the positions and bloom filters of the current stripe
+    fn estimated_size(&self) -> u64 {
+        let positions = self.positions.iter().map(Vec::len).sum::<usize>() * 8;
+        let bloom_filters = self
+            .bloom_filter
+            .iter()
+            .chain(&self.bloom_filters)
+            .map(BloomFilter::size)
+            .sum::<usize>();
+        (positions + bloom_filters) as u64
+    }
+
+    fn finish_row_group(&mut self) {
+        if let Some(bloom_filter) = self.bloom_filter.as_mut() {
+            self.bloom_filters.push(bloom_filter.clone());
+            bloom_filter.reset();
+        }
+    }
+
+    /// Finishes the index streams of the stripe, whose row groups have `statistics`
+    fn finish_stripe(
+        &mut self,
+        column: u32,
+        statistics: &[ColumnStatistics],
+        has_nulls: bool,
+        streams: &mut Vec<EncodedStream>,
+    ) -> Result<(), Error> {
+        // the positions of the start of a row group without rows at the end of the stripe
+        self.positions.truncate(statistics.len());
+        if !has_nulls {
+            // the Present stream is omitted: so are its positions, which come first
+            let present = match self.compression {
+                CompressionKind::None => 3,
+                _ => 4,
+            };
+            for positions in self.positions.iter_mut() {
+                positions.drain(..present.min(positions.len()));
+            }
+        }
+
+        let entry = std::mem::take(&mut self.positions)
+            .into_iter()
+            .zip(statistics.iter().cloned())
+            .map(|(positions, statistics)| RowIndexEntry {
+                positions,
+                statistics: Some(statistics),
+            })
+            .collect();
+        let mut push = |kind, data: Vec<u8>| -> Result<(), Error> {
+            streams.push(EncodedStream {
+                kind,
+                column,
+                data: compress(&data, self.compression, self.compression_block_size)?,
+            });
+            Ok(())
+        };
+        push(Kind::RowIndex, RowIndex { entry }.encode_to_vec())?;
+
+        let bloom_filters = std::mem::take(&mut self.bloom_filters);
+        if self.bloom_filter.is_some() {
+            let index = |utf8| {
+                let bloom_filter = bloom_filters
+                    .iter()
+                    .map(|bloom_filter| bloom_filter.to_proto(utf8))
+                    .collect();
+                BloomFilterIndex { bloom_filter }.encode_to_vec()
+            };
+            if self.bloom_filter_version == BloomFilterVersion::Original {
+                push(Kind::BloomFilter, index(false))?;
+            }
+            push(Kind::BloomFilterUtf8, index(true))?;
+        }
+        Ok(())
+    }
+}
+
+/// The state shared by the writers of all types: the column's Present stream, statistics
+/// and row index
+#[derive(Debug)]
+struct Base {
+    column: u32,
+    present: StreamWriter<BooleanRleEncoder>,
+    /// whether the current stripe has nulls. The Present stream is omitted when it has not.
+    has_nulls: bool,
+    rows: u64,
+    row_group: Statistics,
+    stripe: Statistics,
+    file: Statistics,
+    /// the statistics of the finished row groups of the current stripe
+    row_groups: Vec<ColumnStatistics>,
+    index: Option<Index>,
+}
+
+impl Base {
+    fn try_new(column: u32, values: Values, options: &WriteOptions) -> Result<Self, Error> {
+        let statistics = Statistics::new(values);
+        Ok(Self {
+            column,
+            present: StreamWriter::try_new(Kind::Present, options)?,
+            has_nulls: false,
+            rows: 0,
+            row_group: statistics.clone(),
+            stripe: statistics.clone(),
+            file: statistics,
+            row_groups: vec![],
+            index: Index::try_new(column, options)?,
+        })
+    }
+
+    /// Writes whether the next row is valid (not null)
+    #[inline]
+    fn present(&mut self, is_valid: bool) {
+        self.present.encoder.encode(is_valid);
+        self.rows += 1;
+        if !is_valid {
+            self.has_nulls = true;
+            self.row_group.null();
+        }
+    }
+
+    /// The bloom filter of the current row group, when the column has bloom filters
+    #[inline]
+    fn bloom_filter(&mut self) -> Option<&mut BloomFilter> {
+        self.index.as_mut()?.bloom_filter.as_mut()
+    }
+
+    /// The positions of the streams at the start of the row group `row_group` of the
+    /// current stripe, when the file has a row index
+    fn positions(&mut self, row_group: usize) -> Option<&mut Vec<u64>> {
+        self.index.as_mut()?.positions.get_mut(row_group)
+    }
+
+    /// The estimated size of the Present stream and of the row index of the current stripe
+    fn estimated_size(&self) -> u64 {
+        self.present.estimated_size() + self.index.as_ref().map_or(0, Index::estimated_size)
+    }
+
+    fn finish_row_group(&mut self) {
+        self.row_groups.push(self.row_group.to_proto());
+        self.stripe.merge(&self.row_group);
+        self.row_group.reset();
+        self.rows = 0;
+        if let Some(index) = self.index.as_mut() {
+            index.finish_row_group();
+        }
+    }
+
+    /// Whether the last row group of the stripe is finished with it: when it has rows, or
+    /// is the only one
+    fn has_last_row_group(&self) -> bool {
+        self.rows > 0 || self.row_groups.is_empty()
+    }
+
+    /// Finishes the Present and index streams, encoding and statistics of the stripe, whose
+    /// row groups are all finished
+    fn finish_stripe(
+        &mut self,
+        stripe: &mut EncodedStripe,
+        encoding: ColumnEncodingKind,
+        dictionary_size: Option<u32>,
+    ) -> Result<(), Error> {
+        if let Some(index) = self.index.as_mut() {
+            index.finish_stripe(
+                self.column,
+                &self.row_groups,
+                self.has_nulls,
+                &mut stripe.streams,
+            )?;
+        }
+        if self.has_nulls {
+            self.present.finish(self.column, &mut stripe.streams)?;
+        } else {
+            self.present.take()?;
+        }
+        stripe.encodings.push(ColumnEncoding {
+            kind: Some(encoding as i32),
+            dictionary_size,
+        });
+        stripe.statistics.push(self.stripe.to_proto());
+
+        self.file.merge(&self.stripe);
+        self.stripe.reset();
+        self.row_groups.clear();
+        self.has_nulls = false;
+        Ok(())
+    }
+}
+
+/// Implements the methods shared by all writers, given the streams of the writer (other
+/// than Present) whose positions are recorded in the row index as values are written.
+/// Writers of nested types also pass the field with their children, and writers buffering
+/// values outside of these streams a method returning the size of that buffer.
+macro_rules! writer {
+    ($writer:ty, [$($stream:ident),*] $(, $children:ident)? $(; $buffered:ident)?) => {
+        impl $writer {
+            /// The column of this writer
+            pub fn column(&self) -> u32 {
+                self.base.column
+            }
+
+            /// Writes a null
+            #[inline]
+            pub fn write_null(&mut self) {
+                self.base.present(false)
+            }
+
+            /// Finishes the current row group (of the column and of its children),
+            /// accumulating its statistics and recording the positions of the next one.
+            /// # Errors
+            /// This function errors when compressing fails.
+            pub fn finish_row_group(&mut self) -> Result<(), Error> {
+                self.base.finish_row_group();
+                self.record_positions()?;
+                $(
+                    for child in self.$children.iter_mut() {
+                        child.finish_row_group()?;
+                    }
+                )?
+                Ok(())
+            }
+
+            /// Finishes the current stripe, adding the streams, encodings and statistics
+            /// of the column (and of its children) to `stripe`.
+            ///
+            /// The last row group is finished with the stripe when this column has rows in
+            /// it (or it is the only one). Its children follow this column, whatever their
+            /// own values, so that every column of the stripe has the same row groups.
+            /// # Errors
+            /// This function errors when compressing fails.
+            pub fn finish_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+                let has_rows = self.base.has_last_row_group();
+                self.finish_last_row_group(has_rows);
+                self.end_stripe(stripe)
+            }
+
+            /// Finishes the last row group of the stripe (of the column and of its
+            /// children) when `has_rows`
+            pub(super) fn finish_last_row_group(&mut self, has_rows: bool) {
+                if has_rows {
+                    self.base.finish_row_group();
+                }
+                $(
+                    for child in self.$children.iter_mut() {
+                        child.finish_last_row_group(has_rows);
+                    }
+                )?
+            }
+
+            /// Finishes the current stripe, whose row groups are finished, and records the
+            /// positions of the first row group of the next one
+            pub(super) fn end_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+                self.write_stripe(stripe)?;
+                self.record_positions()
+            }
+
+            /// Records the positions of the streams at the start of the current row group,
+            /// when the file has a row index
+            pub(super) fn record_positions(&mut self) -> Result<(), Error> {
+                let Some(index) = self.base.index.as_mut() else {
+                    return Ok(());
+                };
+                let mut positions = vec![];
+                self.base.present.record(&mut positions)?;
+                $(self.$stream.record(&mut positions)?;)*
+                index.positions.push(positions);
+                Ok(())
+            }
+
+            /// The estimated size in memory of the current stripe of the column (and of its
+            /// children), once encoded and compressed
+            pub fn estimated_size(&self) -> u64 {
+                self.base.estimated_size()
+                    $(+ self.$stream.estimated_size())*
+                    $(+ self.$children.iter().map(ColumnWriter::estimated_size).sum::<u64>())?
+                    $(+ self.$buffered())?
+            }
+
+            /// Returns the statistics of the stripes finished so far
+            pub fn statistics(&self) -> ColumnStatistics {
+                self.base.file.to_proto()
+            }
+
+            /// Returns the statistics of the row groups finished in the current stripe
+            pub fn row_group_statistics(&self) -> &[ColumnStatistics] {
+                &self.base.row_groups
+            }
+        }
+    };
+}
+use writer;
+
+/// A writer of a column of any type.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum ColumnWriter {
+    /// A writer of booleans
+    Boolean(BooleanWriter),
+    /// A writer of bytes (tinyint)
+    Byte(ByteWriter),
+    /// A writer of short, int and long
+    Integer(IntegerWriter),
+    /// A writer of floats
+    Float(FloatWriter<f32>),
+    /// A writer of doubles
+    Double(FloatWriter<f64>),
+    /// A writer of string, varchar and char
+    String(StringWriter),
+    /// A writer of binary
+    Binary(BinaryWriter),
+    /// A writer of dates
+    Date(DateWriter),
+    /// A writer of timestamps
+    Timestamp(TimestampWriter),
+    /// A writer of decimals
+    Decimal(DecimalWriter),
+    /// A writer of structs
+    Struct(StructWriter),
+    /// A writer of lists
+    List(ListWriter),
+    /// A writer of maps
+    Map(MapWriter),
+    /// A writer of unions
+    Union(UnionWriter),
+}
+
+macro_rules! dispatch {
+    ($self:expr, $writer:ident => $expr:expr) => {
+        match $self {
+            ColumnWriter::Boolean($writer) => $expr,
+            ColumnWriter::Byte($writer) => $expr,
+            ColumnWriter::Integer($writer) => $expr,
+            ColumnWriter::Float($writer) => $expr,
+            ColumnWriter::Double($writer) => $expr,
+            ColumnWriter::String($writer) => $expr,
+            ColumnWriter::Binary($writer) => $expr,
+            ColumnWriter::Date($writer) => $expr,
+            ColumnWriter::Timestamp($writer) => $expr,
+            ColumnWriter::Decimal($writer) => $expr,
+            ColumnWriter::Struct($writer) => $expr,
+            ColumnWriter::List($writer) => $expr,
+            ColumnWriter::Map($writer) => $expr,
+            ColumnWriter::Union($writer) => $expr,
+        }
+    };
+}
+
+impl ColumnWriter {
+    /// Returns a new [`ColumnWriter`] of `column` of `types`, and of its children when
+    /// its type is nested.
+    /// # Errors
+    /// This function errors when `column` does not exist, its type is not supported or
+    /// `options` are not (e.g. its compression or bloom filters' false positive probability).
+    pub fn try_new(types: &[Type], column: u32, options: &WriteOptions) -> Result<Self, Error> {
+        let type_ = get_type(types, column)?;
+        let children = || {
+            children(types, column)?
+                .iter()
+                .map(|child| Self::try_new(types, *child, options))
+                .collect::<Result<Vec<_>, Error>>()
+        };
+        let mut writer = match type_.kind() {
+            TypeKind::Boolean => Self::Boolean(BooleanWriter::try_new(column, options)?),
+            TypeKind::Byte => Self::Byte(ByteWriter::try_new(column, options)?),
+            TypeKind::Short | TypeKind::Int | TypeKind::Long => {
+                Self::Integer(IntegerWriter::try_new(column, options)?)
+            }
+            TypeKind::Float => Self::Float(FloatWriter::try_new(column, options)?),
+            TypeKind::Double => Self::Double(FloatWriter::try_new(column, options)?),
+            TypeKind::String => Self::String(StringWriter::try_new(column, None, options)?),
+            TypeKind::Varchar | TypeKind::Char => {
+                let length = type_.maximum_length.ok_or(Error::OutOfSpec(
+                    "varchar and char types must have a maximum length",
+                ))?;
+                let padded = type_.kind() == TypeKind::Char;
+                Self::String(StringWriter::try_new(
+                    column,
+                    Some((length as usize, padded)),
+                    options,
+                )?)
+            }
+            TypeKind::Binary => Self::Binary(BinaryWriter::try_new(column, options)?),
+            TypeKind::Date => Self::Date(DateWriter::try_new(column, options)?),
+            TypeKind::Timestamp | TypeKind::TimestampInstant => {
+                Self::Timestamp(TimestampWriter::try_new(column, options)?)
+            }
+            TypeKind::Decimal => {
+                Self::Decimal(DecimalWriter::try_new(column, type_.scale(), options)?)
+            }
+            TypeKind::Struct => Self::Struct(StructWriter::try_new(column, children()?, options)?),
+            TypeKind::List => {
+                let [child] = <[_; 1]>::try_from(children()?)
+                    .map_err(|_| Error::OutOfSpec("a list must have one child"))?;
+                Self::List(ListWriter::try_new(column, child, options)?)
+            }
+            TypeKind::Map => {
+                let [keys, values] = <[_; 2]>::try_from(children()?)
+                    .map_err(|_| Error::OutOfSpec("a map must have two children"))?;
+                Self::Map(MapWriter::try_new(column, keys, values, options)?)
+            }
+            TypeKind::Union => Self::Union(UnionWriter::try_new(column, children()?, options)?),
+        };
+        dispatch!(&mut writer, writer => writer.record_positions())?;
+        Ok(writer)
+    }
+
+    /// The column of this writer
+    pub fn column(&self) -> u32 {
+        dispatch!(self, writer => writer.column())
+    }
+
+    /// Writes a null
+    pub fn write_null(&mut self) {
+        dispatch!(self, writer => writer.write_null())
+    }
+
+    /// Finishes the current row group (of the column and of its children), accumulating
+    /// its statistics and recording the positions of the next one in the row index.
+    ///
+    /// Row groups are finished every [`WriteOptions::row_index_stride`] rows of the file.
+    /// # Errors
+    /// This function errors when compressing fails.
+    pub fn finish_row_group(&mut self) -> Result<(), Error> {
+        dispatch!(self, writer => writer.finish_row_group())
+    }
+
+    /// Finishes the current stripe, adding the streams, encodings and statistics of the
+    /// column and of its children, in pre-order, to `stripe`. Its last row group is
+    /// finished, for the column and its children, when the column has rows in it.
+    /// # Errors
+    /// This function errors when compressing fails.
+    pub fn finish_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        dispatch!(self, writer => writer.finish_stripe(stripe))
+    }
+
+    fn finish_last_row_group(&mut self, has_rows: bool) {
+        dispatch!(self, writer => writer.finish_last_row_group(has_rows))
+    }
+
+    fn end_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        dispatch!(self, writer => writer.end_stripe(stripe))
+    }
+
+    /// The estimated size in memory of the current stripe of the column and of its
+    /// children, once encoded and compressed.
+    ///
+    /// Stripes are usually finished once the sum of the sizes of the file's columns
+    /// reaches a threshold (e.g. [`WriteOptions::stripe_size`]), bounding the memory used.
+    pub fn estimated_size(&self) -> u64 {
+        dispatch!(self, writer => writer.estimated_size())
+    }
+
+    /// The writers of the children of the column, empty when its type is not nested
+    pub fn children(&self) -> &[ColumnWriter] {
+        match self {
+            Self::Struct(writer) => &writer.children,
+            Self::List(writer) => &writer.children,
+            Self::Map(writer) => &writer.children,
+            Self::Union(writer) => &writer.children,
+            _ => &[],
+        }
+    }
+
+    /// Adds the statistics of the stripes finished so far of the column and of its
+    /// children, in pre-order, to `statistics`
+    pub fn statistics(&self, statistics: &mut Vec<ColumnStatistics>) {
+        statistics.push(dispatch!(self, writer => writer.statistics()));
+        for child in self.children() {
+            child.statistics(statistics)
+        }
+    }
+
+    /// Returns the statistics of the row groups finished in the current stripe
+    pub fn row_group_statistics(&self) -> &[ColumnStatistics] {
+        dispatch!(self, writer => writer.row_group_statistics())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use crate::proto::Footer;
+    use crate::read::decode::{BooleanIter, SignedRleV2Iter, UnsignedRleV2Iter};
+    use crate::read::decompress::Decompressor;
+    use crate::test_util::{struct_, type_};
+    use crate::write::FileWriter;
+
+    use super::*;
+
+    fn types() -> Vec<Type> {
+        let kinds = [
+            TypeKind::Boolean,
+            TypeKind::Byte,
+            TypeKind::Int,
+            TypeKind::Float,
+            TypeKind::Double,
+            TypeKind::String,
+            TypeKind::Varchar,
+            TypeKind::Char,
+            TypeKind::Binary,
+            TypeKind::Date,
+            TypeKind::Timestamp,
+            TypeKind::Decimal,
+        ];
+        let names = (0..kinds.len())
+            .map(|i| format!("c{i}"))
+            .collect::<Vec<_>>();
+        let fields = names
+            .iter()
+            .map(String::as_str)
+            .zip(kinds)
+            .collect::<Vec<_>>();
+        let mut types = struct_(&fields);
+        types[7].maximum_length = Some(3);
+        types[8].maximum_length = Some(3);
+        types[12].precision = Some(10);
+        types[12].scale = Some(2);
+        types
+    }
+
+    /// Writes `rows` rows of every type, whose every third row is null, in row groups of 2 rows
+    fn write(rows: usize, options: WriteOptions) -> Result<Vec<u8>, Error> {
+        let types = types();
+        let mut writers = (1..types.len() as u32)
+            .map(|column| ColumnWriter::try_new(&types, column, &options))
+            .collect::<Result<Vec<_>, _>>()?;
+        let strings = ["a", "bb", "cccc"];
+        for row in 0..rows {
+            if row % 3 == 2 {
+                writers.iter_mut().for_each(|writer| writer.write_null());
+            } else {
+                let i = row as i64;
+                let string = strings[row % strings.len()];
+                for writer in writers.iter_mut() {
+                    match writer {
+                        ColumnWriter::Boolean(w) => w.write(Some(row % 2 == 0)),
+                        ColumnWriter::Byte(w) => w.write(Some(-(row as i8))),
+                        ColumnWriter::Integer(w) => w.write(Some(i * 1000)),
+                        ColumnWriter::Float(w) => w.write(Some(row as f32 / 2.0)),
+                        ColumnWriter::Double(w) => w.write(Some(-(row as f64))),
+                        ColumnWriter::String(w) => w.write(Some(string)),
+                        ColumnWriter::Binary(w) => w.write(Some(string.as_bytes())),
+                        ColumnWriter::Date(w) => w.write(Some(row as i32 - 1)),
+                        ColumnWriter::Timestamp(w) => w.write(Some(i * 1_500_000_000)),
+                        ColumnWriter::Decimal(w) => w.write(Some(-105 * row as i128)),
+                        _ => unreachable!(),
+                    }
+                }
+            }
+            if row % 2 == 1 {
+                for writer in writers.iter_mut() {
+                    writer.finish_row_group()?;
+                }
+            }
+        }
+
+        let mut stripe = EncodedStripe {
+            number_of_rows: rows as u64,
+            encodings: vec![ColumnEncoding {
+                kind: Some(ColumnEncodingKind::Direct as i32),
+                dictionary_size: None,
+            }],
+            statistics: vec![ColumnStatistics {
+                number_of_values: Some(rows as u64),
+                has_null: Some(false),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut statistics = stripe.statistics.clone();
+        for writer in writers.iter_mut() {
+            assert_eq!(writer.row_group_statistics().len(), rows / 2);
+            writer.finish_stripe(&mut stripe)?;
+            writer.statistics(&mut statistics);
+        }
+
+        let mut file = FileWriter::try_new(vec![], types, options)?;
+        file.write_stripe(stripe)?;
+        file.set_statistics(statistics);
+        file.finish()
+    }
+
+    fn footer(file: &[u8]) -> Result<Footer, Error> {
+        Ok(crate::read::read_metadata(&mut std::io::Cursor::new(file))?.footer)
+    }
+
+    #[test]
+    fn statistics() -> Result<(), Error> {
+        let footer = footer(&write(5, WriteOptions::default())?)?;
+        let statistics = &footer.statistics;
+        // rows 0, 1, 3 and 4 are not null
+        assert_eq!(statistics[1].number_of_values(), 4);
+        assert!(statistics[1].has_null());
+        assert_eq!(statistics[1].bucket_statistics.as_ref().unwrap().count, [2]);
+        let integers = statistics[3].int_statistics.as_ref().unwrap();
+        assert_eq!((integers.minimum(), integers.maximum()), (0, 4000));
+        assert_eq!(integers.sum(), 8000);
+        let strings = statistics[6].string_statistics.as_ref().unwrap();
+        assert_eq!((strings.minimum(), strings.maximum()), ("a", "bb"));
+        assert_eq!(strings.sum(), 1 + 2 + 1 + 2);
+        let chars = statistics[8].string_statistics.as_ref().unwrap();
+        assert_eq!((chars.minimum(), chars.maximum()), ("a  ", "bb "));
+        let timestamps = statistics[11].timestamp_statistics.as_ref().unwrap();
+        assert_eq!(timestamps.maximum_utc(), 6_000);
+        assert_eq!(timestamps.maximum, None);
+        let decimals = statistics[12].decimal_statistics.as_ref().unwrap();
+        assert_eq!(decimals.minimum(), "-4.20");
+        assert_eq!(decimals.sum(), "-8.40");
+        Ok(())
+    }
+
+    #[test]
+    fn dictionary() -> Result<(), Error> {
+        let encoding = |threshold| -> Result<ColumnEncodingKind, Error> {
+            let options = WriteOptions {
+                dictionary_key_size_threshold: threshold,
+                ..Default::default()
+            };
+            let file = write(30, options)?;
+            let mut reader = std::io::Cursor::new(&file);
+            let metadata = crate::read::read_metadata(&mut reader)?;
+            let footer = crate::read::read_stripe_footer(&mut reader, &metadata, 0, &mut vec![])?;
+            Ok(footer.columns[6].kind())
+        };
+        // 2 distinct values in 20 values
+        assert_eq!(encoding(0.0)?, ColumnEncodingKind::DirectV2);
+        assert_eq!(encoding(0.05)?, ColumnEncodingKind::DirectV2);
+        assert_eq!(encoding(0.1)?, ColumnEncodingKind::DictionaryV2);
+        Ok(())
+    }
+
+    /// Writes 3 rows of a struct, a list, a map and a union
+    fn write_nested(options: WriteOptions) -> Result<Vec<u8>, Error> {
+        let types = vec![
+            type_(TypeKind::Struct, vec![1, 4, 6, 9], vec!["s", "l", "m", "u"]),
+            type_(TypeKind::Struct, vec![2, 3], vec!["x", "y"]),
+            type_(TypeKind::Int, vec![], vec![]),
+            type_(TypeKind::String, vec![], vec![]),
+            type_(TypeKind::List, vec![5], vec![]),
+            type_(TypeKind::Int, vec![], vec![]),
+            type_(TypeKind::Map, vec![7, 8], vec![]),
+            type_(TypeKind::String, vec![], vec![]),
+            type_(TypeKind::Int, vec![], vec![]),
+            type_(TypeKind::Union, vec![10, 11], vec![]),
+            type_(TypeKind::Int, vec![], vec![]),
+            type_(TypeKind::String, vec![], vec![]),
+        ];
+        let mut writers = [1, 4, 6, 9]
+            .into_iter()
+            .map(|column| ColumnWriter::try_new(&types, column, &options))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [ColumnWriter::Struct(s), ColumnWriter::List(l), ColumnWriter::Map(m), ColumnWriter::Union(u)] =
+            &mut writers[..]
+        else {
+            panic!()
+        };
+        // {x: 1, y: "a"}, null, {x: null, y: "b"}
+        s.write(true);
+        s.write(false);
+        s.write(true);
+        let [ColumnWriter::Integer(x), ColumnWriter::String(y)] = s.children_mut() else {
+            panic!()
+        };
+        x.write(Some(1));
+        y.write(Some("a"));
+        x.write(None);
+        y.write(Some("b"));
+
+        // [1, 2], null, []
+        l.write(Some(2));
+        let ColumnWriter::Integer(items) = l.child_mut() else {
+            panic!()
+        };
+        items.write(Some(1));
+        items.write(Some(2));
+        l.write(None);
+        l.write(Some(0));
+
+        // {"k": 1}, {}, null
+        m.write(Some(1));
+        let (ColumnWriter::String(keys), ColumnWriter::Integer(values)) = m.children_mut() else {
+            panic!()
+        };
+        keys.write(Some("k"));
+        values.write(Some(1));
+        m.write(Some(0));
+        m.write_null();
+
+        // 5, "s", null
+        u.write(Some(0))?;
+        u.write(Some(1))?;
+        u.write(None)?;
+        assert!(u.write(Some(2)).is_err());
+        let [ColumnWriter::Integer(a), ColumnWriter::String(b)] = u.children_mut() else {
+            panic!()
+        };
+        a.write(Some(5));
+        b.write(Some("s"));
+
+        let mut stripe = EncodedStripe {
+            number_of_rows: 3,
+            encodings: vec![ColumnEncoding {
+                kind: Some(ColumnEncodingKind::Direct as i32),
+                dictionary_size: None,
+            }],
+            statistics: vec![ColumnStatistics {
+                number_of_values: Some(3),
+                has_null: Some(false),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut statistics = stripe.statistics.clone();
+        for writer in writers.iter_mut() {
+            writer.finish_stripe(&mut stripe)?;
+            writer.statistics(&mut statistics);
+        }
+        assert_eq!(stripe.encodings.len(), types.len());
+
+        let mut file = FileWriter::try_new(vec![], types, options)?;
+        file.write_stripe(stripe)?;
+        file.set_statistics(statistics);
+        file.finish()
+    }
+
+    #[test]
+    fn nested() -> Result<(), Error> {
+        let file = write_nested(WriteOptions::default())?;
+        let mut reader = std::io::Cursor::new(&file);
+        let metadata = crate::read::read_metadata(&mut reader)?;
+        let statistics = &metadata.footer.statistics;
+        assert_eq!(statistics.len(), 12);
+        let counts = statistics
+            .iter()
+            .map(|x| (x.number_of_values(), x.has_null()))
+            .collect::<Vec<_>>();
+        let expected = [
+            (3, false),
+            (2, true),
+            (1, true),
+            (2, false),
+            (2, true),
+            (2, false),
+            (2, true),
+            (1, false),
+            (1, false),
+            (2, true),
+            (1, false),
+            (1, false),
+        ];
+        assert_eq!(counts, expected);
+
+        let footer = crate::read::read_stripe_footer(&mut reader, &metadata, 0, &mut vec![])?;
+        let encodings = footer.columns.iter().map(|x| x.kind()).collect::<Vec<_>>();
+        assert_eq!(encodings[4], ColumnEncodingKind::DirectV2);
+        assert_eq!(encodings[9], ColumnEncodingKind::Direct);
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn nested_round_trip() -> Result<(), Error> {
+        use crate::de::{read_stripe, Value};
+
+        let file = write_nested(WriteOptions::default())?;
+        let mut reader = std::io::Cursor::new(&file);
+        let metadata = crate::read::read_metadata(&mut reader)?;
+        let rows = read_stripe(&mut reader, &metadata, 0)?
+            .into_iter()
+            .map(|row| match row {
+                Value::Struct(_, values) => values,
+                _ => panic!(),
+            })
+            .collect::<Vec<_>>();
+
+        let names: std::sync::Arc<[String]> = vec!["x".to_string(), "y".to_string()].into();
+        let string = |x: &str| Value::String(x.to_string());
+        let expected = vec![
+            vec![
+                Value::Struct(names.clone(), vec![Value::Int(1), string("a")]),
+                Value::List(vec![Value::Int(1), Value::Int(2)]),
+                Value::Map(vec![(string("k"), Value::Int(1))]),
+                Value::Union(0, Box::new(Value::Int(5))),
+            ],
+            vec![
+                Value::Null,
+                Value::Null,
+                Value::Map(vec![]),
+                Value::Union(1, Box::new(string("s"))),
+            ],
+            vec![
+                Value::Struct(names, vec![Value::Null, string("b")]),
+                Value::List(vec![]),
+                Value::Null,
+                Value::Null,
+            ],
+        ];
+        assert_eq!(rows, expected);
+        Ok(())
+    }
+
+    /// Finishes the stripe of `writers` (of the root's children) and returns the file
+    fn finish(
+        types: Vec<Type>,
+        writers: &mut [ColumnWriter],
+        rows: u64,
+        options: WriteOptions,
+    ) -> Result<Vec<u8>, Error> {
+        let mut stripe = EncodedStripe {
+            number_of_rows: rows,
+            encodings: vec![ColumnEncoding {
+                kind: Some(ColumnEncodingKind::Direct as i32),
+                dictionary_size: None,
+            }],
+            ..Default::default()
+        };
+        for writer in writers.iter_mut() {
+            writer.finish_stripe(&mut stripe)?;
+        }
+        stripe.statistics.clear();
+        let mut file = FileWriter::try_new(vec![], types, options)?;
+        file.write_stripe(stripe)?;
+        file.finish()
+    }
+
+    /// Returns the kind and data of the streams of `column` in the first stripe of `file`
+    fn streams(file: &[u8], column: u32) -> Result<Vec<(Kind, &[u8])>, Error> {
+        let mut reader = std::io::Cursor::new(file);
+        let metadata = crate::read::read_metadata(&mut reader)?;
+        let footer = crate::read::read_stripe_footer(&mut reader, &metadata, 0, &mut vec![])?;
+        let mut start = metadata.footer.stripes[0].offset() as usize;
+        Ok(footer
+            .streams
+            .iter()
+            .filter_map(|stream| {
+                let data = &file[start..start + stream.length() as usize];
+                start += stream.length() as usize;
+                (stream.column() == column).then_some((stream.kind(), data))
+            })
+            .collect())
+    }
+
+    /// Returns the message of the (index) stream `kind` of `streams`
+    fn index<M: Message + Default>(
+        streams: &[(Kind, &[u8])],
+        kind: Kind,
+        compression: CompressionKind,
+    ) -> M {
+        let (_, stream) = streams.iter().find(|(k, _)| *k == kind).unwrap();
+        let mut data = vec![];
+        Decompressor::new(stream, compression, vec![])
+            .read_to_end(&mut data)
+            .unwrap();
+        M::decode(data.as_slice()).unwrap()
+    }
+
+    /// Returns the (decompressed) data of the stream `kind` of `streams` from the position
+    /// at the front of `positions`
+    fn seek(
+        streams: &[(Kind, &[u8])],
+        kind: Kind,
+        compression: CompressionKind,
+        positions: &mut std::slice::Iter<u64>,
+    ) -> Vec<u8> {
+        let (_, stream) = streams.iter().find(|(k, _)| *k == kind).unwrap();
+        let start = *positions.next().unwrap() as usize;
+        let mut data = vec![];
+        Decompressor::new(&stream[start..], compression, vec![])
+            .read_to_end(&mut data)
+            .unwrap();
+        if compression != CompressionKind::None {
+            data.drain(..*positions.next().unwrap() as usize);
+        }
+        data
+    }
+
+    #[test]
+    fn row_index() -> Result<(), Error> {
+        let types = struct_(&[
+            ("a", TypeKind::Long),
+            ("b", TypeKind::String),
+            ("c", TypeKind::String),
+        ]);
+        let strings = ["c", "a", "b"];
+        for compression in [CompressionKind::None, CompressionKind::Zlib] {
+            let options = WriteOptions {
+                row_index_stride: 100,
+                compression,
+                compression_block_size: 64,
+                ..Default::default()
+            };
+            let mut writers = (1..4)
+                .map(|column| ColumnWriter::try_new(&types, column, &options))
+                .collect::<Result<Vec<_>, _>>()?;
+            let [ColumnWriter::Integer(a), ColumnWriter::String(b), ColumnWriter::String(c)] =
+                &mut writers[..]
+            else {
+                panic!()
+            };
+            // 10 row groups of 100 rows and 1 of 50 rows
+            for row in 0..1050i64 {
+                a.write((row % 5 != 4).then_some(row * 7));
+                b.write(Some(&row.to_string()));
+                c.write(Some(strings[row as usize % 3]));
+                if row % 100 == 99 {
+                    a.finish_row_group()?;
+                    b.finish_row_group()?;
+                    c.finish_row_group()?;
+                }
+            }
+            let file = finish(types.clone(), &mut writers, 1050, options)?;
+            let compressed = (compression != CompressionKind::None) as usize;
+
+            // integers with nulls: Present and Data
+            let streams = streams(&file, 1)?;
+            let row_index: RowIndex = index(&streams, Kind::RowIndex, compression);
+            assert_eq!(row_index.entry.len(), 11);
+            for (row_group, entry) in row_index.entry.iter().enumerate() {
+                let row = row_group as i64 * 100;
+                assert_eq!(entry.positions.len(), 5 + 2 * compressed);
+                let values = entry.statistics.as_ref().unwrap().number_of_values();
+                assert_eq!(values, if row_group < 10 { 80 } else { 40 });
+
+                let mut positions = entry.positions.iter();
+                let present = seek(&streams, Kind::Present, compression, &mut positions);
+                let skip = positions.next().unwrap() * 8 + positions.next().unwrap();
+                let mut present = BooleanIter::new(present.as_slice(), skip as usize + 5);
+                let present = present.by_ref().skip(skip as usize);
+                let present = present.collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(present, [true, true, true, true, false]);
+
+                let data = seek(&streams, Kind::Data, compression, &mut positions);
+                let skip = *positions.next().unwrap() as usize;
+                let value = SignedRleV2Iter::new(data.as_slice(), skip + 1, vec![]).nth(skip);
+                assert_eq!(value.transpose()?, Some(row * 7));
+            }
+
+            // strings without nulls directly encoded: Data and Length
+            let streams = self::streams(&file, 2)?;
+            let row_index: RowIndex = index(&streams, Kind::RowIndex, compression);
+            assert_eq!(row_index.entry.len(), 11);
+            for (row_group, entry) in row_index.entry.iter().enumerate() {
+                let row = (row_group * 100).to_string();
+                assert_eq!(entry.positions.len(), 3 + 2 * compressed);
+
+                let mut positions = entry.positions.iter();
+                let data = seek(&streams, Kind::Data, compression, &mut positions);
+                assert!(data.starts_with(row.as_bytes()));
+                let lengths = seek(&streams, Kind::Length, compression, &mut positions);
+                let skip = *positions.next().unwrap() as usize;
+                let length = UnsignedRleV2Iter::new(lengths.as_slice(), skip + 1, vec![]).nth(skip);
+                assert_eq!(length.transpose()?, Some(row.len() as u64));
+            }
+
+            // strings without nulls in a dictionary: Data
+            let streams = self::streams(&file, 3)?;
+            let row_index: RowIndex = index(&streams, Kind::RowIndex, compression);
+            assert_eq!(row_index.entry.len(), 11);
+            for (row_group, entry) in row_index.entry.iter().enumerate() {
+                assert_eq!(entry.positions.len(), 2 + compressed);
+
+                let mut positions = entry.positions.iter();
+                let data = seek(&streams, Kind::Data, compression, &mut positions);
+                let skip = *positions.next().unwrap() as usize;
+                let index = UnsignedRleV2Iter::new(data.as_slice(), skip + 1, vec![]).nth(skip);
+                // "a", "b" and "c" are at 0, 1 and 2 of the dictionary
+                let expected = (row_group * 100 % 3 + 2) % 3;
+                assert_eq!(index.transpose()?, Some(expected as u64));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn last_row_group_of_children() -> Result<(), Error> {
+        let types = vec![
+            type_(TypeKind::Struct, vec![1], vec!["l"]),
+            type_(TypeKind::List, vec![2], vec![]),
+            type_(TypeKind::Int, vec![], vec![]),
+        ];
+        let options = WriteOptions {
+            row_index_stride: 10,
+            ..Default::default()
+        };
+        let mut writer = crate::write::OrcWriter::try_new(vec![], types, options)?;
+        // the lists of the last row group are empty: the items have no values in it
+        for row in 0..15 {
+            let [ColumnWriter::List(l)] = writer.columns_mut() else {
+                panic!()
+            };
+            l.write(Some((row < 10) as usize));
+            if row < 10 {
+                let ColumnWriter::Integer(items) = l.child_mut() else {
+                    panic!()
+                };
+                items.write(Some(row));
+            }
+            writer.write_rows(1)?;
+        }
+        let file = writer.finish()?;
+
+        for column in 0..3 {
+            let streams = streams(&file, column)?;
+            let row_index: RowIndex = index(&streams, Kind::RowIndex, CompressionKind::None);
+            assert_eq!(row_index.entry.len(), 2);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bloom_filters() -> Result<(), Error> {
+        let types = struct_(&[("a", TypeKind::Long), ("b", TypeKind::String)]);
+        let write = |options: WriteOptions| -> Result<Vec<u8>, Error> {
+            let mut writers = (1..3)
+                .map(|column| ColumnWriter::try_new(&types, column, &options))
+                .collect::<Result<Vec<_>, _>>()?;
+            let [ColumnWriter::Integer(a), ColumnWriter::String(b)] = &mut writers[..] else {
+                panic!()
+            };
+            for row in 0..25i64 {
+                a.write((row != 3).then_some(row));
+                b.write(Some(&row.to_string()));
+                if row % 10 == 9 {
+                    a.finish_row_group()?;
+                    b.finish_row_group()?;
+                }
+            }
+            finish(types.clone(), &mut writers, 25, options)
+        };
+        let kinds = |file: &[u8], column| -> Result<Vec<Kind>, Error> {
+            Ok(streams(file, column)?.into_iter().map(|x| x.0).collect())
+        };
+
+        let options = WriteOptions {
+            row_index_stride: 10,
+            bloom_filter_columns: vec![1],
+            bloom_filter_version: BloomFilterVersion::Original,
+            compression: CompressionKind::Zlib,
+            ..Default::default()
+        };
+        let file = write(options.clone())?;
+        let streams = streams(&file, 1)?;
+        let expected = [Kind::RowIndex, Kind::BloomFilter, Kind::BloomFilterUtf8];
+        assert_eq!(kinds(&file, 1)?[..3], expected);
+        assert_eq!(kinds(&file, 2)?[0], Kind::RowIndex);
+        assert!(!kinds(&file, 2)?.contains(&Kind::BloomFilterUtf8));
+
+        let utf8: BloomFilterIndex = index(&streams, Kind::BloomFilterUtf8, CompressionKind::Zlib);
+        let original: BloomFilterIndex = index(&streams, Kind::BloomFilter, CompressionKind::Zlib);
+        assert_eq!(utf8.bloom_filter.len(), 3);
+        for (row_group, (utf8, original)) in utf8
+            .bloom_filter
+            .iter()
+            .zip(&original.bloom_filter)
+            .enumerate()
+        {
+            let mut expected = BloomFilter::new(10, 0.05);
+            let rows = row_group as i64 * 10..(row_group as i64 * 10 + 10).min(25);
+            rows.filter(|row| *row != 3)
+                .for_each(|row| expected.add_long(row));
+            assert_eq!(utf8, &expected.to_proto(true));
+            assert_eq!(original, &expected.to_proto(false));
+        }
+
+        // the data of the column is read after its index streams
+        let mut reader = std::io::Cursor::new(&file);
+        let metadata = crate::read::read_metadata(&mut reader)?;
+        let footer = crate::read::read_stripe_footer(&mut reader, &metadata, 0, &mut vec![])?;
+        let column = crate::read::read_stripe_column(&mut reader, &metadata, 0, footer, 1, vec![])?;
+        let values = SignedRleV2Iter::new(column.get_stream(Kind::Data, vec![])?, 24, vec![])
+            .collect::<Result<Vec<_>, Error>>()?;
+        assert_eq!(values[..4], [0, 1, 2, 4]);
+
+        let file = write(WriteOptions {
+            bloom_filter_version: BloomFilterVersion::Utf8,
+            ..options.clone()
+        })?;
+        assert_eq!(
+            kinds(&file, 1)?[..2],
+            [Kind::RowIndex, Kind::BloomFilterUtf8]
+        );
+        assert!(!kinds(&file, 1)?.contains(&Kind::BloomFilter));
+
+        // without a row index, there are neither row indexes nor bloom filters
+        let file = write(WriteOptions {
+            row_index_stride: 0,
+            ..options.clone()
+        })?;
+        assert_eq!(kinds(&file, 1)?, [Kind::Present, Kind::Data]);
+
+        let options = WriteOptions {
+            bloom_filter_fpp: 1.0,
+            ..options
+        };
+        assert!(ColumnWriter::try_new(&types, 1, &options).is_err());
+        assert!(ColumnWriter::try_new(&types, 2, &options).is_ok());
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn round_trip() -> Result<(), Error> {
+        use crate::de::{read_stripe, Value};
+
+        for (compression, threshold) in [(CompressionKind::None, 0.0), (CompressionKind::Zlib, 1.0)]
+        {
+            let options = WriteOptions {
+                compression,
+                compression_block_size: 16,
+                dictionary_key_size_threshold: threshold,
+                ..Default::default()
+            };
+            let file = write(7, options)?;
+            let mut reader = std::io::Cursor::new(&file);
+            let metadata = crate::read::read_metadata(&mut reader)?;
+            let rows = read_stripe(&mut reader, &metadata, 0)?;
+            assert_eq!(rows.len(), 7);
+
+            let Value::Struct(_, row) = &rows[4] else {
+                panic!()
+            };
+            let expected = [
+                Value::Boolean(true),
+                Value::Byte(-4),
+                Value::Int(4000),
+                Value::Float(2.0),
+                Value::Double(-4.0),
+                Value::String("bb".to_string()),
+                Value::String("bb".to_string()),
+                Value::String("bb ".to_string()),
+                Value::Binary(b"bb".to_vec()),
+                Value::Date(3),
+                Value::Timestamp(6_000_000_000),
+                Value::Decimal(-420, 2),
+            ];
+            assert_eq!(row, &expected);
+            let Value::Struct(_, row) = &rows[5] else {
+                panic!()
+            };
+            assert!(row.iter().all(|value| *value == Value::Null));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+use crate::error::Error;
+use crate::proto::column_encoding::Kind as ColumnEncodingKind;
+use crate::proto::stream::Kind;
+use crate::proto::ColumnStatistics;
+
+use super::super::encode::{ByteRleEncoder, UnsignedRleV2Encoder};
+use super::super::statistics::Values;
+use super::super::{EncodedStripe, WriteOptions};
+use super::{writer, Base, ColumnWriter, StreamWriter};
+
+/// Finishes the stripe of every child, in order, whose row groups are finished
+fn finish_children(children: &mut [ColumnWriter], stripe: &mut EncodedStripe) -> Result<(), Error> {
+    children
+        .iter_mut()
+        .try_for_each(|child| child.end_stripe(stripe))
+}
+
+/// A writer of a column of structs.
+///
+/// Its children only contain the rows where the struct is valid: for every valid row,
+/// one value (or null) must be written to each child.
+#[derive(Debug)]
+pub struct StructWriter {
+    base: Base,
+    pub(super) children: Vec<ColumnWriter>,
+}
+
+impl StructWriter {
+    pub(super) fn try_new(
+        column: u32,
+        children: Vec<ColumnWriter>,
+        options: &WriteOptions,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            base: Base::try_new(column, Values::None, options)?,
+            children,
+        })
+    }
+
+    /// Writes whether the next struct is valid (not null)
+    #[inline]
+    pub fn write(&mut self, is_valid: bool) {
+        self.base.present(is_valid);
+        if is_valid {
+            self.base.row_group.value();
+        }
+    }
+
+    /// The writers of the fields of the struct
+    pub fn children_mut(&mut self) -> &mut [ColumnWriter] {
+        &mut self.children
+    }
+
+    /// Adds the streams, encoding and statistics of the current stripe to `stripe`
+    fn write_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        self.base
+            .finish_stripe(stripe, ColumnEncodingKind::Direct, None)?;
+        finish_children(&mut self.children, stripe)
+    }
+}
+
+writer!(StructWriter, [], children);
+
+/// A writer of a column of lists.
+///
+/// Its child contains the items of the valid lists, one after the other: for every valid
+/// list of length `n`, `n` items must be written to the child.
+#[derive(Debug)]
+pub struct ListWriter {
+    base: Base,
+    lengths: StreamWriter<UnsignedRleV2Encoder>,
+    /// the writer of the items (a single child)
+    pub(super) children: Vec<ColumnWriter>,
+}
+
+impl ListWriter {
+    pub(super) fn try_new(
+        column: u32,
+        child: ColumnWriter,
+        options: &WriteOptions,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            base: Base::try_new(column, Values::None, options)?,
+            lengths: StreamWriter::try_new(Kind::Length, options)?,
+            children: vec![child],
+        })
+    }
+
+    /// Writes the length of the next list, or a null
+    #[inline]
+    pub fn write(&mut self, length: Option<usize>) {
+        self.base.present(length.is_some());
+        if let Some(length) = length {
+            self.lengths.encoder.encode(length as u64);
+            self.base.row_group.value();
+        }
+    }
+
+    /// The writer of the items of the lists
+    pub fn child_mut(&mut self) -> &mut ColumnWriter {
+        &mut self.children[0]
+    }
+
+    /// Adds the streams, encoding and statistics of the current stripe to `stripe`
+    fn write_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        self.base
+            .finish_stripe(stripe, ColumnEncodingKind::DirectV2, None)?;
+        self.lengths.finish(self.base.column, &mut stripe.streams)?;
+        finish_children(&mut self.children, stripe)
+    }
+}
+
+writer!(ListWriter, [lengths], children);
+
+/// A writer of a column of maps.
+///
+/// Its children contain the keys and values of the entries of the valid maps, one after
+/// the other: for every valid map of length `n`, `n` keys and `n` values must be written.
+#[derive(Debug)]
+pub struct MapWriter {
+    base: Base,
+    lengths: StreamWriter<UnsignedRleV2Encoder>,
+    /// the writers of the keys and of the values
+    pub(super) children: Vec<ColumnWriter>,
+}
+
+impl MapWriter {
+    pub(super) fn try_new(
+        column: u32,
+        keys: ColumnWriter,
+        values: ColumnWriter,
+        options: &WriteOptions,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            base: Base::try_new(column, Values::None, options)?,
+            lengths: StreamWriter::try_new(Kind::Length, options)?,
+            children: vec![keys, values],
+        })
+    }
+
+    /// Writes the number of entries of the next map, or a null
+    #[inline]
+    pub fn write(&mut self, length: Option<usize>) {
+        self.base.present(length.is_some());
+        if let Some(length) = length {
+            self.lengths.encoder.encode(length as u64);
+            self.base.row_group.value();
+        }
+    }
+
+    /// The writers of the keys and of the values of the maps
+    pub fn children_mut(&mut self) -> (&mut ColumnWriter, &mut ColumnWriter) {
+        let (keys, values) = self.children.split_at_mut(1);
+        (&mut keys[0], &mut values[0])
+    }
+
+    /// Adds the streams, encoding and statistics of the current stripe to `stripe`
+    fn write_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        self.base
+            .finish_stripe(stripe, ColumnEncodingKind::DirectV2, None)?;
+        self.lengths.finish(self.base.column, &mut stripe.streams)?;
+        finish_children(&mut self.children, stripe)
+    }
+}
+
+writer!(MapWriter, [lengths], children);
+
+/// A writer of a column of unions.
+///
+/// Its children only contain the rows with their tag: for every valid row with tag `t`,
+/// one value (or null) must be written to the child `t`.
+#[derive(Debug)]
+pub struct UnionWriter {
+    base: Base,
+    tags: StreamWriter<ByteRleEncoder>,
+    pub(super) children: Vec<ColumnWriter>,
+}
+
+impl UnionWriter {
+    pub(super) fn try_new(
+        column: u32,
+        children: Vec<ColumnWriter>,
+        options: &WriteOptions,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            base: Base::try_new(column, Values::None, options)?,
+            tags: StreamWriter::try_new(Kind::Data, options)?,
+            children,
+        })
+    }
+
+    /// Writes the tag (the variant) of the next union, or a null
+    /// # Errors
+    /// This function errors when the union has no variant `tag`.
+    #[inline]
+    pub fn write(&mut self, tag: Option<u8>) -> Result<(), Error> {
+        if tag.is_some_and(|tag| tag as usize >= self.children.len()) {
+            return Err(Error::OutOfSpec(
+                "a union's tag must be one of its variants",
+            ));
+        }
+        self.base.present(tag.is_some());
+        if let Some(tag) = tag {
+            self.tags.encoder.encode(tag);
+            self.base.row_group.value();
+        }
+        Ok(())
+    }
+
+    /// The writers of the variants of the union
+    pub fn children_mut(&mut self) -> &mut [ColumnWriter] {
+        &mut self.children
+    }
+
+    /// Adds the streams, encoding and statistics of the current stripe to `stripe`
+    fn write_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        self.base
+            .finish_stripe(stripe, ColumnEncodingKind::Direct, None)?;
+        self.tags.finish(self.base.column, &mut stripe.streams)?;
+        finish_children(&mut self.children, stripe)
+    }
+}
+
+writer!(UnionWriter, [tags], children);
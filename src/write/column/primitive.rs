@@ -0,0 +1,358 @@
+use crate::error::Error;
+use crate::proto::column_encoding::Kind as ColumnEncodingKind;
+use crate::proto::stream::Kind;
+use crate::proto::ColumnStatistics;
+use crate::read::decode::Float;
+use crate::read::ORC_EPOCH;
+
+use super::super::encode::{
+    BooleanRleEncoder, ByteRleEncoder, DecimalEncoder, SignedRleV2Encoder, UnsignedRleV2Encoder,
+};
+use super::super::statistics::{format_decimal, Values};
+use super::super::{EncodedStripe, WriteOptions};
+use super::{writer, Base, StreamWriter};
+
+/// A writer of a column of booleans
+#[derive(Debug)]
+pub struct BooleanWriter {
+    base: Base,
+    data: StreamWriter<BooleanRleEncoder>,
+}
+
+impl BooleanWriter {
+    pub(super) fn try_new(column: u32, options: &WriteOptions) -> Result<Self, Error> {
+        Ok(Self {
+            base: Base::try_new(column, Values::Boolean { true_count: 0 }, options)?,
+            data: StreamWriter::try_new(Kind::Data, options)?,
+        })
+    }
+
+    /// Writes a value or a null
+    #[inline]
+    pub fn write(&mut self, value: Option<bool>) {
+        self.base.present(value.is_some());
+        if let Some(value) = value {
+            self.data.encoder.encode(value);
+            self.base.row_group.boolean(value);
+            if let Some(bloom_filter) = self.base.bloom_filter() {
+                bloom_filter.add_long(value as i64)
+            }
+        }
+    }
+
+    /// Adds the streams, encoding and statistics of the current stripe to `stripe`
+    fn write_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        self.base
+            .finish_stripe(stripe, ColumnEncodingKind::Direct, None)?;
+        self.data.finish(self.base.column, &mut stripe.streams)
+    }
+}
+
+writer!(BooleanWriter, [data]);
+
+/// A writer of a column of bytes (tinyint)
+#[derive(Debug)]
+pub struct ByteWriter {
+    base: Base,
+    data: StreamWriter<ByteRleEncoder>,
+}
+
+impl ByteWriter {
+    pub(super) fn try_new(column: u32, options: &WriteOptions) -> Result<Self, Error> {
+        let values = Values::Integer {
+            range: None,
+            sum: None,
+        };
+        Ok(Self {
+            base: Base::try_new(column, values, options)?,
+            data: StreamWriter::try_new(Kind::Data, options)?,
+        })
+    }
+
+    /// Writes a value or a null
+    #[inline]
+    pub fn write(&mut self, value: Option<i8>) {
+        self.base.present(value.is_some());
+        if let Some(value) = value {
+            self.data.encoder.encode(value as u8);
+            self.base.row_group.integer(value as i64);
+            if let Some(bloom_filter) = self.base.bloom_filter() {
+                bloom_filter.add_long(value as i64)
+            }
+        }
+    }
+
+    /// Adds the streams, encoding and statistics of the current stripe to `stripe`
+    fn write_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        self.base
+            .finish_stripe(stripe, ColumnEncodingKind::Direct, None)?;
+        self.data.finish(self.base.column, &mut stripe.streams)
+    }
+}
+
+writer!(ByteWriter, [data]);
+
+/// A writer of a column of short, int or long
+#[derive(Debug)]
+pub struct IntegerWriter {
+    base: Base,
+    data: StreamWriter<SignedRleV2Encoder>,
+}
+
+impl IntegerWriter {
+    pub(super) fn try_new(column: u32, options: &WriteOptions) -> Result<Self, Error> {
+        let values = Values::Integer {
+            range: None,
+            sum: None,
+        };
+        Ok(Self {
+            base: Base::try_new(column, values, options)?,
+            data: StreamWriter::try_new(Kind::Data, options)?,
+        })
+    }
+
+    /// Writes a value or a null
+    #[inline]
+    pub fn write(&mut self, value: Option<i64>) {
+        self.base.present(value.is_some());
+        if let Some(value) = value {
+            self.data.encoder.encode(value);
+            self.base.row_group.integer(value);
+            if let Some(bloom_filter) = self.base.bloom_filter() {
+                bloom_filter.add_long(value)
+            }
+        }
+    }
+
+    /// Adds the streams, encoding and statistics of the current stripe to `stripe`
+    fn write_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        self.base
+            .finish_stripe(stripe, ColumnEncodingKind::DirectV2, None)?;
+        self.data.finish(self.base.column, &mut stripe.streams)
+    }
+}
+
+writer!(IntegerWriter, [data]);
+
+/// A writer of a column of floats (`f32`) or doubles (`f64`)
+#[derive(Debug)]
+pub struct FloatWriter<T: Float> {
+    base: Base,
+    data: StreamWriter<Vec<u8>>,
+    phantom: std::marker::PhantomData<T>,
+}
+
+impl<T: Float + Into<f64>> FloatWriter<T> {
+    pub(super) fn try_new(column: u32, options: &WriteOptions) -> Result<Self, Error> {
+        let values = Values::Double {
+            range: None,
+            sum: 0.0,
+        };
+        Ok(Self {
+            base: Base::try_new(column, values, options)?,
+            data: StreamWriter::try_new(Kind::Data, options)?,
+            phantom: Default::default(),
+        })
+    }
+
+    /// Writes a value or a null
+    #[inline]
+    pub fn write(&mut self, value: Option<T>) {
+        self.base.present(value.is_some());
+        if let Some(value) = value {
+            self.data
+                .encoder
+                .extend_from_slice(value.to_le_bytes().as_ref());
+            self.base.row_group.double(value.into());
+            if let Some(bloom_filter) = self.base.bloom_filter() {
+                bloom_filter.add_double(value.into())
+            }
+        }
+    }
+
+    /// Adds the streams, encoding and statistics of the current stripe to `stripe`
+    fn write_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        self.base
+            .finish_stripe(stripe, ColumnEncodingKind::Direct, None)?;
+        self.data.finish(self.base.column, &mut stripe.streams)
+    }
+}
+
+writer!(FloatWriter<f32>, [data]);
+writer!(FloatWriter<f64>, [data]);
+
+/// A writer of a column of dates, in days since the unix epoch
+#[derive(Debug)]
+pub struct DateWriter {
+    base: Base,
+    data: StreamWriter<SignedRleV2Encoder>,
+}
+
+impl DateWriter {
+    pub(super) fn try_new(column: u32, options: &WriteOptions) -> Result<Self, Error> {
+        Ok(Self {
+            base: Base::try_new(column, Values::Date { range: None }, options)?,
+            data: StreamWriter::try_new(Kind::Data, options)?,
+        })
+    }
+
+    /// Writes a value or a null
+    #[inline]
+    pub fn write(&mut self, value: Option<i32>) {
+        self.base.present(value.is_some());
+        if let Some(value) = value {
+            self.data.encoder.encode(value as i64);
+            self.base.row_group.date(value);
+            if let Some(bloom_filter) = self.base.bloom_filter() {
+                bloom_filter.add_long(value as i64)
+            }
+        }
+    }
+
+    /// Adds the streams, encoding and statistics of the current stripe to `stripe`
+    fn write_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        self.base
+            .finish_stripe(stripe, ColumnEncodingKind::DirectV2, None)?;
+        self.data.finish(self.base.column, &mut stripe.streams)
+    }
+}
+
+writer!(DateWriter, [data]);
+
+/// Encodes the nanoseconds of a timestamp, removing its trailing zeros and storing their
+/// number (minus one) in the 3 least significant bits.
+fn encode_nanoseconds(nanoseconds: u64) -> u64 {
+    if nanoseconds == 0 || !nanoseconds.is_multiple_of(100) {
+        return nanoseconds << 3;
+    }
+    let mut nanoseconds = nanoseconds / 100;
+    let mut zeros = 1;
+    while nanoseconds.is_multiple_of(10) && zeros < 7 {
+        nanoseconds /= 10;
+        zeros += 1;
+    }
+    nanoseconds << 3 | zeros
+}
+
+/// A writer of a column of timestamps, in nanoseconds since the unix epoch
+#[derive(Debug)]
+pub struct TimestampWriter {
+    base: Base,
+    seconds: StreamWriter<SignedRleV2Encoder>,
+    nanoseconds: StreamWriter<UnsignedRleV2Encoder>,
+}
+
+impl TimestampWriter {
+    pub(super) fn try_new(column: u32, options: &WriteOptions) -> Result<Self, Error> {
+        Ok(Self {
+            base: Base::try_new(column, Values::Timestamp { range: None }, options)?,
+            seconds: StreamWriter::try_new(Kind::Data, options)?,
+            nanoseconds: StreamWriter::try_new(Kind::Secondary, options)?,
+        })
+    }
+
+    /// Writes a value or a null.
+    ///
+    /// Like the reference implementation, the seconds of negative timestamps are truncated
+    /// towards zero, so timestamps in the second before the unix epoch are not represented.
+    #[inline]
+    pub fn write(&mut self, value: Option<i64>) {
+        self.base.present(value.is_some());
+        if let Some(value) = value {
+            let mut seconds = value.div_euclid(1_000_000_000);
+            let nanoseconds = value.rem_euclid(1_000_000_000) as u64;
+            if seconds < 0 && nanoseconds > 999_999 {
+                seconds += 1;
+            }
+            self.seconds.encoder.encode(seconds - ORC_EPOCH);
+            self.nanoseconds
+                .encoder
+                .encode(encode_nanoseconds(nanoseconds));
+            self.base.row_group.timestamp(value);
+            if let Some(bloom_filter) = self.base.bloom_filter() {
+                bloom_filter.add_long(value.div_euclid(1_000_000))
+            }
+        }
+    }
+
+    /// Adds the streams, encoding and statistics of the current stripe to `stripe`
+    fn write_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        self.base
+            .finish_stripe(stripe, ColumnEncodingKind::DirectV2, None)?;
+        self.seconds.finish(self.base.column, &mut stripe.streams)?;
+        self.nanoseconds
+            .finish(self.base.column, &mut stripe.streams)
+    }
+}
+
+writer!(TimestampWriter, [seconds, nanoseconds]);
+
+/// A writer of a column of decimals, whose (unscaled) values have the scale of the column
+#[derive(Debug)]
+pub struct DecimalWriter {
+    base: Base,
+    scale: u32,
+    data: StreamWriter<DecimalEncoder>,
+    scales: StreamWriter<SignedRleV2Encoder>,
+}
+
+impl DecimalWriter {
+    pub(super) fn try_new(column: u32, scale: u32, options: &WriteOptions) -> Result<Self, Error> {
+        let values = Values::Decimal {
+            range: None,
+            sum: None,
+            scale,
+        };
+        Ok(Self {
+            base: Base::try_new(column, values, options)?,
+            scale,
+            data: StreamWriter::try_new(Kind::Data, options)?,
+            scales: StreamWriter::try_new(Kind::Secondary, options)?,
+        })
+    }
+
+    /// Writes a value or a null
+    #[inline]
+    pub fn write(&mut self, value: Option<i128>) {
+        self.base.present(value.is_some());
+        if let Some(value) = value {
+            self.data.encoder.encode(value);
+            self.scales.encoder.encode(self.scale as i64);
+            self.base.row_group.decimal(value);
+            if let Some(bloom_filter) = self.base.bloom_filter() {
+                // as the reference implementation, without trailing zeros
+                let value = format_decimal(value, self.scale);
+                let value = match value.contains('.') {
+                    true => value.trim_end_matches('0').trim_end_matches('.'),
+                    false => &value,
+                };
+                bloom_filter.add_bytes(value.as_bytes())
+            }
+        }
+    }
+
+    /// Adds the streams, encoding and statistics of the current stripe to `stripe`
+    fn write_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        self.base
+            .finish_stripe(stripe, ColumnEncodingKind::DirectV2, None)?;
+        self.data.finish(self.base.column, &mut stripe.streams)?;
+        self.scales.finish(self.base.column, &mut stripe.streams)
+    }
+}
+
+writer!(DecimalWriter, [data, scales]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nanoseconds() {
+        assert_eq!(encode_nanoseconds(0), 0);
+        assert_eq!(encode_nanoseconds(1), 1 << 3);
+        assert_eq!(encode_nanoseconds(1_000), 1 << 3 | 2);
+        assert_eq!(encode_nanoseconds(120_000), 12 << 3 | 3);
+        assert_eq!(encode_nanoseconds(500_000_000), 5 << 3 | 7);
+        assert_eq!(encode_nanoseconds(999_999_999), 999_999_999 << 3);
+    }
+}
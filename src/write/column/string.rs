@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::proto::column_encoding::Kind as ColumnEncodingKind;
+use crate::proto::stream::Kind;
+use crate::proto::ColumnStatistics;
+
+use super::super::encode::UnsignedRleV2Encoder;
+use super::super::statistics::Values;
+use super::super::{EncodedStripe, WriteOptions};
+use super::{writer, Base, StreamWriter};
+
+/// Truncates `value` to `length` characters, padding it with spaces to `length` when `padded`
+fn fit(value: &str, length: usize, padded: bool) -> std::borrow::Cow<'_, str> {
+    match value.char_indices().nth(length) {
+        Some((end, _)) => value[..end].into(),
+        None if padded => {
+            let missing = length - value.chars().count();
+            format!("{value}{}", " ".repeat(missing)).into()
+        }
+        None => value.into(),
+    }
+}
+
+/// A writer of a column of string, varchar or char.
+///
+/// Values are kept in a dictionary until the end of the stripe, when the column is encoded
+/// with [`ColumnEncodingKind::DictionaryV2`] if the number of distinct values is at most
+/// [`WriteOptions::dictionary_key_size_threshold`] of the number of values, and with
+/// [`ColumnEncodingKind::DirectV2`] otherwise.
+#[derive(Debug)]
+pub struct StringWriter {
+    base: Base,
+    /// the maximum length in characters of varchar and char, and whether values are padded
+    max_length: Option<(usize, bool)>,
+    threshold: f64,
+    dictionary: HashMap<String, u32>,
+    /// the number of bytes of the values of the dictionary
+    dictionary_bytes: usize,
+    /// the index in the dictionary of every value of the stripe
+    indices: Vec<u32>,
+    data: StreamWriter<Vec<u8>>,
+    dictionary_indices: StreamWriter<UnsignedRleV2Encoder>,
+    dictionary_data: StreamWriter<Vec<u8>>,
+    lengths: StreamWriter<UnsignedRleV2Encoder>,
+}
+
+impl StringWriter {
+    pub(super) fn try_new(
+        column: u32,
+        max_length: Option<(usize, bool)>,
+        options: &WriteOptions,
+    ) -> Result<Self, Error> {
+        let values = Values::String {
+            range: None,
+            sum: 0,
+        };
+        Ok(Self {
+            base: Base::try_new(column, values, options)?,
+            max_length,
+            threshold: options.dictionary_key_size_threshold,
+            dictionary: HashMap::new(),
+            dictionary_bytes: 0,
+            indices: vec![],
+            data: StreamWriter::try_new(Kind::Data, options)?,
+            dictionary_indices: StreamWriter::try_new(Kind::Data, options)?,
+            dictionary_data: StreamWriter::try_new(Kind::DictionaryData, options)?,
+            lengths: StreamWriter::try_new(Kind::Length, options)?,
+        })
+    }
+
+    /// Writes a value or a null. Values of varchar and char are truncated to their maximum
+    /// length, and values of char are padded with spaces to it.
+    pub fn write(&mut self, value: Option<&str>) {
+        self.base.present(value.is_some());
+        let Some(value) = value else {
+            return;
+        };
+        let value = match self.max_length {
+            Some((length, padded)) => fit(value, length, padded),
+            None => value.into(),
+        };
+        let index = match self.dictionary.get(value.as_ref()) {
+            Some(index) => *index,
+            None => {
+                let index = self.dictionary.len() as u32;
+                self.dictionary.insert(value.to_string(), index);
+                self.dictionary_bytes += value.len();
+                index
+            }
+        };
+        self.indices.push(index);
+        self.base.row_group.string(&value);
+        if let Some(bloom_filter) = self.base.bloom_filter() {
+            bloom_filter.add_bytes(value.as_bytes())
+        }
+    }
+
+    /// The size of the values buffered until the end of the stripe: the dictionary and the
+    /// index of every value
+    fn buffered_size(&self) -> u64 {
+        (self.dictionary_bytes + self.indices.len() * 4) as u64
+    }
+
+    /// Whether the current stripe is encoded with a dictionary
+    fn use_dictionary(&self) -> bool {
+        let ratio = if self.indices.is_empty() {
+            0.0
+        } else {
+            self.dictionary.len() as f64 / self.indices.len() as f64
+        };
+        ratio <= self.threshold
+    }
+
+    /// Adds the streams, encoding and statistics of the current stripe to `stripe`.
+    ///
+    /// The streams of values are only encoded here, once the encoding is known: the
+    /// positions of the row groups in them are recorded as their values are encoded.
+    fn write_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        let column = self.base.column;
+        let use_dictionary = self.use_dictionary();
+        self.dictionary_bytes = 0;
+        let mut keys = std::mem::take(&mut self.dictionary)
+            .into_iter()
+            .collect::<Vec<_>>();
+        let row_groups = self
+            .base
+            .row_groups
+            .iter()
+            .map(|statistics| statistics.number_of_values() as usize)
+            .collect::<Vec<_>>();
+        let mut indices = self.indices.drain(..);
+        if use_dictionary {
+            // the dictionary is sorted, as written by the reference implementation
+            keys.sort_unstable();
+            let mut positions = vec![0u32; keys.len()];
+            for (position, (key, index)) in keys.iter().enumerate() {
+                positions[*index as usize] = position as u32;
+                self.dictionary_data
+                    .encoder
+                    .extend_from_slice(key.as_bytes());
+                self.lengths.encoder.encode(key.len() as u64);
+            }
+            for (row_group, values) in row_groups.into_iter().enumerate() {
+                if let Some(row_group) = self.base.positions(row_group) {
+                    self.dictionary_indices.record(row_group)?;
+                }
+                for index in indices.by_ref().take(values) {
+                    self.dictionary_indices
+                        .encoder
+                        .encode(positions[index as usize] as u64);
+                }
+            }
+
+            self.base.finish_stripe(
+                stripe,
+                ColumnEncodingKind::DictionaryV2,
+                Some(keys.len() as u32),
+            )?;
+            self.dictionary_indices
+                .finish(column, &mut stripe.streams)?;
+            self.dictionary_data.finish(column, &mut stripe.streams)?;
+        } else {
+            keys.sort_unstable_by_key(|(_, index)| *index);
+            for (row_group, values) in row_groups.into_iter().enumerate() {
+                if let Some(row_group) = self.base.positions(row_group) {
+                    self.data.record(row_group)?;
+                    self.lengths.record(row_group)?;
+                }
+                for index in indices.by_ref().take(values) {
+                    let key = &keys[index as usize].0;
+                    self.data.encoder.extend_from_slice(key.as_bytes());
+                    self.lengths.encoder.encode(key.len() as u64);
+                }
+            }
+
+            self.base
+                .finish_stripe(stripe, ColumnEncodingKind::DirectV2, None)?;
+            self.data.finish(column, &mut stripe.streams)?;
+        }
+        self.lengths.finish(column, &mut stripe.streams)
+    }
+}
+
+writer!(StringWriter, []; buffered_size);
+
+/// A writer of a column of binary
+#[derive(Debug)]
+pub struct BinaryWriter {
+    base: Base,
+    data: StreamWriter<Vec<u8>>,
+    lengths: StreamWriter<UnsignedRleV2Encoder>,
+}
+
+impl BinaryWriter {
+    pub(super) fn try_new(column: u32, options: &WriteOptions) -> Result<Self, Error> {
+        Ok(Self {
+            base: Base::try_new(column, Values::Binary { sum: 0 }, options)?,
+            data: StreamWriter::try_new(Kind::Data, options)?,
+            lengths: StreamWriter::try_new(Kind::Length, options)?,
+        })
+    }
+
+    /// Writes a value or a null
+    #[inline]
+    pub fn write(&mut self, value: Option<&[u8]>) {
+        self.base.present(value.is_some());
+        if let Some(value) = value {
+            self.data.encoder.extend_from_slice(value);
+            self.lengths.encoder.encode(value.len() as u64);
+            self.base.row_group.binary(value);
+            if let Some(bloom_filter) = self.base.bloom_filter() {
+                bloom_filter.add_bytes(value)
+            }
+        }
+    }
+
+    /// Adds the streams, encoding and statistics of the current stripe to `stripe`
+    fn write_stripe(&mut self, stripe: &mut EncodedStripe) -> Result<(), Error> {
+        self.base
+            .finish_stripe(stripe, ColumnEncodingKind::DirectV2, None)?;
+        self.data.finish(self.base.column, &mut stripe.streams)?;
+        self.lengths.finish(self.base.column, &mut stripe.streams)
+    }
+}
+
+writer!(BinaryWriter, [data, lengths]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_to_length() {
+        assert_eq!(fit("abc", 2, false), "ab");
+        assert_eq!(fit("abc", 5, false), "abc");
+        assert_eq!(fit("abc", 5, true), "abc  ");
+        assert_eq!(fit("ação", 2, true), "aç");
+    }
+}
@@ -0,0 +1,274 @@
+//! Contains [`Compressor`]
+use std::io::Write;
+use std::sync::Arc;
+
+use crate::error::Error;
+use crate::proto::CompressionKind;
+
+/// The default size of the (uncompressed) chunks of compressed streams
+pub const DEFAULT_COMPRESSION_BLOCK_SIZE: u64 = 256 * 1024;
+
+/// The maximum size of a chunk, whose length is stored in 23 bits of its header
+const MAX_CHUNK_SIZE: u64 = (1 << 23) - 1;
+
+/// The 3 bytes header of a chunk, the inverse of `decode_header`
+fn encode_header(is_original: bool, length: usize) -> [u8; 3] {
+    let header = (length as u32) << 1 | is_original as u32;
+    let [a, b, c, _] = header.to_le_bytes();
+    [a, b, c]
+}
+
+/// Compresses a stream in chunks of up to `compression_block_size` bytes, the inverse of
+/// [`Decompressor`]. Each chunk is prefixed by its header and stored as original when
+/// compressing it does not make it smaller.
+///
+/// Streams of files without compression are written as is.
+///
+/// # Example
+/// ```
+/// use std::io::{Read, Write};
+/// use orc_format::proto::CompressionKind;
+/// use orc_format::read::decompress::Decompressor;
+/// use orc_format::write::Compressor;
+///
+/// let mut compressor = Compressor::try_new(CompressionKind::Zlib, 1024)?;
+/// compressor.write_all(&[1; 4000])?;
+/// let stream = compressor.finish()?;
+/// assert!(stream.len() < 100);
+///
+/// let mut data = vec![];
+/// Decompressor::new(&stream, CompressionKind::Zlib, vec![]).read_to_end(&mut data)?;
+/// assert_eq!(data, vec![1; 4000]);
+/// # Ok::<(), orc_format::error::Error>(())
+/// ```
+///
+/// [`Decompressor`]: crate::read::decompress::Decompressor
+pub struct Compressor {
+    compression: CompressionKind,
+    block_size: usize,
+    /// the uncompressed data of the current chunk
+    buffer: Vec<u8>,
+    output: Vec<u8>,
+    scratch: Vec<u8>,
+    deflate: Option<flate2::Compress>,
+}
+
+impl std::fmt::Debug for Compressor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Compressor")
+            .field("compression", &self.compression)
+            .field("block_size", &self.block_size)
+            .field("buffered", &self.buffer.len())
+            .field("written", &self.output.len())
+            .finish()
+    }
+}
+
+impl Compressor {
+    /// Returns a new [`Compressor`] with chunks of up to `block_size` bytes.
+    /// # Errors
+    /// This function errors when `compression` is not supported or `block_size` is zero or
+    /// larger than the length a chunk's header can hold.
+    pub fn try_new(compression: CompressionKind, block_size: u64) -> Result<Self, Error> {
+        let deflate = match compression {
+            CompressionKind::None => None,
+            CompressionKind::Zlib => {
+                Some(flate2::Compress::new(flate2::Compression::default(), false))
+            }
+            other => return Err(Error::UnsupportedCompression(other)),
+        };
+        if block_size == 0 || block_size > MAX_CHUNK_SIZE {
+            return Err(Error::OutOfSpec(
+                "the compression block size must be between 1 byte and 8 MiB",
+            ));
+        }
+        Ok(Self {
+            compression,
+            block_size: block_size as usize,
+            buffer: vec![],
+            output: vec![],
+            scratch: vec![],
+            deflate,
+        })
+    }
+
+    /// The compression of this compressor
+    pub fn compression(&self) -> CompressionKind {
+        self.compression
+    }
+
+    /// Returns the position of the next byte written: the offset of its chunk in the
+    /// (compressed) output and its offset in the (uncompressed) chunk. Streams without
+    /// compression only have the first.
+    ///
+    /// These are the positions of a stream in the entries of a row index.
+    pub fn position(&self) -> (u64, Option<u64>) {
+        match self.deflate {
+            None => (self.output.len() as u64, None),
+            Some(_) => (self.output.len() as u64, Some(self.buffer.len() as u64)),
+        }
+    }
+
+    /// The number of bytes written to the output so far, excluding the current chunk
+    pub fn len(&self) -> usize {
+        self.output.len()
+    }
+
+    /// Whether nothing was written to the output so far
+    pub fn is_empty(&self) -> bool {
+        self.output.is_empty()
+    }
+
+    /// Compresses the current chunk to the output
+    fn write_chunk(&mut self) -> Result<(), Error> {
+        let Some(deflate) = self.deflate.as_mut() else {
+            return Ok(());
+        };
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        deflate.reset();
+        self.scratch.clear();
+        self.scratch.reserve(self.buffer.len());
+        let status = deflate
+            .compress_vec(
+                &self.buffer,
+                &mut self.scratch,
+                flate2::FlushCompress::Finish,
+            )
+            .map_err(|error| Error::Compression(Arc::new(std::io::Error::other(error))))?;
+
+        // chunks whose compression does not fit in their original size are stored as original
+        let compressed = status == flate2::Status::StreamEnd
+            && deflate.total_in() as usize == self.buffer.len()
+            && self.scratch.len() < self.buffer.len();
+        let chunk = if compressed {
+            &self.scratch
+        } else {
+            &self.buffer
+        };
+        self.output
+            .extend_from_slice(&encode_header(!compressed, chunk.len()));
+        self.output.extend_from_slice(chunk);
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Compresses the remaining data and takes the compressed stream, leaving the compressor
+    /// empty to compress a new stream
+    /// # Errors
+    /// This function errors when compressing fails.
+    pub fn take(&mut self) -> Result<Vec<u8>, Error> {
+        self.write_chunk()?;
+        Ok(std::mem::take(&mut self.output))
+    }
+
+    /// Compresses the remaining data and returns the compressed stream
+    /// # Errors
+    /// This function errors when compressing fails.
+    pub fn finish(mut self) -> Result<Vec<u8>, Error> {
+        self.write_chunk()?;
+        Ok(self.output)
+    }
+}
+
+impl Write for Compressor {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.deflate.is_none() {
+            self.output.extend_from_slice(buf);
+            return Ok(buf.len());
+        }
+        let length = buf.len().min(self.block_size - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..length]);
+        if self.buffer.len() == self.block_size {
+            self.write_chunk().map_err(std::io::Error::from)?;
+        }
+        Ok(length)
+    }
+
+    /// Compresses the current chunk, even when it is smaller than the block size
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.write_chunk().map_err(std::io::Error::from)
+    }
+}
+
+/// Returns `data` compressed with `compression` in chunks of `block_size` bytes
+pub fn compress(
+    data: &[u8],
+    compression: CompressionKind,
+    block_size: u64,
+) -> Result<Vec<u8>, Error> {
+    let mut compressor = Compressor::try_new(compression, block_size)?;
+    compressor.write_all(data)?;
+    compressor.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use crate::read::decompress::Decompressor;
+
+    use super::*;
+
+    fn decompress(stream: &[u8], compression: CompressionKind) -> Vec<u8> {
+        let mut data = vec![];
+        Decompressor::new(stream, compression, vec![])
+            .read_to_end(&mut data)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn header() {
+        assert_eq!(encode_header(true, 5), [0b1011, 0, 0]);
+        assert_eq!(encode_header(false, 100_000), [0x40, 0x0d, 0x03]);
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), Error> {
+        let data = (0..10_000u32)
+            .flat_map(|i| (i / 7).to_le_bytes())
+            .collect::<Vec<_>>();
+        for block_size in [10, 100, 1000, 1 << 20] {
+            let stream = compress(&data, CompressionKind::Zlib, block_size)?;
+            assert_eq!(decompress(&stream, CompressionKind::Zlib), data);
+        }
+
+        let stream = compress(&data, CompressionKind::None, 10)?;
+        assert_eq!(stream, data);
+        Ok(())
+    }
+
+    #[test]
+    fn original_chunks() -> Result<(), Error> {
+        // data that deflate can't shrink is stored as original
+        let data = [1, 2, 3, 4];
+        let stream = compress(&data, CompressionKind::Zlib, 2)?;
+        assert_eq!(stream, [0b101, 0, 0, 1, 2, 0b101, 0, 0, 3, 4]);
+        assert_eq!(decompress(&stream, CompressionKind::Zlib), data);
+        Ok(())
+    }
+
+    #[test]
+    fn positions() -> Result<(), Error> {
+        let mut compressor = Compressor::try_new(CompressionKind::Zlib, 4)?;
+        compressor.write_all(&[1, 2, 3, 4, 5, 6])?;
+        assert_eq!(compressor.position(), (7, Some(2)));
+        compressor.flush()?;
+        assert_eq!(compressor.position(), (12, Some(0)));
+
+        let mut compressor = Compressor::try_new(CompressionKind::None, 4)?;
+        compressor.write_all(&[1, 2, 3, 4, 5, 6])?;
+        assert_eq!(compressor.position(), (6, None));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Compressor::try_new(CompressionKind::Lzo, 10).is_err());
+        assert!(Compressor::try_new(CompressionKind::Zlib, 0).is_err());
+        assert!(Compressor::try_new(CompressionKind::Zlib, 1 << 23).is_err());
+    }
+}
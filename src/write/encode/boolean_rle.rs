@@ -0,0 +1,110 @@
+use super::ByteRleEncoder;
+
+/// Encodes booleans as bits (MSB first) of bytes encoded with [`ByteRleEncoder`], the
+/// inverse of [`BooleanIter`]. Used e.g. by the Present streams.
+///
+/// [`BooleanIter`]: crate::read::decode::BooleanIter
+#[derive(Debug, Default)]
+pub struct BooleanRleEncoder {
+    bytes: ByteRleEncoder,
+    current: u8,
+    bits: u8,
+}
+
+impl BooleanRleEncoder {
+    /// Returns a new empty encoder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encodes `value`. Values are buffered until they complete a run.
+    #[inline]
+    pub fn encode(&mut self, value: bool) {
+        self.current |= (value as u8) << (7 - self.bits);
+        self.bits += 1;
+        if self.bits == 8 {
+            self.bytes.encode(self.current);
+            self.current = 0;
+            self.bits = 0;
+        }
+    }
+
+    /// Encodes all `values`
+    pub fn extend(&mut self, values: &[bool]) {
+        values.iter().for_each(|value| self.encode(*value))
+    }
+
+    /// Writes the buffered values to the output, padding the last byte with zeros
+    pub fn flush(&mut self) {
+        if self.bits > 0 {
+            self.bytes.encode(self.current);
+            self.current = 0;
+            self.bits = 0;
+        }
+        self.bytes.flush()
+    }
+
+    /// The number of values encoded but not yet written to the output
+    pub fn buffered(&self) -> usize {
+        self.bytes.buffered() * 8 + self.bits as usize
+    }
+
+    /// The bytes written so far
+    pub fn output(&self) -> &[u8] {
+        self.bytes.output()
+    }
+
+    /// Takes the bytes written so far, leaving the buffered values in the encoder
+    pub fn take(&mut self) -> Vec<u8> {
+        self.bytes.take()
+    }
+
+    /// Flushes the buffered values and returns the encoded bytes
+    pub fn finish(mut self) -> Vec<u8> {
+        self.flush();
+        self.bytes.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use crate::read::decode::BooleanIter;
+
+    use super::*;
+
+    fn encode(values: &[bool]) -> Vec<u8> {
+        let mut encoder = BooleanRleEncoder::new();
+        encoder.extend(values);
+        encoder.finish()
+    }
+
+    fn decode(data: &[u8], length: usize) -> Vec<bool> {
+        BooleanIter::new(data, length)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn msb_first() {
+        let values = [true, false, false, false, false, false, false, true, true];
+        assert_eq!(encode(&values), [0xfe, 0b10000001, 0b10000000]);
+
+        assert_eq!(encode(&[true; 80]), [0x07, 0xff]);
+    }
+
+    quickcheck! {
+        fn round_trip(values: Vec<bool>) -> bool {
+            decode(&encode(&values), values.len()) == values
+        }
+
+        fn round_trip_runs(runs: Vec<(bool, u16)>) -> bool {
+            let values = runs
+                .into_iter()
+                .flat_map(|(value, length)| std::iter::repeat_n(value, length as usize % 100))
+                .collect::<Vec<_>>();
+            decode(&encode(&values), values.len()) == values
+        }
+    }
+}
@@ -0,0 +1,165 @@
+/// The minimum number of repeated bytes of a run
+const MIN_REPEAT: usize = 3;
+/// The maximum number of repeated bytes of a run
+const MAX_REPEAT: usize = 127 + MIN_REPEAT;
+/// The maximum number of bytes of literals
+const MAX_LITERALS: usize = 128;
+
+/// Encodes bytes with byte run-length encoding, the inverse of [`ByteIter`], choosing runs
+/// the way the reference implementation (Java's `RunLengthByteWriter`) does.
+///
+/// [`ByteIter`]: crate::read::decode::ByteIter
+#[derive(Debug, Default)]
+pub struct ByteRleEncoder {
+    output: Vec<u8>,
+    literals: Vec<u8>,
+    repeat: bool,
+    tail_run_length: usize,
+}
+
+impl ByteRleEncoder {
+    /// Returns a new empty encoder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encodes `value`. Values are buffered until they complete a run.
+    pub fn encode(&mut self, value: u8) {
+        let Some(&last) = self.literals.last() else {
+            self.literals.push(value);
+            self.tail_run_length = 1;
+            return;
+        };
+
+        if self.repeat {
+            if value == last {
+                self.literals.push(value);
+                if self.literals.len() == MAX_REPEAT {
+                    self.flush();
+                }
+            } else {
+                self.flush();
+                self.literals.push(value);
+                self.tail_run_length = 1;
+            }
+            return;
+        }
+
+        if value == last {
+            self.tail_run_length += 1;
+        } else {
+            self.tail_run_length = 1;
+        }
+        if self.tail_run_length == MIN_REPEAT {
+            // the repeated bytes start a run, after the literals before them
+            let literals = self.literals.len() + 1 - MIN_REPEAT;
+            if literals > 0 {
+                self.literals.truncate(literals);
+                self.flush();
+            }
+            self.literals.resize(MIN_REPEAT, value);
+            self.repeat = true;
+        } else {
+            self.literals.push(value);
+            if self.literals.len() == MAX_LITERALS {
+                self.flush();
+            }
+        }
+    }
+
+    /// Encodes all `values`
+    pub fn extend(&mut self, values: &[u8]) {
+        values.iter().for_each(|value| self.encode(*value))
+    }
+
+    /// Writes the buffered values to the output as a run or literals
+    pub fn flush(&mut self) {
+        if self.literals.is_empty() {
+            return;
+        }
+        if self.repeat {
+            self.output.push((self.literals.len() - MIN_REPEAT) as u8);
+            self.output.push(self.literals[0]);
+        } else {
+            self.output.push((self.literals.len() as u8).wrapping_neg());
+            self.output.extend_from_slice(&self.literals);
+        }
+        self.literals.clear();
+        self.repeat = false;
+        self.tail_run_length = 0;
+    }
+
+    /// The number of values encoded but not yet written to the output
+    pub fn buffered(&self) -> usize {
+        self.literals.len()
+    }
+
+    /// The bytes written so far
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Takes the bytes written so far, leaving the buffered values in the encoder
+    pub fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Flushes the buffered values and returns the encoded bytes
+    pub fn finish(mut self) -> Vec<u8> {
+        self.flush();
+        self.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use crate::read::decode::ByteIter;
+
+    use super::*;
+
+    fn encode(values: &[u8]) -> Vec<u8> {
+        let mut encoder = ByteRleEncoder::new();
+        encoder.extend(values);
+        encoder.finish()
+    }
+
+    fn decode(data: &[u8], length: usize) -> Vec<u8> {
+        ByteIter::new(data, length)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn runs() {
+        assert_eq!(encode(&[0; 100]), [0x61, 0x00]);
+        assert_eq!(encode(&[0x44, 0x45]), [0xfe, 0x44, 0x45]);
+        assert_eq!(encode(&[1, 2, 3, 3, 3, 3]), [0xfe, 1, 2, 0x01, 3]);
+        assert_eq!(encode(&[7; 131]), [0x7f, 7, 0xff, 7]);
+        assert!(encode(&[]).is_empty());
+    }
+
+    #[test]
+    fn long_literals() {
+        let values = (0..=255).collect::<Vec<u8>>();
+        let data = encode(&values);
+        assert_eq!(data.len(), 256 + 2);
+        assert_eq!(data[0], 0x80);
+        assert_eq!(decode(&data, values.len()), values);
+    }
+
+    quickcheck! {
+        fn round_trip(values: Vec<u8>) -> bool {
+            decode(&encode(&values), values.len()) == values
+        }
+
+        fn round_trip_runs(runs: Vec<(u8, u8)>) -> bool {
+            let values = runs
+                .into_iter()
+                .flat_map(|(value, length)| std::iter::repeat_n(value, length as usize % 8))
+                .collect::<Vec<_>>();
+            decode(&encode(&values), values.len()) == values
+        }
+    }
+}
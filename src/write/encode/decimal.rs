@@ -0,0 +1,83 @@
+/// Encodes the (unscaled) values of decimals as zigzag-encoded varints of arbitrary
+/// length, the inverse of [`DecimalIter`].
+///
+/// [`DecimalIter`]: crate::read::decode::DecimalIter
+#[derive(Debug, Default)]
+pub struct DecimalEncoder {
+    output: Vec<u8>,
+}
+
+impl DecimalEncoder {
+    /// Returns a new empty encoder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encodes `value`
+    #[inline]
+    pub fn encode(&mut self, value: i128) {
+        let mut value = ((value << 1) ^ (value >> 127)) as u128;
+        while value >= 0x80 {
+            self.output.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.output.push(value as u8);
+    }
+
+    /// Encodes all `values`
+    pub fn extend(&mut self, values: &[i128]) {
+        values.iter().for_each(|value| self.encode(*value))
+    }
+
+    /// Values are not buffered: this is a no-op
+    pub fn flush(&mut self) {}
+
+    /// The bytes written so far
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Takes the bytes written so far
+    pub fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Returns the encoded bytes
+    pub fn finish(self) -> Vec<u8> {
+        self.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use crate::read::decode::DecimalIter;
+
+    use super::*;
+
+    fn round_trip(values: &[i128]) -> Vec<i128> {
+        let mut encoder = DecimalEncoder::new();
+        encoder.extend(values);
+        let data = encoder.finish();
+        DecimalIter::new(data.as_slice(), values.len())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn extremes() {
+        let values = [0, -1, 1, 63, -64, 64, i128::MAX, i128::MIN];
+        assert_eq!(round_trip(&values), values);
+    }
+
+    quickcheck! {
+        fn round_trips(values: Vec<(i64, u64)>) -> bool {
+            let values = values
+                .into_iter()
+                .map(|(high, low)| (high as i128) << 64 | low as i128)
+                .collect::<Vec<_>>();
+            round_trip(&values) == values
+        }
+    }
+}
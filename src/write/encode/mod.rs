@@ -0,0 +1,26 @@
+//! Contains encoders that write values of ORC's physical types to the bytes of a stream.
+//! They are the inverse of the iterators in [`crate::read::decode`].
+mod boolean_rle;
+mod byte_rle;
+mod decimal;
+mod rle_v2;
+
+pub use boolean_rle::BooleanRleEncoder;
+pub use byte_rle::ByteRleEncoder;
+pub use decimal::DecimalEncoder;
+pub use rle_v2::{SignedRleV2Encoder, UnsignedRleV2Encoder};
+
+/// Writes `value` as a base 128 varint
+#[inline]
+fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+#[inline]
+fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
@@ -0,0 +1,753 @@
+//! Encoder of integers with RLE v2, following the choice of runs of the reference
+//! implementation (Java's `RunLengthIntegerWriterV2`).
+use crate::read::decode::{closest_fixed_bits, rle_v2_direct_bit_width};
+
+use super::{write_varint, zigzag_encode};
+
+/// The maximum number of values of a run
+const MAX_SCOPE: usize = 512;
+/// The minimum number of repeated values of a Short Repeat run
+const MIN_REPEAT: usize = 3;
+const MAX_SHORT_REPEAT_LENGTH: usize = 10;
+
+/// The run (and its parameters) chosen to encode the buffered values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Run {
+    ShortRepeat,
+    Direct {
+        bit_width: u8,
+    },
+    /// A fixed delta when all deltas are equal; otherwise the deltas are bit-packed
+    Delta {
+        fixed_delta: Option<i64>,
+        bit_width: u8,
+    },
+    PatchedBase {
+        base: i64,
+        bit_width: u8,
+        patch_width: u8,
+        patch_gap_width: u8,
+    },
+}
+
+/// Encoder shared by signed and unsigned integers: unsigned integers are encoded as their
+/// two's complement, which the decoders' wrapping arithmetic reverses.
+#[derive(Debug)]
+struct RleV2Encoder {
+    signed: bool,
+    output: Vec<u8>,
+    literals: Vec<i64>,
+    fixed_run_length: usize,
+    variable_run_length: usize,
+    prev_delta: i64,
+    // scratches of the runs
+    zigzag_literals: Vec<u64>,
+    base_reduced_literals: Vec<u64>,
+    adjacent_deltas: Vec<u64>,
+    patch_list: Vec<u64>,
+}
+
+impl RleV2Encoder {
+    fn new(signed: bool) -> Self {
+        Self {
+            signed,
+            output: vec![],
+            literals: Vec::with_capacity(MAX_SCOPE),
+            fixed_run_length: 0,
+            variable_run_length: 0,
+            prev_delta: 0,
+            zigzag_literals: Vec::with_capacity(MAX_SCOPE),
+            base_reduced_literals: Vec::with_capacity(MAX_SCOPE),
+            adjacent_deltas: Vec::with_capacity(MAX_SCOPE),
+            patch_list: vec![],
+        }
+    }
+
+    fn initialize(&mut self, value: i64) {
+        self.literals.push(value);
+        self.fixed_run_length = 1;
+        self.variable_run_length = 1;
+    }
+
+    fn encode(&mut self, value: i64) {
+        let Some(&last) = self.literals.last() else {
+            self.initialize(value);
+            return;
+        };
+        if self.literals.len() == 1 {
+            self.prev_delta = value.wrapping_sub(last);
+            self.literals.push(value);
+            if value == last {
+                self.fixed_run_length = 2;
+                self.variable_run_length = 0;
+            } else {
+                self.fixed_run_length = 0;
+                self.variable_run_length = 2;
+            }
+            return;
+        }
+
+        let current_delta = value.wrapping_sub(last);
+        if self.prev_delta == 0 && current_delta == 0 {
+            self.literals.push(value);
+            if self.variable_run_length > 0 {
+                self.fixed_run_length = 2;
+            }
+            self.fixed_run_length += 1;
+
+            // flush the variable run before the repeated values, which start a new run
+            if self.fixed_run_length >= MIN_REPEAT && self.variable_run_length > 0 {
+                let tail = self.literals.len() - MIN_REPEAT;
+                let mut repeated = [0; MIN_REPEAT];
+                repeated.copy_from_slice(&self.literals[tail..]);
+                self.literals.truncate(tail);
+                self.variable_run_length -= MIN_REPEAT - 1;
+                self.write_run();
+                self.literals.extend_from_slice(&repeated);
+            }
+
+            if self.fixed_run_length == MAX_SCOPE {
+                self.write_run();
+            }
+        } else {
+            if self.fixed_run_length >= MIN_REPEAT {
+                let run = if self.fixed_run_length <= MAX_SHORT_REPEAT_LENGTH {
+                    Run::ShortRepeat
+                } else {
+                    Run::Delta {
+                        fixed_delta: Some(0),
+                        bit_width: 0,
+                    }
+                };
+                self.write(run);
+            }
+
+            if self.fixed_run_length > 0 && self.fixed_run_length < MIN_REPEAT && value != last {
+                self.variable_run_length = self.fixed_run_length;
+                self.fixed_run_length = 0;
+            }
+
+            match self.literals.last() {
+                None => self.initialize(value),
+                Some(&last) => {
+                    self.prev_delta = value.wrapping_sub(last);
+                    self.literals.push(value);
+                    self.variable_run_length += 1;
+                    if self.variable_run_length == MAX_SCOPE {
+                        self.write_run();
+                    }
+                }
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.literals.is_empty() {
+            return;
+        }
+        if self.variable_run_length != 0 {
+            self.write_run();
+        } else if self.fixed_run_length < MIN_REPEAT {
+            self.variable_run_length = self.fixed_run_length;
+            self.fixed_run_length = 0;
+            self.write_run();
+        } else if self.fixed_run_length <= MAX_SHORT_REPEAT_LENGTH {
+            self.write(Run::ShortRepeat);
+        } else {
+            self.write(Run::Delta {
+                fixed_delta: Some(0),
+                bit_width: 0,
+            });
+        }
+    }
+
+    /// Writes the buffered values with the run that best encodes them
+    fn write_run(&mut self) {
+        let run = self.determine_run();
+        self.write(run);
+    }
+
+    fn determine_run(&mut self) -> Run {
+        let signed = self.signed;
+        self.zigzag_literals.clear();
+        self.zigzag_literals
+            .extend(self.literals.iter().map(|&value| {
+                if signed {
+                    zigzag_encode(value)
+                } else {
+                    value as u64
+                }
+            }));
+        let zigzag_bits_100p = percentile_bits(&self.zigzag_literals, 1.0);
+        let direct = Run::Direct {
+            bit_width: zigzag_bits_100p,
+        };
+
+        let literals = &self.literals;
+        if literals.len() <= MIN_REPEAT {
+            return direct;
+        }
+
+        let initial_delta = literals[1].wrapping_sub(literals[0]);
+        let mut is_increasing = true;
+        let mut is_decreasing = true;
+        let mut is_fixed_delta = true;
+        let mut min = literals[0];
+        let mut max = literals[0];
+        let mut delta_max = 0;
+        self.adjacent_deltas.clear();
+        for (i, pair) in literals.windows(2).enumerate() {
+            let (previous, current) = (pair[0], pair[1]);
+            let delta = current.wrapping_sub(previous);
+            min = min.min(current);
+            max = max.max(current);
+            is_increasing &= previous <= current;
+            is_decreasing &= previous >= current;
+            is_fixed_delta &= delta == initial_delta;
+            // the first delta is stored separately
+            if i > 0 {
+                let delta = delta.unsigned_abs();
+                delta_max = delta_max.max(delta);
+                self.adjacent_deltas.push(delta);
+            }
+        }
+
+        // deltas (and the base reduced values) overflow: only Direct can encode them
+        if max.checked_sub(min).is_none() {
+            return direct;
+        }
+
+        // runs of repeated values longer than what Short Repeat supports
+        if min == max {
+            return Run::Delta {
+                fixed_delta: Some(0),
+                bit_width: 0,
+            };
+        }
+
+        if is_fixed_delta {
+            return Run::Delta {
+                fixed_delta: Some(initial_delta),
+                bit_width: 0,
+            };
+        }
+
+        // deltas of monotonic sequences share the sign of the first delta
+        if initial_delta != 0 && (is_increasing || is_decreasing) {
+            return Run::Delta {
+                fixed_delta: None,
+                bit_width: closest_num_bits(delta_max),
+            };
+        }
+
+        // Patched Base is only worth it when the 10% largest values need (much) more bits
+        let zigzag_bits_90p = percentile_bits(&self.zigzag_literals, 0.9);
+        if zigzag_bits_100p - zigzag_bits_90p <= 1 {
+            return direct;
+        }
+
+        self.base_reduced_literals.clear();
+        self.base_reduced_literals.extend(
+            self.literals
+                .iter()
+                .map(|value| value.wrapping_sub(min) as u64),
+        );
+        let bits_95p = percentile_bits(&self.base_reduced_literals, 0.95);
+        let bits_100p = percentile_bits(&self.base_reduced_literals, 1.0);
+        if bits_100p == bits_95p {
+            return direct;
+        }
+        self.prepare_patched_base(min, bits_95p, bits_100p)
+            .unwrap_or(direct)
+    }
+
+    /// Splits the base reduced values larger than `bits_95p` bits in the patch list,
+    /// returning `None` when the run can't be encoded as Patched Base.
+    fn prepare_patched_base(&mut self, base: i64, bits_95p: u8, bits_100p: u8) -> Option<Run> {
+        let mut bit_width = bits_95p;
+        let mut patch_width = closest_fixed_bits(bits_100p - bits_95p);
+        // a patch and its gap must fit in 64 bits
+        if patch_width == 64 {
+            patch_width = 56;
+            bit_width = 8;
+        }
+        let mask = (1u64 << bit_width) - 1;
+
+        let mut patches = vec![];
+        let mut previous = 0;
+        let mut max_gap = 0;
+        for (i, value) in self.base_reduced_literals.iter_mut().enumerate() {
+            if *value > mask {
+                let gap = i - previous;
+                max_gap = max_gap.max(gap);
+                previous = i;
+                patches.push((gap as u64, *value >> bit_width));
+                *value &= mask;
+            }
+        }
+
+        let patch_gap_width = if max_gap == 0 && !patches.is_empty() {
+            1
+        } else {
+            closest_num_bits(max_gap as u64).min(8)
+        };
+
+        // gaps larger than 255 are split in entries with a gap of 255 and an empty patch
+        self.patch_list.clear();
+        for (mut gap, patch) in patches {
+            while gap > 255 {
+                self.patch_list.push(255 << patch_width);
+                gap -= 255;
+            }
+            self.patch_list.push((gap << patch_width) | patch);
+        }
+
+        // the length of the patch list and the base are limited to 5 bits and 8 bytes
+        let base_bytes = (closest_num_bits(base.unsigned_abs()) as usize + 1).div_ceil(8);
+        (self.patch_list.len() < 32 && base_bytes <= 8).then_some(Run::PatchedBase {
+            base,
+            bit_width,
+            patch_width,
+            patch_gap_width,
+        })
+    }
+
+    fn write(&mut self, run: Run) {
+        match run {
+            Run::ShortRepeat => {
+                self.write_short_repeat();
+                self.fixed_run_length = 0;
+            }
+            Run::Direct { bit_width } => {
+                self.write_direct(bit_width);
+                self.variable_run_length = 0;
+            }
+            Run::Delta {
+                fixed_delta,
+                bit_width,
+            } => {
+                self.write_delta(fixed_delta, bit_width);
+                if fixed_delta.is_some() && self.fixed_run_length > MIN_REPEAT {
+                    self.fixed_run_length = 0;
+                } else {
+                    self.variable_run_length = 0;
+                }
+            }
+            Run::PatchedBase {
+                base,
+                bit_width,
+                patch_width,
+                patch_gap_width,
+            } => {
+                self.write_patched_base(base, bit_width, patch_width, patch_gap_width);
+                self.variable_run_length = 0;
+            }
+        }
+        self.literals.clear();
+        self.prev_delta = 0;
+    }
+
+    /// Writes the first byte of the headers of Direct, Patched Base and Delta runs and the
+    /// 9 bits of their length
+    fn write_header(&mut self, kind: u8, encoded_bit_width: u8) {
+        let length = self.literals.len() - 1;
+        self.output
+            .push(kind << 6 | encoded_bit_width << 1 | (length >> 8) as u8 & 1);
+        self.output.push(length as u8);
+    }
+
+    fn write_short_repeat(&mut self) {
+        let value = self.literals[0];
+        let value = if self.signed {
+            zigzag_encode(value)
+        } else {
+            value as u64
+        };
+        let bytes = closest_num_bits(value).div_ceil(8);
+        let count = self.literals.len() - MIN_REPEAT;
+        self.output.push((bytes - 1) << 3 | count as u8);
+        self.output
+            .extend_from_slice(&value.to_be_bytes()[8 - bytes as usize..]);
+    }
+
+    fn write_direct(&mut self, bit_width: u8) {
+        self.write_header(0b01, encode_bit_width(bit_width));
+        write_packed(&mut self.output, &self.zigzag_literals, bit_width);
+    }
+
+    fn write_delta(&mut self, fixed_delta: Option<i64>, bit_width: u8) {
+        // an encoded width of 0 declares a fixed delta
+        let bit_width = match (fixed_delta, bit_width) {
+            (Some(_), _) => 0,
+            (None, 1) => 2,
+            (None, bit_width) => bit_width,
+        };
+        let encoded_bit_width = if bit_width == 0 {
+            0
+        } else {
+            encode_bit_width(bit_width)
+        };
+        self.write_header(0b11, encoded_bit_width);
+
+        let base = self.literals[0];
+        if self.signed {
+            write_varint(&mut self.output, zigzag_encode(base));
+        } else {
+            write_varint(&mut self.output, base as u64);
+        }
+        match fixed_delta {
+            Some(delta) => write_varint(&mut self.output, zigzag_encode(delta)),
+            None => {
+                let initial_delta = self.literals[1].wrapping_sub(base);
+                write_varint(&mut self.output, zigzag_encode(initial_delta));
+                write_packed(&mut self.output, &self.adjacent_deltas, bit_width);
+            }
+        }
+    }
+
+    fn write_patched_base(
+        &mut self,
+        base: i64,
+        bit_width: u8,
+        patch_width: u8,
+        patch_gap_width: u8,
+    ) {
+        self.write_header(0b10, encode_bit_width(bit_width));
+
+        // the base is stored in sign-magnitude, big-endian
+        let magnitude = base.unsigned_abs();
+        let base_bytes = (closest_num_bits(magnitude) as usize + 1).div_ceil(8);
+        let sign = ((base < 0) as u64) << (base_bytes * 8 - 1);
+        self.output
+            .push(((base_bytes - 1) as u8) << 5 | encode_bit_width(patch_width));
+        self.output
+            .push((patch_gap_width - 1) << 5 | self.patch_list.len() as u8);
+        self.output
+            .extend_from_slice(&(magnitude | sign).to_be_bytes()[8 - base_bytes..]);
+
+        write_packed(&mut self.output, &self.base_reduced_literals, bit_width);
+        write_packed(
+            &mut self.output,
+            &self.patch_list,
+            closest_fixed_bits(patch_gap_width + patch_width),
+        );
+    }
+}
+
+/// Returns the smallest bit width of Direct and Patched Base runs that can hold `value`
+fn closest_num_bits(value: u64) -> u8 {
+    closest_fixed_bits((64 - value.leading_zeros()) as u8)
+}
+
+/// Encodes a bit width to the 5 bits of the headers, the inverse of [`rle_v2_direct_bit_width`]
+fn encode_bit_width(num_bits: u8) -> u8 {
+    match closest_fixed_bits(num_bits) {
+        num_bits @ 1..=24 => num_bits - 1,
+        26 => 24,
+        28 => 25,
+        30 => 26,
+        32 => 27,
+        40 => 28,
+        48 => 29,
+        56 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of bits required to hold the `percentile` (in `[0, 1]`) of `values`
+fn percentile_bits(values: &[u64], percentile: f64) -> u8 {
+    let mut histogram = [0usize; 32];
+    for value in values {
+        histogram[encode_bit_width(closest_num_bits(*value)) as usize] += 1;
+    }
+
+    let mut remaining = (values.len() as f64 * (1.0 - percentile)) as i64;
+    for (encoded, count) in histogram.iter().enumerate().rev() {
+        remaining -= *count as i64;
+        if remaining < 0 {
+            return rle_v2_direct_bit_width(encoded as u8);
+        }
+    }
+    0
+}
+
+/// Bit-packs `values` of `num_bits` bits each (MSB first) to `output`
+fn write_packed(output: &mut Vec<u8>, values: &[u64], num_bits: u8) {
+    let mask = u64::MAX >> (64 - num_bits);
+    let mut buffer = 0u128;
+    let mut bits = 0;
+    for value in values {
+        buffer = buffer << num_bits | (value & mask) as u128;
+        bits += num_bits;
+        while bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        output.push((buffer << (8 - bits)) as u8);
+    }
+}
+
+macro_rules! encoder {
+    ($name:ident, $type:ty, $signed:expr, $iter:literal) => {
+        #[doc = concat!("Encodes `", stringify!($type), "` with RLE v2; the inverse of [`", $iter, "`].")]
+        #[doc = ""]
+        #[doc = concat!("[`", $iter, "`]: crate::read::decode::", $iter)]
+        #[derive(Debug)]
+        pub struct $name(RleV2Encoder);
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl $name {
+            /// Returns a new empty encoder
+            pub fn new() -> Self {
+                Self(RleV2Encoder::new($signed))
+            }
+
+            /// Encodes `value`. Values are buffered until they complete a run.
+            #[inline]
+            pub fn encode(&mut self, value: $type) {
+                self.0.encode(value as i64)
+            }
+
+            /// Encodes all `values`
+            pub fn extend(&mut self, values: &[$type]) {
+                values.iter().for_each(|value| self.encode(*value))
+            }
+
+            /// Writes the buffered values to the output as runs
+            pub fn flush(&mut self) {
+                self.0.flush()
+            }
+
+            /// The number of values encoded but not yet written to the output
+            pub fn buffered(&self) -> usize {
+                self.0.literals.len()
+            }
+
+            /// The bytes written so far
+            pub fn output(&self) -> &[u8] {
+                &self.0.output
+            }
+
+            /// Takes the bytes written so far, leaving the buffered values in the encoder
+            pub fn take(&mut self) -> Vec<u8> {
+                std::mem::take(&mut self.0.output)
+            }
+
+            /// Flushes the buffered values and returns the encoded bytes
+            pub fn finish(mut self) -> Vec<u8> {
+                self.flush();
+                self.0.output
+            }
+        }
+    };
+}
+
+encoder!(SignedRleV2Encoder, i64, true, "SignedRleV2Iter");
+encoder!(UnsignedRleV2Encoder, u64, false, "UnsignedRleV2Iter");
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::{quickcheck, Arbitrary, Gen};
+
+    use crate::read::decode::{SignedRleV2Iter, UnsignedRleV2Iter};
+
+    use super::*;
+
+    fn encode_unsigned(values: &[u64]) -> Vec<u8> {
+        let mut encoder = UnsignedRleV2Encoder::new();
+        encoder.extend(values);
+        encoder.finish()
+    }
+
+    fn encode_signed(values: &[i64]) -> Vec<u8> {
+        let mut encoder = SignedRleV2Encoder::new();
+        encoder.extend(values);
+        encoder.finish()
+    }
+
+    fn decode_unsigned(data: &[u8], length: usize) -> Vec<u64> {
+        UnsignedRleV2Iter::new(data, length, vec![])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    fn decode_signed(data: &[u8], length: usize) -> Vec<i64> {
+        SignedRleV2Iter::new(data, length, vec![])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    /// The kind of the first run of `data`
+    fn first_run(data: &[u8]) -> u8 {
+        data[0] >> 6
+    }
+
+    #[test]
+    fn short_repeat() {
+        let values = [10000; 5];
+        assert_eq!(encode_unsigned(&values), [0x0a, 0x27, 0x10]);
+    }
+
+    #[test]
+    fn direct() {
+        let values = [23713, 43806, 57005, 48879];
+        let expected = [0x5e, 0x03, 0x5c, 0xa1, 0xab, 0x1e, 0xde, 0xad, 0xbe, 0xef];
+        assert_eq!(encode_unsigned(&values), expected);
+    }
+
+    #[test]
+    fn delta() {
+        let values = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29];
+        // the deltas are packed in the 3 bits of the largest delta
+        let expected = [0xc4, 0x09, 0x02, 0x02, 0x4a, 0x28, 0xa6];
+        assert_eq!(encode_unsigned(&values), expected);
+        assert_eq!(decode_unsigned(&expected, values.len()), values);
+    }
+
+    #[test]
+    fn patched_base() {
+        let values = [
+            2030, 2000, 2020, 1000000, 2040, 2050, 2060, 2070, 2080, 2090, 2100, 2110, 2120, 2130,
+            2140, 2150, 2160, 2170, 2180, 2190,
+        ];
+        let expected = [
+            0x8e, 0x13, 0x2b, 0x21, 0x07, 0xd0, 0x1e, 0x00, 0x14, 0x70, 0x28, 0x32, 0x3c, 0x46,
+            0x50, 0x5a, 0x64, 0x6e, 0x78, 0x82, 0x8c, 0x96, 0xa0, 0xaa, 0xb4, 0xbe, 0xfc, 0xe8,
+        ];
+        assert_eq!(encode_unsigned(&values), expected);
+    }
+
+    #[test]
+    fn long_repeat() {
+        // longer than a Short Repeat: a Delta run with a fixed delta of 0
+        let values = [-7; 100];
+        let data = encode_signed(&values);
+        assert_eq!(data, [0xc0, 99, 13, 0]);
+        assert_eq!(decode_signed(&data, values.len()), values);
+    }
+
+    #[test]
+    fn extremes() {
+        let values = [i64::MIN, i64::MAX, 0, i64::MIN, -1, i64::MAX, 1];
+        let data = encode_signed(&values);
+        assert_eq!(decode_signed(&data, values.len()), values);
+
+        let values = [u64::MAX, 0, u64::MAX, 1, u64::MAX / 2, 2, 3, 4, 5];
+        let data = encode_unsigned(&values);
+        assert_eq!(decode_unsigned(&data, values.len()), values);
+    }
+
+    #[test]
+    fn buffering() {
+        let mut encoder = SignedRleV2Encoder::new();
+        encoder.extend(&[1, 5, 3]);
+        assert_eq!(encoder.buffered(), 3);
+        assert!(encoder.output().is_empty());
+        encoder.flush();
+        assert_eq!(encoder.buffered(), 0);
+        let first = encoder.take();
+        encoder.extend(&[4; 20]);
+        let second = encoder.finish();
+        let data = [first, second].concat();
+        assert_eq!(decode_signed(&data, 23)[..4], [1, 5, 3, 4]);
+    }
+
+    /// A sequence made of runs of the shapes that each kind of run encodes
+    #[derive(Debug, Clone)]
+    struct Runs(Vec<i64>);
+
+    impl Arbitrary for Runs {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let mut values = vec![];
+            for _ in 0..usize::arbitrary(g) % 8 {
+                let length = usize::arbitrary(g) % 600;
+                let start = i64::arbitrary(g) >> (u8::arbitrary(g) % 64);
+                match u8::arbitrary(g) % 4 {
+                    // repeated
+                    0 => values.extend(std::iter::repeat_n(start, length)),
+                    // arithmetic sequences
+                    1 => {
+                        let step = i64::arbitrary(g) >> (u8::arbitrary(g) % 56 + 8);
+                        values.extend(
+                            (0..length as i64).map(|i| start.wrapping_add(i.wrapping_mul(step))),
+                        )
+                    }
+                    // monotonic
+                    2 => values.extend((0..length).scan(start, |value, _| {
+                        *value = value.wrapping_add((u16::arbitrary(g) % 300) as i64);
+                        Some(*value)
+                    })),
+                    // small values with outliers
+                    _ => values.extend((0..length).map(|_| {
+                        if u8::arbitrary(g) % 32 == 0 {
+                            i64::arbitrary(g)
+                        } else {
+                            start.wrapping_add((u8::arbitrary(g) % 100) as i64)
+                        }
+                    })),
+                }
+            }
+            Self(values)
+        }
+
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            Box::new(self.0.shrink().map(Self))
+        }
+    }
+
+    quickcheck! {
+        fn round_trip_signed(values: Vec<i64>) -> bool {
+            decode_signed(&encode_signed(&values), values.len()) == values
+        }
+
+        fn round_trip_unsigned(values: Vec<u64>) -> bool {
+            decode_unsigned(&encode_unsigned(&values), values.len()) == values
+        }
+
+        fn round_trip_signed_runs(runs: Runs) -> bool {
+            let values = runs.0;
+            decode_signed(&encode_signed(&values), values.len()) == values
+        }
+
+        fn round_trip_unsigned_runs(runs: Runs) -> bool {
+            let values = runs.0.into_iter().map(|value| value as u64).collect::<Vec<_>>();
+            decode_unsigned(&encode_unsigned(&values), values.len()) == values
+        }
+    }
+
+    #[test]
+    fn chooses_runs() {
+        // patched base
+        let mut values = vec![5; 100];
+        values
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, v)| *v += i as i64 % 7);
+        values[50] = 1 << 40;
+        let data = encode_signed(&values);
+        assert_eq!(first_run(&data), 0b10);
+        assert_eq!(decode_signed(&data, values.len()), values);
+
+        // patches with gaps larger than 255
+        let mut values = (0..512).map(|i| i % 13).collect::<Vec<i64>>();
+        values[0] = 1 << 50;
+        values[400] = 1 << 45;
+        let data = encode_signed(&values);
+        assert_eq!(first_run(&data), 0b10);
+        assert_eq!(decode_signed(&data, values.len()), values);
+
+        // delta
+        let values = (0..300).map(|i| i * i).collect::<Vec<i64>>();
+        let data = encode_signed(&values);
+        assert_eq!(first_run(&data), 0b11);
+        assert_eq!(decode_signed(&data, values.len()), values);
+    }
+}
@@ -0,0 +1,365 @@
+//! Contains [`merge`], which concatenates the stripes of files without decoding them.
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::error::Error;
+use crate::proto::{CompressionKind, PostScript};
+use crate::read::{read_metadata, FileMetadata};
+
+use super::statistics::{Statistics, Values};
+use super::{FileWriter, WriteOptions, DEFAULT_COMPRESSION_BLOCK_SIZE};
+
+/// The size of the chunks of a compressed file
+fn compression_block_size(postscript: &PostScript) -> Option<u64> {
+    match postscript.compression() {
+        CompressionKind::None => None,
+        _ => Some(
+            postscript
+                .compression_block_size
+                .unwrap_or(DEFAULT_COMPRESSION_BLOCK_SIZE),
+        ),
+    }
+}
+
+/// Errors when the stripes of `other` cannot be copied to a file whose first input is `first`
+fn check(first: &FileMetadata, other: &FileMetadata) -> Result<(), Error> {
+    if other.footer.types != first.footer.types {
+        return Err(Error::OutOfSpec("merged files must have the same types"));
+    }
+    if other.postscript.compression() != first.postscript.compression()
+        || compression_block_size(&other.postscript) != compression_block_size(&first.postscript)
+    {
+        return Err(Error::OutOfSpec(
+            "merged files must have the same compression and compression block size",
+        ));
+    }
+    if other.footer.row_index_stride() != first.footer.row_index_stride() {
+        return Err(Error::OutOfSpec(
+            "merged files must have the same row index stride",
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the statistics of the columns of the file of `metadata`, or `None` when it has none
+fn statistics(metadata: &FileMetadata) -> Option<Vec<Statistics>> {
+    let footer = &metadata.footer;
+    (footer.statistics.len() == footer.types.len()).then(|| {
+        footer
+            .statistics
+            .iter()
+            .zip(&footer.types)
+            .map(|(statistics, type_)| Statistics::from_proto(statistics, type_.scale()))
+            .collect()
+    })
+}
+
+/// Merges the statistics of a column of two files. Statistics of values of different
+/// types (e.g. known in one file and not in the other) are dropped.
+fn merge_statistics(statistics: &mut Statistics, other: &Statistics) {
+    if std::mem::discriminant(&statistics.values) != std::mem::discriminant(&other.values) {
+        statistics.values = Values::None;
+    }
+    statistics.merge(other)
+}
+
+/// Copies the stripes of the file of `metadata` from `reader` to `file`
+fn copy_stripes<R: Read + Seek, W: Write>(
+    file: &mut FileWriter<W>,
+    reader: &mut R,
+    metadata: &FileMetadata,
+) -> Result<(), Error> {
+    let stripe_stats = &metadata.metadata()?.stripe_stats;
+    for (index, information) in metadata.footer.stripes.iter().enumerate() {
+        reader.seek(SeekFrom::Start(information.offset()))?;
+        let statistics = stripe_stats.get(index).cloned().unwrap_or_default();
+        file.copy_stripe(reader, information, statistics)?;
+    }
+    Ok(())
+}
+
+/// Merges `inputs` to a file written to `writer`, returning it, by copying their stripes
+/// as is, in order: stripes are neither decoded nor encoded again.
+///
+/// The inputs must have the same types, compression (and compression block size) and row
+/// index stride, which the merged file has too: those of `options` are replaced, while the
+/// others (e.g. [`WriteOptions::block_padding`]) apply to it.
+/// The merged file has the statistics of the stripes of the inputs, their statistics
+/// merged and their user metadata, the first value of every name. Its writer version is
+/// the oldest of the inputs', so that readers apply the fixes that its stripes need.
+/// # Errors
+/// This function errors when there are no inputs, the inputs are not ORC files or cannot
+/// be merged, and when reading or writing fails. The inputs' compression must be supported
+/// by this crate, to write the file's tail.
+pub fn merge<I, R, W>(inputs: I, writer: W, options: WriteOptions) -> Result<W, Error>
+where
+    I: IntoIterator<Item = R>,
+    R: Read + Seek,
+    W: Write,
+{
+    let mut inputs = inputs.into_iter();
+    let mut input = inputs
+        .next()
+        .ok_or(Error::OutOfSpec("at least one file must be merged"))?;
+    let first = read_metadata(&mut input)?;
+    let options = WriteOptions {
+        row_index_stride: first.footer.row_index_stride(),
+        compression: first.postscript.compression(),
+        compression_block_size: compression_block_size(&first.postscript)
+            .unwrap_or(options.compression_block_size),
+        ..options
+    };
+    let mut file = FileWriter::try_new(writer, first.footer.types.clone(), options)?;
+    file.writer_version = first.writer_version();
+    file.footer.metadata = first.footer.metadata.clone();
+    let mut merged = statistics(&first);
+    copy_stripes(&mut file, &mut input, &first)?;
+
+    for mut input in inputs {
+        let metadata = read_metadata(&mut input)?;
+        check(&first, &metadata)?;
+        file.writer_version = file.writer_version.min(metadata.writer_version());
+        for item in &metadata.footer.metadata {
+            let metadata = &mut file.footer.metadata;
+            if !metadata.iter().any(|existing| existing.name == item.name) {
+                metadata.push(item.clone());
+            }
+        }
+        merged = merged
+            .zip(statistics(&metadata))
+            .map(|(mut merged, other)| {
+                for (statistics, other) in merged.iter_mut().zip(&other) {
+                    merge_statistics(statistics, other);
+                }
+                merged
+            });
+        copy_stripes(&mut file, &mut input, &metadata)?;
+    }
+
+    let statistics = merged.unwrap_or_default();
+    file.set_statistics(statistics.iter().map(Statistics::to_proto).collect());
+    file.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use prost::Message;
+
+    use crate::proto::r#type::Kind as TypeKind;
+    use crate::proto::stream::Kind;
+    use crate::proto::Type;
+    use crate::read::decode::SignedRleV2Iter;
+    use crate::read::{read_stripe_column, read_stripe_footer};
+    use crate::test_util::struct_;
+    use crate::version::WriterVersion;
+    use crate::write::column::ColumnWriter;
+    use crate::write::{OrcWriter, WRITER_VERSION};
+
+    use super::*;
+
+    fn types() -> Vec<Type> {
+        struct_(&[("a", TypeKind::Long), ("b", TypeKind::String)])
+    }
+
+    /// A file with the rows `rows`, in stripes of 30 rows, and user metadata `name`
+    fn file(rows: std::ops::Range<i64>, name: &str, options: &WriteOptions) -> Vec<u8> {
+        let options = WriteOptions {
+            stripe_row_count: 30,
+            row_index_stride: 10,
+            ..options.clone()
+        };
+        let mut writer = OrcWriter::try_new(vec![], types(), options).unwrap();
+        for value in rows {
+            let [ColumnWriter::Integer(a), ColumnWriter::String(b)] = writer.columns_mut() else {
+                unreachable!()
+            };
+            a.write(Some(value));
+            b.write((value % 7 != 0).then(|| value.to_string()).as_deref());
+            writer.write_rows(1).unwrap();
+        }
+        writer.add_user_metadata(name.to_string(), rows_name(name));
+        writer.add_user_metadata("common".to_string(), rows_name(name));
+        writer.finish().unwrap()
+    }
+
+    fn rows_name(name: &str) -> Vec<u8> {
+        format!("rows of {name}").into_bytes()
+    }
+
+    /// Returns `file` whose postscript declares the writer version `version`
+    fn with_writer_version(file: &[u8], version: WriterVersion) -> Vec<u8> {
+        let start = file.len() - 1 - *file.last().unwrap() as usize;
+        let mut postscript = PostScript::decode(&file[start..file.len() - 1]).unwrap();
+        postscript.writer_version = Some(version.into());
+        let postscript = postscript.encode_to_vec();
+        let mut file = file[..start].to_vec();
+        file.extend(&postscript);
+        file.push(postscript.len() as u8);
+        file
+    }
+
+    #[test]
+    fn merged() -> Result<(), Error> {
+        let options = WriteOptions {
+            compression: CompressionKind::Zlib,
+            compression_block_size: 1024,
+            ..Default::default()
+        };
+        let inputs = [
+            file(0..100, "a", &options),
+            file(100..150, "b", &options),
+            file(150..150, "c", &options),
+        ];
+        let merged = merge(
+            inputs.iter().map(Cursor::new),
+            vec![],
+            WriteOptions::default(),
+        )?;
+
+        let mut reader = Cursor::new(&merged);
+        let metadata = read_metadata(&mut reader)?;
+        let footer = &metadata.footer;
+        assert_eq!(footer.types, types());
+        assert_eq!(footer.number_of_rows(), 150);
+        assert_eq!(footer.row_index_stride(), 10);
+        assert_eq!(metadata.postscript.compression(), CompressionKind::Zlib);
+        assert_eq!(metadata.postscript.compression_block_size(), 1024);
+
+        // the stripes are copied as is, with their statistics
+        let inputs = inputs
+            .iter()
+            .map(|input| Ok((input, read_metadata(&mut Cursor::new(input))?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let stripes = inputs.iter().flat_map(|(input, metadata)| {
+            let stripes = metadata.footer.stripes.iter();
+            let stripe_stats = metadata.metadata().unwrap().stripe_stats.clone();
+            stripes.zip(stripe_stats).map(|(stripe, statistics)| {
+                let start = stripe.offset() as usize;
+                let end = start
+                    + (stripe.index_length() + stripe.data_length() + stripe.footer_length())
+                        as usize;
+                (&input[start..end], stripe.number_of_rows(), statistics)
+            })
+        });
+        let stripes = stripes.collect::<Vec<_>>();
+        assert_eq!(stripes.len(), 6);
+        assert_eq!(footer.stripes.len(), 6);
+        for (index, (stripe, (bytes, rows, statistics))) in
+            footer.stripes.iter().zip(&stripes).enumerate()
+        {
+            let start = stripe.offset() as usize;
+            assert_eq!(&&merged[start..start + bytes.len()], bytes);
+            assert_eq!(stripe.number_of_rows(), *rows);
+            assert_eq!(&metadata.metadata()?.stripe_stats[index], statistics);
+        }
+
+        let mut values = vec![];
+        for stripe in 0..footer.stripes.len() {
+            let stripe_footer = read_stripe_footer(&mut reader, &metadata, stripe, &mut vec![])?;
+            let column =
+                read_stripe_column(&mut reader, &metadata, stripe, stripe_footer, 1, vec![])?;
+            let rows = column.number_of_rows();
+            let data = column.get_stream(Kind::Data, vec![])?;
+            for value in SignedRleV2Iter::new(data, rows, vec![]) {
+                values.push(value?);
+            }
+        }
+        assert_eq!(values, (0..150).collect::<Vec<_>>());
+
+        // the statistics are merged
+        let statistics = &footer.statistics;
+        assert_eq!(statistics[0].number_of_values(), 150);
+        let integers = statistics[1].int_statistics.clone().unwrap_or_default();
+        assert_eq!(integers.minimum(), 0);
+        assert_eq!(integers.maximum(), 149);
+        assert_eq!(integers.sum(), (0..150).sum::<i64>());
+        assert!(statistics[2].has_null());
+        assert_eq!(statistics[2].number_of_values(), 150 - 22);
+        let strings = statistics[2].string_statistics.clone().unwrap_or_default();
+        assert_eq!((strings.minimum(), strings.maximum()), ("1", "99"));
+
+        let names = footer.metadata.iter().map(|item| item.name());
+        assert_eq!(names.collect::<Vec<_>>(), vec!["a", "common", "b", "c"]);
+        assert_eq!(footer.metadata[1].value(), rows_name("a"));
+        Ok(())
+    }
+
+    #[test]
+    fn padded() -> Result<(), Error> {
+        let inputs = [
+            file(0..100, "a", &WriteOptions::default()),
+            file(0..100, "b", &WriteOptions::default()),
+        ];
+        let options = WriteOptions {
+            block_size: 1024,
+            stripe_size: 10_000,
+            ..Default::default()
+        };
+        let merged = merge(inputs.iter().map(Cursor::new), vec![], options)?;
+        let metadata = read_metadata(&mut Cursor::new(&merged))?;
+        let stripes = &metadata.footer.stripes;
+        assert_eq!(stripes.len(), 8);
+        // a stripe crossing a block boundary is moved to the next block
+        assert!(stripes.iter().any(|stripe| stripe.offset() % 1024 == 0));
+        for stripe in stripes {
+            let start = stripe.offset() % 1024;
+            let length = stripe.index_length() + stripe.data_length() + stripe.footer_length();
+            assert!(start + length <= 1024 || start == 0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        let none = WriteOptions::default();
+        let zlib = WriteOptions {
+            compression: CompressionKind::Zlib,
+            ..Default::default()
+        };
+        let a = file(0..10, "a", &none);
+        let merge =
+            |inputs: &[&Vec<u8>]| merge(inputs.iter().map(Cursor::new), vec![], none.clone());
+
+        assert!(merge(&[]).is_err());
+        assert!(merge(&[&a, &file(0..10, "b", &zlib)]).is_err());
+        let small_chunks = WriteOptions {
+            compression_block_size: 1024,
+            ..zlib.clone()
+        };
+        assert!(merge(&[&file(0..10, "a", &zlib), &file(0..10, "b", &small_chunks)]).is_err());
+
+        let mut types = types();
+        types[2].kind = Some(TypeKind::Binary as i32);
+        let other_types = OrcWriter::try_new(vec![], types, none.clone())
+            .and_then(OrcWriter::finish)
+            .unwrap();
+        assert!(merge(&[&a, &other_types]).is_err());
+
+        assert!(merge(&[&a, &b"ORC".to_vec()]).is_err());
+        assert!(merge(&[&a, &a]).is_ok());
+    }
+
+    #[test]
+    fn writer_versions() -> Result<(), Error> {
+        let options = WriteOptions::default();
+        let a = file(0..10, "a", &options);
+        let old = with_writer_version(&file(10..20, "b", &options), WriterVersion::Hive8732);
+        let future = with_writer_version(&a, WriterVersion::from(20));
+
+        // the merged file has the oldest writer version of the inputs
+        for (inputs, expected) in [
+            ([&a, &old], WriterVersion::Hive8732),
+            ([&a, &a], WRITER_VERSION),
+        ] {
+            let merged = merge(inputs.iter().map(Cursor::new), vec![], options.clone())?;
+            let metadata = read_metadata(&mut Cursor::new(&merged))?;
+            assert_eq!(metadata.writer_version(), expected);
+            assert_eq!(metadata.footer.number_of_rows(), 20);
+        }
+        // files of unknown writer versions are not merged
+        let merged = merge([&a, &future].iter().map(Cursor::new), vec![], options);
+        assert!(matches!(merged, Err(Error::UnknownWriterVersion(20))));
+        Ok(())
+    }
+}
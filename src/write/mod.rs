@@ -0,0 +1,644 @@
+//! APIs to write ORC files.
+//!
+//! Writing to ORC is essentially composed by:
+//! 1. Encode the streams of every column of a stripe
+//! 2. Write the stripe (its streams and its footer) via [`FileWriter::write_stripe`]
+//! 3. Write the file's tail (metadata, footer and postscript) via [`FileWriter::finish`]
+//!
+//! [`FileWriter`] is responsible for the layout of the file; the streams it writes are
+//! encoded by the caller, e.g. with the encoders in [`encode`], and compressed with the
+//! file's [`Compressor`]. [`OrcWriter`] does all of the above from the values of rows,
+//! written to the [`column::ColumnWriter`]s of the file.
+use std::io::{Read, Write};
+
+use prost::Message;
+
+use crate::error::Error;
+use crate::proto::stream::Kind;
+use crate::proto::{
+    ColumnEncoding, ColumnStatistics, CompressionKind, Footer, Metadata, PostScript, Stream,
+    StripeFooter, StripeInformation, StripeStatistics, Type, UserMetadataItem,
+};
+use crate::read::is_index;
+use crate::read::types::{top_level_columns, validate_types};
+use crate::version::{WriterVersion, MAGIC};
+
+mod bloom_filter;
+pub mod column;
+mod compress;
+pub mod encode;
+mod merge;
+mod statistics;
+mod writer;
+
+pub use compress::{compress, Compressor, DEFAULT_COMPRESSION_BLOCK_SIZE};
+pub use merge::merge;
+pub use writer::OrcWriter;
+
+/// The version of the file format (`[major, minor]`) written by this crate
+pub const VERSION: [u32; 2] = [0, 12];
+
+/// The [`WriterVersion`] of the files written by this crate, whose timestamp statistics
+/// are in UTC
+pub const WRITER_VERSION: WriterVersion = WriterVersion::Orc135;
+
+/// The streams in which bloom filters are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BloomFilterVersion {
+    /// Both [`Kind::BloomFilter`] and [`Kind::BloomFilterUtf8`] streams, for readers of
+    /// files written before the latter existed
+    Original,
+    /// Only [`Kind::BloomFilterUtf8`] streams
+    #[default]
+    Utf8,
+}
+
+/// Options to write a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteOptions {
+    /// The number of rows between entries of the row index, declared in the file's footer.
+    /// Zero when the file has no row index.
+    pub row_index_stride: u32,
+    /// The columns with a bloom filter of the values of each row group. Bloom filters are
+    /// part of the row index and are not written when the file has none.
+    pub bloom_filter_columns: Vec<u32>,
+    /// The false positive probability of bloom filters, between 0 and 1
+    pub bloom_filter_fpp: f64,
+    /// The streams in which bloom filters are written
+    pub bloom_filter_version: BloomFilterVersion,
+    /// The compression of the file's streams, footers and metadata
+    pub compression: CompressionKind,
+    /// The maximum size of the (uncompressed) chunks of compressed streams
+    pub compression_block_size: u64,
+    /// The maximum ratio between the number of distinct values and the number of values of
+    /// a string column in a stripe for it to be encoded with a dictionary. Zero disables
+    /// dictionaries unless all values are null.
+    pub dictionary_key_size_threshold: f64,
+    /// The size in bytes at which [`OrcWriter`] finishes a stripe: the estimated size of its
+    /// columns, encoded and compressed, in memory
+    pub stripe_size: u64,
+    /// The maximum number of rows of the stripes written by [`OrcWriter`]
+    pub stripe_row_count: u64,
+    /// The size of the blocks of the file system (e.g. HDFS) storing the file
+    pub block_size: u64,
+    /// Whether a stripe that would cross the boundary of a block is moved to the start of
+    /// the next block, padding the file with zeros, when the padding is at most
+    /// [`WriteOptions::padding_tolerance`] of the stripe size
+    pub block_padding: bool,
+    /// The maximum padding before a stripe, as a fraction of [`WriteOptions::stripe_size`]
+    pub padding_tolerance: f64,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            row_index_stride: 10_000,
+            bloom_filter_columns: vec![],
+            bloom_filter_fpp: 0.05,
+            bloom_filter_version: BloomFilterVersion::default(),
+            compression: CompressionKind::None,
+            compression_block_size: DEFAULT_COMPRESSION_BLOCK_SIZE,
+            dictionary_key_size_threshold: 0.8,
+            stripe_size: 64 * 1024 * 1024,
+            stripe_row_count: u64::MAX,
+            block_size: 256 * 1024 * 1024,
+            block_padding: true,
+            padding_tolerance: 0.05,
+        }
+    }
+}
+
+/// A stream of a column, encoded and compressed by the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedStream {
+    /// The kind of the stream
+    pub kind: Kind,
+    /// The column of the stream
+    pub column: u32,
+    /// The (encoded and compressed) data of the stream
+    pub data: Vec<u8>,
+}
+
+/// A stripe whose streams were encoded and compressed by the caller.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EncodedStripe {
+    /// The number of rows of the stripe
+    pub number_of_rows: u64,
+    /// The streams of the stripe. Index streams (e.g. [`Kind::RowIndex`]) are written before
+    /// the data streams, in the order they appear.
+    pub streams: Vec<EncodedStream>,
+    /// The encoding of each column of the file
+    pub encodings: Vec<ColumnEncoding>,
+    /// The statistics of each column of the file in this stripe, or empty when unknown
+    pub statistics: Vec<ColumnStatistics>,
+    /// The timezone of the writer, used by timestamp columns
+    pub writer_timezone: Option<String>,
+}
+
+/// Writes an ORC file stripe by stripe to a [`Write`].
+///
+/// # Example
+/// ```
+/// use orc_format::proto::{r#type::Kind, Type};
+/// use orc_format::write::{FileWriter, WriteOptions};
+///
+/// let types = vec![Type {
+///     kind: Some(Kind::Struct as i32),
+///     ..Default::default()
+/// }];
+/// let writer = FileWriter::try_new(vec![], types, WriteOptions::default())?;
+/// let file = writer.finish()?;
+///
+/// let metadata = orc_format::read::read_metadata(&mut std::io::Cursor::new(file))?;
+/// assert_eq!(metadata.footer.number_of_rows(), 0);
+/// # Ok::<(), orc_format::error::Error>(())
+/// ```
+pub struct FileWriter<W: Write> {
+    writer: W,
+    offset: u64,
+    options: WriteOptions,
+    footer: Footer,
+    metadata: Metadata,
+    /// the version of the writer of the file's stripes
+    writer_version: WriterVersion,
+}
+
+impl<W: Write> FileWriter<W> {
+    /// Returns a new [`FileWriter`] of a file with `types`, writing the file's header to `writer`.
+    /// # Errors
+    /// This function errors when `types` are not a tree in pre-order whose root is a struct,
+    /// the compression is not supported or writing fails.
+    pub fn try_new(mut writer: W, types: Vec<Type>, options: WriteOptions) -> Result<Self, Error> {
+        validate_types(&types)?;
+        top_level_columns(&types)?;
+        Compressor::try_new(options.compression, options.compression_block_size)?;
+        writer.write_all(MAGIC.as_bytes())?;
+        let header_length = MAGIC.len() as u64;
+        Ok(Self {
+            writer,
+            offset: header_length,
+            footer: Footer {
+                header_length: Some(header_length),
+                types,
+                number_of_rows: Some(0),
+                row_index_stride: Some(options.row_index_stride),
+                ..Default::default()
+            },
+            options,
+            metadata: Metadata::default(),
+            writer_version: WRITER_VERSION,
+        })
+    }
+
+    /// Returns the types of the file
+    pub fn types(&self) -> &[Type] {
+        &self.footer.types
+    }
+
+    /// Returns the options of the file
+    pub fn options(&self) -> &WriteOptions {
+        &self.options
+    }
+
+    /// Returns a new [`Compressor`] with the file's compression, to compress its streams
+    /// # Errors
+    /// This function does not error since the compression was validated by [`Self::try_new`].
+    pub fn compressor(&self) -> Result<Compressor, Error> {
+        Compressor::try_new(
+            self.options.compression,
+            self.options.compression_block_size,
+        )
+    }
+
+    /// Returns the number of bytes written so far
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Writes `stripe` to the file: its index streams, its data streams and its footer.
+    /// # Errors
+    /// This function errors when `stripe` has no encoding per column, statistics for some but
+    /// not all columns, or streams of columns that do not exist, and when writing fails.
+    pub fn write_stripe(&mut self, stripe: EncodedStripe) -> Result<(), Error> {
+        let columns = self.footer.types.len();
+        if stripe.encodings.len() != columns {
+            return Err(Error::OutOfSpec(
+                "a stripe must have one encoding per column",
+            ));
+        }
+        if !stripe.statistics.is_empty() && stripe.statistics.len() != columns {
+            return Err(Error::OutOfSpec(
+                "a stripe must have statistics for all or none of its columns",
+            ));
+        }
+        if let Some(stream) = stripe
+            .streams
+            .iter()
+            .find(|stream| stream.column as usize >= columns)
+        {
+            return Err(Error::InvalidColumn(stream.column));
+        }
+
+        let (index, data): (Vec<_>, Vec<_>) = stripe
+            .streams
+            .into_iter()
+            .partition(|stream| is_index(stream.kind));
+        let streams = index.iter().chain(&data).map(|stream| Stream {
+            kind: Some(stream.kind as i32),
+            column: Some(stream.column),
+            length: Some(stream.data.len() as u64),
+        });
+        let length = |streams: &[EncodedStream]| -> u64 {
+            streams.iter().map(|stream| stream.data.len() as u64).sum()
+        };
+        let (index_length, data_length) = (length(&index), length(&data));
+
+        let footer = StripeFooter {
+            streams: streams.collect(),
+            columns: stripe.encodings,
+            writer_timezone: stripe.writer_timezone,
+        }
+        .encode_to_vec();
+        let footer = compress(
+            &footer,
+            self.options.compression,
+            self.options.compression_block_size,
+        )?;
+
+        self.pad(index_length + data_length + footer.len() as u64)?;
+        let offset = self.offset;
+        for stream in index.iter().chain(&data) {
+            self.writer.write_all(&stream.data)?;
+        }
+        self.writer.write_all(&footer)?;
+        self.offset += index_length + data_length + footer.len() as u64;
+
+        self.footer.stripes.push(StripeInformation {
+            offset: Some(offset),
+            index_length: Some(index_length),
+            data_length: Some(data_length),
+            footer_length: Some(footer.len() as u64),
+            number_of_rows: Some(stripe.number_of_rows),
+        });
+        self.footer.number_of_rows = Some(self.footer.number_of_rows() + stripe.number_of_rows);
+        self.metadata.stripe_stats.push(StripeStatistics {
+            col_stats: stripe.statistics,
+        });
+        Ok(())
+    }
+
+    /// Copies a stripe of another file with the same types and compression from `reader`,
+    /// positioned at its start, without decoding it. `information` is the stripe's
+    /// information in the other file and `statistics` its statistics.
+    fn copy_stripe<R: Read>(
+        &mut self,
+        reader: &mut R,
+        information: &StripeInformation,
+        statistics: StripeStatistics,
+    ) -> Result<(), Error> {
+        let length = information
+            .index_length()
+            .checked_add(information.data_length())
+            .and_then(|length| length.checked_add(information.footer_length()))
+            .ok_or(Error::OutOfSpec(
+                "the length of a stripe must fit in 64 bits",
+            ))?;
+        self.pad(length)?;
+        let copied = std::io::copy(&mut reader.take(length), &mut self.writer)?;
+        if copied != length {
+            return Err(Error::OutOfSpec("a stripe must be within its file"));
+        }
+
+        self.footer.stripes.push(StripeInformation {
+            offset: Some(self.offset),
+            ..information.clone()
+        });
+        self.offset += length;
+        let rows = information.number_of_rows();
+        self.footer.number_of_rows = Some(self.footer.number_of_rows() + rows);
+        self.metadata.stripe_stats.push(statistics);
+        Ok(())
+    }
+
+    /// Pads the file with zeros to the start of the next block when a stripe of `length`
+    /// bytes would cross the boundary of the current one, as the reference implementation
+    /// does, unless the padding is larger than the tolerance.
+    fn pad(&mut self, length: u64) -> Result<(), Error> {
+        let WriteOptions {
+            block_size,
+            block_padding,
+            padding_tolerance,
+            stripe_size,
+            ..
+        } = self.options;
+        if !block_padding || block_size == 0 {
+            return Ok(());
+        }
+        let in_block = self.offset % block_size;
+        if in_block == 0 || in_block + length <= block_size {
+            return Ok(());
+        }
+        let padding = block_size - in_block;
+        if padding as f64 <= (padding_tolerance * stripe_size as f64).round() {
+            std::io::copy(&mut std::io::repeat(0).take(padding), &mut self.writer)?;
+            self.offset += padding;
+        }
+        Ok(())
+    }
+
+    /// Adds a user-defined `name` and `value` to the file's footer
+    pub fn add_user_metadata(&mut self, name: String, value: Vec<u8>) {
+        self.footer.metadata.push(UserMetadataItem {
+            name: Some(name),
+            value: Some(value),
+        });
+    }
+
+    /// Sets the statistics of each column of the whole file
+    pub fn set_statistics(&mut self, statistics: Vec<ColumnStatistics>) {
+        self.footer.statistics = statistics;
+    }
+
+    /// Writes the file's tail (metadata, footer, postscript and postscript length),
+    /// returning the inner writer.
+    /// # Errors
+    /// This function errors when compressing or writing fails.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.footer.content_length = Some(self.offset - self.footer.header_length());
+
+        let WriteOptions {
+            compression,
+            compression_block_size,
+            ..
+        } = self.options;
+        let metadata = compress(
+            &self.metadata.encode_to_vec(),
+            compression,
+            compression_block_size,
+        )?;
+        self.writer.write_all(&metadata)?;
+        let footer = compress(
+            &self.footer.encode_to_vec(),
+            compression,
+            compression_block_size,
+        )?;
+        self.writer.write_all(&footer)?;
+
+        let postscript = PostScript {
+            footer_length: Some(footer.len() as u64),
+            compression: Some(compression as i32),
+            compression_block_size: (compression != CompressionKind::None)
+                .then_some(compression_block_size),
+            version: VERSION.to_vec(),
+            metadata_length: Some(metadata.len() as u64),
+            writer_version: Some(self.writer_version.into()),
+            magic: Some(MAGIC.to_string()),
+        }
+        .encode_to_vec();
+        // the postscript has no repeated fields of unbounded size
+        debug_assert!(postscript.len() < 256);
+        self.writer.write_all(&postscript)?;
+        self.writer.write_all(&[postscript.len() as u8])?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::proto::column_encoding::Kind as ColumnEncodingKind;
+    use crate::proto::r#type::Kind as TypeKind;
+    use crate::proto::IntegerStatistics;
+    use crate::read::decode::SignedRleV2Iter;
+    use crate::read::{read_metadata, read_stripe_column, read_stripe_footer};
+    use crate::test_util::struct_;
+
+    use super::*;
+
+    fn types() -> Vec<Type> {
+        struct_(&[("a", TypeKind::Long)])
+    }
+
+    fn encodings() -> Vec<ColumnEncoding> {
+        let encoding = ColumnEncoding {
+            kind: Some(ColumnEncodingKind::DirectV2 as i32),
+            dictionary_size: None,
+        };
+        vec![encoding.clone(), encoding]
+    }
+
+    fn statistics(values: u64) -> Vec<ColumnStatistics> {
+        vec![
+            ColumnStatistics {
+                number_of_values: Some(values),
+                ..Default::default()
+            },
+            ColumnStatistics {
+                number_of_values: Some(values),
+                int_statistics: Some(IntegerStatistics {
+                    minimum: Some(3),
+                    maximum: Some(3),
+                    sum: Some(3 * values as i64),
+                }),
+                ..Default::default()
+            },
+        ]
+    }
+
+    /// A stripe of `rows` (3..=10) rows whose column is always 3, as a RLE v2 short repeat
+    fn stripe(rows: u8) -> EncodedStripe {
+        EncodedStripe {
+            number_of_rows: rows as u64,
+            streams: vec![
+                EncodedStream {
+                    kind: Kind::Data,
+                    column: 1,
+                    data: vec![rows - 3, 3 << 1],
+                },
+                EncodedStream {
+                    kind: Kind::RowIndex,
+                    column: 1,
+                    data: vec![],
+                },
+            ],
+            encodings: encodings(),
+            statistics: statistics(rows as u64),
+            writer_timezone: None,
+        }
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), Error> {
+        let mut writer = FileWriter::try_new(vec![], types(), WriteOptions::default())?;
+        writer.write_stripe(stripe(5))?;
+        writer.write_stripe(stripe(10))?;
+        writer.add_user_metadata("key".to_string(), b"value".to_vec());
+        writer.set_statistics(statistics(15));
+        let file = writer.finish()?;
+        assert_eq!(&file[..3], b"ORC");
+
+        let mut reader = Cursor::new(&file);
+        let metadata = read_metadata(&mut reader)?;
+        let footer = &metadata.footer;
+        assert_eq!(footer.types, types());
+        assert_eq!(footer.number_of_rows(), 15);
+        assert_eq!(footer.statistics, statistics(15));
+        assert_eq!(footer.metadata[0].value(), b"value");
+        assert_eq!(footer.row_index_stride(), 10_000);
+        assert_eq!(metadata.postscript.version, VERSION);
+        assert_eq!(metadata.writer_version(), WRITER_VERSION);
+        assert_eq!(
+            metadata.metadata()?.stripe_stats[1].col_stats,
+            statistics(10)
+        );
+
+        // the stripes follow each other, after the header and before the tail
+        let stripes = &footer.stripes;
+        assert_eq!(stripes[0].offset(), 3);
+        assert_eq!(stripes[0].index_length(), 0);
+        assert_eq!(stripes[0].data_length(), 2);
+        let end = stripes[1].offset()
+            + stripes[1].index_length()
+            + stripes[1].data_length()
+            + stripes[1].footer_length();
+        assert_eq!(
+            stripes[0].offset() + stripes[0].data_length() + stripes[0].footer_length(),
+            stripes[1].offset()
+        );
+        assert_eq!(footer.content_length(), end - 3);
+
+        for (index, rows) in [5, 10].into_iter().enumerate() {
+            let stripe_footer = read_stripe_footer(&mut reader, &metadata, index, &mut vec![])?;
+            // index streams come first
+            assert_eq!(stripe_footer.streams[0].kind(), Kind::RowIndex);
+            let column =
+                read_stripe_column(&mut reader, &metadata, index, stripe_footer, 1, vec![])?;
+            let values = SignedRleV2Iter::new(column.get_stream(Kind::Data, vec![])?, rows, vec![])
+                .collect::<Result<Vec<_>, Error>>()?;
+            assert_eq!(values, vec![3; rows]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn compressed() -> Result<(), Error> {
+        let options = WriteOptions {
+            compression: CompressionKind::Zlib,
+            compression_block_size: 64,
+            ..Default::default()
+        };
+        let mut writer = FileWriter::try_new(vec![], types(), options)?;
+        let mut stripe = stripe(10);
+        for stream in stripe.streams.iter_mut() {
+            let mut compressor = writer.compressor()?;
+            compressor.write_all(&stream.data)?;
+            stream.data = compressor.finish()?;
+        }
+        writer.write_stripe(stripe)?;
+        // a footer larger than a chunk
+        writer.add_user_metadata("key".to_string(), vec![1; 1000]);
+        let file = writer.finish()?;
+
+        let mut reader = Cursor::new(&file);
+        let metadata = read_metadata(&mut reader)?;
+        assert_eq!(metadata.postscript.compression(), CompressionKind::Zlib);
+        assert_eq!(metadata.postscript.compression_block_size(), 64);
+        assert_eq!(metadata.footer.types, types());
+        assert_eq!(metadata.footer.metadata[0].value(), vec![1; 1000]);
+        assert_eq!(
+            metadata.metadata()?.stripe_stats[0].col_stats,
+            statistics(10)
+        );
+
+        let stripe_footer = read_stripe_footer(&mut reader, &metadata, 0, &mut vec![])?;
+        let column = read_stripe_column(&mut reader, &metadata, 0, stripe_footer, 1, vec![])?;
+        let values = SignedRleV2Iter::new(column.get_stream(Kind::Data, vec![])?, 10, vec![])
+            .collect::<Result<Vec<_>, Error>>()?;
+        assert_eq!(values, vec![3; 10]);
+        Ok(())
+    }
+
+    #[test]
+    fn padding() -> Result<(), Error> {
+        // padding of up to 10 bytes (0.05 of 200)
+        let options = WriteOptions {
+            block_size: 64,
+            stripe_size: 200,
+            ..Default::default()
+        };
+        let mut writer = FileWriter::try_new(vec![], types(), options.clone())?;
+        for _ in 0..3 {
+            writer.write_stripe(stripe(5))?;
+        }
+        let file = writer.finish()?;
+        let mut reader = Cursor::new(&file);
+        let metadata = read_metadata(&mut reader)?;
+        let stripes = &metadata.footer.stripes;
+        let length = stripes[0].data_length() + stripes[0].footer_length();
+        assert_eq!(length, 26);
+        // the third stripe would cross the boundary at 64 and is moved to it
+        let offsets = stripes.iter().map(|stripe| stripe.offset());
+        assert_eq!(offsets.collect::<Vec<_>>(), vec![3, 29, 64]);
+        assert!(file[55..64].iter().all(|byte| *byte == 0));
+        for index in 0..3 {
+            let stripe_footer = read_stripe_footer(&mut reader, &metadata, index, &mut vec![])?;
+            let column =
+                read_stripe_column(&mut reader, &metadata, index, stripe_footer, 1, vec![])?;
+            let values = SignedRleV2Iter::new(column.get_stream(Kind::Data, vec![])?, 5, vec![])
+                .collect::<Result<Vec<_>, Error>>()?;
+            assert_eq!(values, vec![3; 5]);
+        }
+
+        // no padding above the tolerance or when disabled
+        for options in [
+            WriteOptions {
+                stripe_size: 100,
+                ..options.clone()
+            },
+            WriteOptions {
+                block_padding: false,
+                ..options
+            },
+        ] {
+            let mut writer = FileWriter::try_new(vec![], types(), options)?;
+            for _ in 0..3 {
+                writer.write_stripe(stripe(5))?;
+            }
+            let metadata = read_metadata(&mut Cursor::new(&writer.finish()?))?;
+            assert_eq!(metadata.footer.stripes[2].offset(), 55);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() -> Result<(), Error> {
+        let options = WriteOptions {
+            compression: CompressionKind::Lzo,
+            ..Default::default()
+        };
+        assert!(FileWriter::try_new(vec![], types(), options).is_err());
+
+        let mut types = types();
+        types[0].kind = Some(TypeKind::Int as i32);
+        assert!(FileWriter::try_new(vec![], types, WriteOptions::default()).is_err());
+
+        let mut writer = FileWriter::try_new(vec![], self::types(), WriteOptions::default())?;
+        let mut invalid = stripe(5);
+        invalid.encodings.pop();
+        assert!(writer.write_stripe(invalid).is_err());
+
+        let mut invalid = stripe(5);
+        invalid.statistics.pop();
+        assert!(writer.write_stripe(invalid).is_err());
+
+        let mut invalid = stripe(5);
+        invalid.streams[0].column = 2;
+        assert!(writer.write_stripe(invalid).is_err());
+
+        // failed stripes are not written
+        assert_eq!(writer.offset(), 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,531 @@
+//! Contains [`Statistics`], the accumulation of a column's [`ColumnStatistics`] while
+//! writing its values.
+use crate::proto::{
+    BinaryStatistics, BucketStatistics, ColumnStatistics, DateStatistics, DecimalStatistics,
+    DoubleStatistics, IntegerStatistics, StringStatistics, TimestampStatistics,
+};
+
+/// The statistics of the (non-null) values of a column, by type
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Values {
+    /// Columns without statistics of their values, e.g. structs
+    None,
+    Boolean {
+        true_count: u64,
+    },
+    Integer {
+        range: Option<(i64, i64)>,
+        /// `None` when the sum overflows
+        sum: Option<i64>,
+    },
+    Double {
+        range: Option<(f64, f64)>,
+        sum: f64,
+    },
+    String {
+        range: Option<(String, String)>,
+        /// The total length in bytes
+        sum: i64,
+    },
+    Binary {
+        /// The total length in bytes
+        sum: i64,
+    },
+    Date {
+        range: Option<(i32, i32)>,
+    },
+    Timestamp {
+        /// In milliseconds since the unix epoch
+        range: Option<(i64, i64)>,
+    },
+    Decimal {
+        range: Option<(i128, i128)>,
+        /// `None` when the sum overflows
+        sum: Option<i128>,
+        scale: u32,
+    },
+}
+
+impl Values {
+    /// Returns empty statistics of the same type
+    fn empty(&self) -> Self {
+        match self {
+            Self::None => Self::None,
+            Self::Boolean { .. } => Self::Boolean { true_count: 0 },
+            Self::Integer { .. } => Self::Integer {
+                range: None,
+                sum: Some(0),
+            },
+            Self::Double { .. } => Self::Double {
+                range: None,
+                sum: 0.0,
+            },
+            Self::String { .. } => Self::String {
+                range: None,
+                sum: 0,
+            },
+            Self::Binary { .. } => Self::Binary { sum: 0 },
+            Self::Date { .. } => Self::Date { range: None },
+            Self::Timestamp { .. } => Self::Timestamp { range: None },
+            Self::Decimal { scale, .. } => Self::Decimal {
+                range: None,
+                sum: Some(0),
+                scale: *scale,
+            },
+        }
+    }
+}
+
+fn update_range<T: PartialOrd + Copy>(range: &mut Option<(T, T)>, value: T) {
+    match range {
+        Some((min, max)) => {
+            if value < *min {
+                *min = value;
+            }
+            if value > *max {
+                *max = value;
+            }
+        }
+        None => *range = Some((value, value)),
+    }
+}
+
+fn merge_range<T: PartialOrd + Copy>(range: &mut Option<(T, T)>, other: &Option<(T, T)>) {
+    if let Some((min, max)) = other {
+        update_range(range, *min);
+        update_range(range, *max);
+    }
+}
+
+/// Formats `value` with `scale` decimal digits, e.g. `-1.05` for `(-105, 2)`
+pub(crate) fn format_decimal(value: i128, scale: u32) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let sign = if value < 0 { "-" } else { "" };
+    let scale = scale as usize;
+    if scale == 0 {
+        return format!("{sign}{digits}");
+    }
+    let digits = format!("{digits:0>width$}", width = scale + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    format!("{sign}{integer}.{fraction}")
+}
+
+/// The range of a column with `number_of_values` values from its minimum and maximum,
+/// or `None` when they are unknown
+fn known_range<T>(range: Option<(T, T)>, number_of_values: u64) -> Option<Option<(T, T)>> {
+    match (range, number_of_values) {
+        (None, 0) => Some(None),
+        (None, _) => None,
+        (range, _) => Some(range),
+    }
+}
+
+/// Parses a decimal formatted by [`format_decimal`] (or without trailing zeros) to its
+/// unscaled value with `scale` decimal digits, or `None` when it has more digits
+fn parse_decimal(value: &str, scale: u32) -> Option<i128> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let scale = scale as usize;
+    let (fraction, rest) = fraction.split_at(fraction.len().min(scale));
+    let digits = format!("{integer}{fraction:0<scale$}");
+    if digits.is_empty() || !rest.bytes().all(|digit| digit == b'0') {
+        return None;
+    }
+    if !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+        return None;
+    }
+    let value = digits.parse::<i128>().ok()?;
+    Some(if negative { -value } else { value })
+}
+
+/// The statistics of a column, accumulated while writing its values
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Statistics {
+    pub number_of_values: u64,
+    pub has_null: bool,
+    pub values: Values,
+}
+
+impl Statistics {
+    pub fn new(values: Values) -> Self {
+        Self {
+            number_of_values: 0,
+            has_null: false,
+            values: values.empty(),
+        }
+    }
+
+    /// Returns the statistics of `statistics`, of a column whose decimals have `scale`.
+    /// The statistics of values that are incomplete (e.g. the range of a column with
+    /// values but without minimum) are dropped.
+    pub fn from_proto(statistics: &ColumnStatistics, scale: u32) -> Self {
+        let number_of_values = statistics.number_of_values();
+        let values = if let Some(s) = &statistics.bucket_statistics {
+            s.count.first().map(|true_count| Values::Boolean {
+                true_count: *true_count,
+            })
+        } else if let Some(s) = &statistics.int_statistics {
+            known_range(s.minimum.zip(s.maximum), number_of_values)
+                .map(|range| Values::Integer { range, sum: s.sum })
+        } else if let Some(s) = &statistics.double_statistics {
+            known_range(s.minimum.zip(s.maximum), number_of_values)
+                .zip(s.sum)
+                .map(|(range, sum)| Values::Double { range, sum })
+        } else if let Some(s) = &statistics.string_statistics {
+            let minimum_maximum = s.minimum.clone().zip(s.maximum.clone());
+            known_range(minimum_maximum, number_of_values)
+                .zip(s.sum)
+                .map(|(range, sum)| Values::String { range, sum })
+        } else if let Some(s) = &statistics.binary_statistics {
+            s.sum.map(|sum| Values::Binary { sum })
+        } else if let Some(s) = &statistics.date_statistics {
+            known_range(s.minimum.zip(s.maximum), number_of_values)
+                .map(|range| Values::Date { range })
+        } else if let Some(s) = &statistics.timestamp_statistics {
+            // the (legacy) minimum and maximum are in the timezone of the writer
+            known_range(s.minimum_utc.zip(s.maximum_utc), number_of_values)
+                .map(|range| Values::Timestamp { range })
+        } else if let Some(s) = &statistics.decimal_statistics {
+            let parse = |value: &Option<String>| parse_decimal(value.as_deref()?, scale);
+            // unlike a missing sum, an unparsable one is not an overflow
+            let sum = match &s.sum {
+                Some(sum) => parse_decimal(sum, scale).map(Some),
+                None => Some(None),
+            };
+            known_range(parse(&s.minimum).zip(parse(&s.maximum)), number_of_values)
+                .zip(sum)
+                .map(|(range, sum)| Values::Decimal { range, sum, scale })
+        } else {
+            None
+        };
+        Self {
+            number_of_values,
+            has_null: statistics.has_null(),
+            values: values.unwrap_or(Values::None),
+        }
+    }
+
+    /// Resets the statistics to those of no values
+    pub fn reset(&mut self) {
+        *self = Self::new(self.values.empty())
+    }
+
+    pub fn null(&mut self) {
+        self.has_null = true;
+    }
+
+    /// Adds a value of a column without statistics of its values, e.g. a struct
+    pub fn value(&mut self) {
+        self.number_of_values += 1;
+    }
+
+    pub fn boolean(&mut self, value: bool) {
+        self.number_of_values += 1;
+        if let Values::Boolean { true_count } = &mut self.values {
+            *true_count += value as u64;
+        }
+    }
+
+    pub fn integer(&mut self, value: i64) {
+        self.number_of_values += 1;
+        if let Values::Integer { range, sum } = &mut self.values {
+            update_range(range, value);
+            *sum = sum.and_then(|sum| sum.checked_add(value));
+        }
+    }
+
+    pub fn double(&mut self, value: f64) {
+        self.number_of_values += 1;
+        if let Values::Double { range, sum } = &mut self.values {
+            update_range(range, value);
+            *sum += value;
+        }
+    }
+
+    pub fn string(&mut self, value: &str) {
+        self.number_of_values += 1;
+        if let Values::String { range, sum } = &mut self.values {
+            match range {
+                Some((min, max)) => {
+                    if value < min.as_str() {
+                        *min = value.to_string();
+                    }
+                    if value > max.as_str() {
+                        *max = value.to_string();
+                    }
+                }
+                None => *range = Some((value.to_string(), value.to_string())),
+            }
+            *sum += value.len() as i64;
+        }
+    }
+
+    pub fn binary(&mut self, value: &[u8]) {
+        self.number_of_values += 1;
+        if let Values::Binary { sum } = &mut self.values {
+            *sum += value.len() as i64;
+        }
+    }
+
+    pub fn date(&mut self, value: i32) {
+        self.number_of_values += 1;
+        if let Values::Date { range } = &mut self.values {
+            update_range(range, value);
+        }
+    }
+
+    /// Adds a timestamp in nanoseconds since the unix epoch
+    pub fn timestamp(&mut self, value: i64) {
+        self.number_of_values += 1;
+        if let Values::Timestamp { range } = &mut self.values {
+            update_range(range, value.div_euclid(1_000_000));
+        }
+    }
+
+    pub fn decimal(&mut self, value: i128) {
+        self.number_of_values += 1;
+        if let Values::Decimal { range, sum, .. } = &mut self.values {
+            update_range(range, value);
+            *sum = sum.and_then(|sum| sum.checked_add(value));
+        }
+    }
+
+    /// Merges the statistics of `other` (of the same type) into these
+    pub fn merge(&mut self, other: &Self) {
+        self.number_of_values += other.number_of_values;
+        self.has_null |= other.has_null;
+        match (&mut self.values, &other.values) {
+            (Values::Boolean { true_count }, Values::Boolean { true_count: other }) => {
+                *true_count += other;
+            }
+            (Values::Integer { range, sum }, Values::Integer { range: r, sum: s }) => {
+                merge_range(range, r);
+                *sum = sum.zip(*s).and_then(|(a, b)| a.checked_add(b));
+            }
+            (Values::Double { range, sum }, Values::Double { range: r, sum: s }) => {
+                merge_range(range, r);
+                *sum += s;
+            }
+            (Values::String { range, sum }, Values::String { range: r, sum: s }) => {
+                if let Some((other_min, other_max)) = r {
+                    match range {
+                        Some((min, max)) => {
+                            if other_min < min {
+                                *min = other_min.clone();
+                            }
+                            if other_max > max {
+                                *max = other_max.clone();
+                            }
+                        }
+                        None => *range = r.clone(),
+                    }
+                }
+                *sum += s;
+            }
+            (Values::Binary { sum }, Values::Binary { sum: s }) => *sum += s,
+            (Values::Date { range }, Values::Date { range: r }) => merge_range(range, r),
+            (Values::Timestamp { range }, Values::Timestamp { range: r }) => merge_range(range, r),
+            (
+                Values::Decimal { range, sum, .. },
+                Values::Decimal {
+                    range: r, sum: s, ..
+                },
+            ) => {
+                merge_range(range, r);
+                *sum = sum.zip(*s).and_then(|(a, b)| a.checked_add(b));
+            }
+            _ => {}
+        }
+    }
+
+    pub fn to_proto(&self) -> ColumnStatistics {
+        let mut statistics = ColumnStatistics {
+            number_of_values: Some(self.number_of_values),
+            has_null: Some(self.has_null),
+            ..Default::default()
+        };
+        match &self.values {
+            Values::None => {}
+            Values::Boolean { true_count } => {
+                statistics.bucket_statistics = Some(BucketStatistics {
+                    count: vec![*true_count],
+                })
+            }
+            Values::Integer { range, sum } => {
+                statistics.int_statistics = Some(IntegerStatistics {
+                    minimum: range.map(|x| x.0),
+                    maximum: range.map(|x| x.1),
+                    sum: *sum,
+                })
+            }
+            Values::Double { range, sum } => {
+                statistics.double_statistics = Some(DoubleStatistics {
+                    minimum: range.map(|x| x.0),
+                    maximum: range.map(|x| x.1),
+                    sum: Some(*sum),
+                })
+            }
+            Values::String { range, sum } => {
+                statistics.string_statistics = Some(StringStatistics {
+                    minimum: range.as_ref().map(|x| x.0.clone()),
+                    maximum: range.as_ref().map(|x| x.1.clone()),
+                    sum: Some(*sum),
+                })
+            }
+            Values::Binary { sum } => {
+                statistics.binary_statistics = Some(BinaryStatistics { sum: Some(*sum) })
+            }
+            Values::Date { range } => {
+                statistics.date_statistics = Some(DateStatistics {
+                    minimum: range.map(|x| x.0),
+                    maximum: range.map(|x| x.1),
+                })
+            }
+            Values::Timestamp { range } => {
+                statistics.timestamp_statistics = Some(TimestampStatistics {
+                    minimum_utc: range.map(|x| x.0),
+                    maximum_utc: range.map(|x| x.1),
+                    ..Default::default()
+                })
+            }
+            Values::Decimal { range, sum, scale } => {
+                statistics.decimal_statistics = Some(DecimalStatistics {
+                    minimum: range.map(|x| format_decimal(x.0, *scale)),
+                    maximum: range.map(|x| format_decimal(x.1, *scale)),
+                    sum: sum.map(|sum| format_decimal(sum, *scale)),
+                })
+            }
+        }
+        statistics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimals() {
+        assert_eq!(format_decimal(-105, 2), "-1.05");
+        assert_eq!(format_decimal(5, 3), "0.005");
+        assert_eq!(format_decimal(1234, 0), "1234");
+        assert_eq!(format_decimal(0, 1), "0.0");
+    }
+
+    #[test]
+    fn parse_decimals() {
+        for (value, scale) in [(-105, 2), (5, 3), (1234, 0), (0, 1), (i128::MAX, 38)] {
+            assert_eq!(
+                parse_decimal(&format_decimal(value, scale), scale),
+                Some(value)
+            );
+        }
+        assert_eq!(parse_decimal("1.5", 2), Some(150));
+        assert_eq!(parse_decimal("-2", 1), Some(-20));
+        assert_eq!(parse_decimal("1.50", 1), Some(15));
+        assert_eq!(parse_decimal("1.05", 1), None);
+        assert_eq!(parse_decimal("1E+3", 0), None);
+        assert_eq!(parse_decimal("", 0), None);
+        assert_eq!(parse_decimal("-+1", 0), None);
+    }
+
+    #[test]
+    fn from_proto() {
+        let mut a = Statistics::new(Values::Decimal {
+            range: None,
+            sum: None,
+            scale: 2,
+        });
+        a.decimal(-105);
+        a.decimal(300);
+        a.null();
+        assert_eq!(Statistics::from_proto(&a.to_proto(), 2), a);
+        let empty = Statistics::new(a.values.clone());
+        assert_eq!(Statistics::from_proto(&empty.to_proto(), 2), empty);
+
+        let mut a = Statistics::new(Values::String {
+            range: None,
+            sum: 0,
+        });
+        a.string("a");
+        assert_eq!(Statistics::from_proto(&a.to_proto(), 0), a);
+
+        // a range without minimum is dropped
+        let mut proto = a.to_proto();
+        if let Some(statistics) = proto.string_statistics.as_mut() {
+            statistics.minimum = None;
+        }
+        let statistics = Statistics::from_proto(&proto, 0);
+        assert_eq!(statistics.values, Values::None);
+        assert_eq!(statistics.number_of_values, 1);
+
+        let mut a = Statistics::new(Values::Timestamp { range: None });
+        a.timestamp(-1_500_000);
+        assert_eq!(Statistics::from_proto(&a.to_proto(), 0), a);
+
+        // timestamps in the timezone of the writer (written before ORC-135) are dropped
+        let mut proto = a.to_proto();
+        proto.timestamp_statistics = Some(TimestampStatistics {
+            minimum: Some(-2),
+            maximum: Some(-2),
+            ..Default::default()
+        });
+        let statistics = Statistics::from_proto(&proto, 0);
+        assert_eq!(statistics.values, Values::None);
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = Statistics::new(Values::Integer {
+            range: None,
+            sum: None,
+        });
+        a.integer(3);
+        a.integer(-1);
+        let mut b = Statistics::new(a.values.clone());
+        b.integer(i64::MAX);
+        b.null();
+        a.merge(&b);
+
+        let expected = ColumnStatistics {
+            number_of_values: Some(3),
+            has_null: Some(true),
+            int_statistics: Some(IntegerStatistics {
+                minimum: Some(-1),
+                maximum: Some(i64::MAX),
+                sum: None,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(a.to_proto(), expected);
+
+        a.reset();
+        assert_eq!(a.number_of_values, 0);
+        assert_eq!(
+            a.values,
+            Values::Integer {
+                range: None,
+                sum: Some(0)
+            }
+        );
+    }
+
+    #[test]
+    fn strings() {
+        let mut a = Statistics::new(Values::String {
+            range: None,
+            sum: 0,
+        });
+        ["b", "a", "c"].into_iter().for_each(|x| a.string(x));
+        let mut b = Statistics::new(a.values.clone());
+        b.string("0");
+        a.merge(&b);
+        let statistics = a.to_proto().string_statistics.unwrap();
+        assert_eq!(statistics.minimum(), "0");
+        assert_eq!(statistics.maximum(), "c");
+        assert_eq!(statistics.sum(), 4);
+    }
+}
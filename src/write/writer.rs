@@ -0,0 +1,328 @@
+//! Contains [`OrcWriter`], which writes the rows of a file, finishing its row groups and
+//! stripes as they fill up.
+use std::io::Write;
+
+use crate::error::Error;
+use crate::proto::Type;
+
+use super::column::{ColumnWriter, StructWriter};
+use super::{EncodedStripe, FileWriter, WriteOptions};
+
+/// Writes the rows of an ORC file.
+///
+/// The values of rows are written to the writers of the file's top-level columns
+/// ([`OrcWriter::columns_mut`]), one value (or null) per row and column, and the rows are
+/// then ended with [`OrcWriter::write_rows`]. This finishes row groups every
+/// [`WriteOptions::row_index_stride`] rows, and stripes once they have
+/// [`WriteOptions::stripe_row_count`] rows or their estimated size reaches
+/// [`WriteOptions::stripe_size`], bounding the memory used regardless of the number of
+/// columns.
+///
+/// # Example
+/// ```
+/// use orc_format::proto::{r#type::Kind, Type};
+/// use orc_format::write::column::ColumnWriter;
+/// use orc_format::write::{OrcWriter, WriteOptions};
+///
+/// let types = vec![
+///     Type {
+///         kind: Some(Kind::Struct as i32),
+///         subtypes: vec![1],
+///         field_names: vec!["a".to_string()],
+///         ..Default::default()
+///     },
+///     Type {
+///         kind: Some(Kind::Long as i32),
+///         ..Default::default()
+///     },
+/// ];
+/// let mut writer = OrcWriter::try_new(vec![], types, WriteOptions::default())?;
+/// if let ColumnWriter::Integer(column) = &mut writer.columns_mut()[0] {
+///     column.write(Some(1));
+///     column.write(None);
+/// }
+/// writer.write_rows(2)?;
+/// let file = writer.finish()?;
+/// # Ok::<(), orc_format::error::Error>(())
+/// ```
+pub struct OrcWriter<W: Write> {
+    file: FileWriter<W>,
+    /// the writer of the root struct, whose children are the top-level columns
+    root: StructWriter,
+    /// the number of rows of the current stripe
+    stripe_rows: u64,
+    /// the number of rows of the current row group
+    row_group_rows: u64,
+}
+
+impl<W: Write> OrcWriter<W> {
+    /// Returns a new [`OrcWriter`] of a file with `types`, writing the file's header to `writer`.
+    /// # Errors
+    /// This function errors when the root of `types` is not a struct, a type or `options`
+    /// are not supported, or writing fails.
+    pub fn try_new(writer: W, types: Vec<Type>, options: WriteOptions) -> Result<Self, Error> {
+        if options.stripe_row_count == 0 {
+            return Err(Error::OutOfSpec("stripes must have at least one row"));
+        }
+        let ColumnWriter::Struct(root) = ColumnWriter::try_new(&types, 0, &options)? else {
+            return Err(Error::OutOfSpec("the root type of a file must be a struct"));
+        };
+        Ok(Self {
+            file: FileWriter::try_new(writer, types, options)?,
+            root,
+            stripe_rows: 0,
+            row_group_rows: 0,
+        })
+    }
+
+    /// Returns the types of the file
+    pub fn types(&self) -> &[Type] {
+        self.file.types()
+    }
+
+    /// The writers of the top-level columns of the file
+    pub fn columns_mut(&mut self) -> &mut [ColumnWriter] {
+        self.root.children_mut()
+    }
+
+    /// The maximum number of rows that can be ended by the next [`OrcWriter::write_rows`]:
+    /// the rows left in the current row group and stripe
+    pub fn max_rows(&self) -> u64 {
+        let options = self.file.options();
+        let rows = options.stripe_row_count - self.stripe_rows;
+        match options.row_index_stride as u64 {
+            0 => rows,
+            stride => rows.min(stride - self.row_group_rows),
+        }
+    }
+
+    /// Ends `rows` rows, whose values were written to every column, finishing the row group
+    /// and the stripe when they are full.
+    /// # Errors
+    /// This function errors when `rows` is larger than [`OrcWriter::max_rows`], and when
+    /// compressing or writing fails.
+    pub fn write_rows(&mut self, rows: u64) -> Result<(), Error> {
+        if rows > self.max_rows() {
+            return Err(Error::OutOfSpec(
+                "rows must not span more than one row group or stripe",
+            ));
+        }
+        (0..rows).for_each(|_| self.root.write(true));
+        self.stripe_rows += rows;
+        self.row_group_rows += rows;
+
+        let options = self.file.options();
+        let (stride, stripe_row_count, stripe_size) = (
+            options.row_index_stride as u64,
+            options.stripe_row_count,
+            options.stripe_size,
+        );
+        if self.row_group_rows == stride {
+            self.root.finish_row_group()?;
+            self.row_group_rows = 0;
+        }
+        if self.stripe_rows == stripe_row_count || self.estimated_size() >= stripe_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// The estimated size in memory of the current stripe, once encoded and compressed
+    pub fn estimated_size(&self) -> u64 {
+        self.root.estimated_size()
+    }
+
+    /// Writes the current stripe to the file, if it has rows
+    /// # Errors
+    /// This function errors when compressing or writing fails.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        if self.stripe_rows == 0 {
+            return Ok(());
+        }
+        let mut stripe = EncodedStripe {
+            number_of_rows: self.stripe_rows,
+            writer_timezone: Some("UTC".to_string()),
+            ..Default::default()
+        };
+        // the root finishes the last row group of every column when the file has rows in it
+        self.root.finish_stripe(&mut stripe)?;
+        self.file.write_stripe(stripe)?;
+        self.stripe_rows = 0;
+        self.row_group_rows = 0;
+        Ok(())
+    }
+
+    /// Adds a user-defined `name` and `value` to the file's footer
+    pub fn add_user_metadata(&mut self, name: String, value: Vec<u8>) {
+        self.file.add_user_metadata(name, value)
+    }
+
+    /// Writes the current stripe and the file's tail, returning the inner writer.
+    /// # Errors
+    /// This function errors when compressing or writing fails.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.flush()?;
+        let mut statistics = vec![self.root.statistics()];
+        for column in self.root.children_mut() {
+            column.statistics(&mut statistics);
+        }
+        self.file.set_statistics(statistics);
+        self.file.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use prost::Message;
+
+    use crate::proto::r#type::Kind as TypeKind;
+    use crate::proto::stream::Kind;
+    use crate::proto::{CompressionKind, RowIndex};
+    use crate::read::decode::SignedRleV2Iter;
+    use crate::read::decompress::Decompressor;
+    use crate::read::{read_metadata, read_stripe_column, read_stripe_footer, FileMetadata};
+    use crate::test_util::struct_;
+
+    use super::*;
+
+    fn types() -> Vec<Type> {
+        struct_(&[("a", TypeKind::Long), ("b", TypeKind::String)])
+    }
+
+    /// Writes `rows` rows, at most `batch` at a time
+    fn write(options: WriteOptions, rows: i64, batch: u64) -> Result<Vec<u8>, Error> {
+        let mut writer = OrcWriter::try_new(vec![], types(), options)?;
+        let mut row = 0;
+        while row < rows {
+            let end = rows.min(row + writer.max_rows().min(batch) as i64);
+            for value in row..end {
+                let [ColumnWriter::Integer(a), ColumnWriter::String(b)] = writer.columns_mut()
+                else {
+                    unreachable!()
+                };
+                a.write(Some(value));
+                b.write(Some(&value.to_string()));
+            }
+            writer.write_rows((end - row) as u64)?;
+            row = end;
+        }
+        writer.finish()
+    }
+
+    /// The values of column 1 of every stripe
+    fn values(file: &[u8], metadata: &FileMetadata) -> Result<Vec<Vec<i64>>, Error> {
+        let mut reader = Cursor::new(file);
+        (0..metadata.footer.stripes.len())
+            .map(|stripe| {
+                let footer = read_stripe_footer(&mut reader, metadata, stripe, &mut vec![])?;
+                let column = read_stripe_column(&mut reader, metadata, stripe, footer, 1, vec![])?;
+                let rows = column.number_of_rows();
+                let data = column.get_stream(Kind::Data, vec![])?;
+                SignedRleV2Iter::new(data, rows, vec![]).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stripe_row_count() -> Result<(), Error> {
+        let options = WriteOptions {
+            row_index_stride: 100,
+            stripe_row_count: 250,
+            compression: CompressionKind::Zlib,
+            ..Default::default()
+        };
+        let file = write(options, 600, u64::MAX)?;
+        let metadata = read_metadata(&mut Cursor::new(&file))?;
+        let footer = &metadata.footer;
+        let rows = footer.stripes.iter().map(|stripe| stripe.number_of_rows());
+        assert_eq!(rows.collect::<Vec<_>>(), vec![250, 250, 100]);
+        assert_eq!(footer.number_of_rows(), 600);
+        assert_eq!(footer.statistics.len(), 3);
+        assert_eq!(footer.statistics[1].number_of_values(), 600);
+
+        let values = values(&file, &metadata)?;
+        assert_eq!(values.concat(), (0..600).collect::<Vec<_>>());
+
+        // row groups restart with every stripe
+        let mut reader = Cursor::new(&file);
+        let mut entries = vec![];
+        for (index, stripe) in footer.stripes.iter().enumerate() {
+            let stripe_footer = read_stripe_footer(&mut reader, &metadata, index, &mut vec![])?;
+            // the row index of the root comes first
+            let stream = &stripe_footer.streams[0];
+            assert_eq!((stream.kind(), stream.column()), (Kind::RowIndex, 0));
+            let start = stripe.offset() as usize;
+            let data = &file[start..start + stream.length() as usize];
+            let mut index = vec![];
+            Decompressor::new(data, CompressionKind::Zlib, vec![]).read_to_end(&mut index)?;
+            let index = RowIndex::decode(index.as_slice()).unwrap();
+            let values = index.entry.iter().map(|entry| {
+                let statistics = entry.statistics.as_ref();
+                statistics.map_or(0, |statistics| statistics.number_of_values())
+            });
+            entries.push(values.collect::<Vec<_>>());
+        }
+        assert_eq!(
+            entries,
+            vec![vec![100, 100, 50], vec![100, 100, 50], vec![100]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn stripe_size() -> Result<(), Error> {
+        // the estimated size of the stripe grows with its rows
+        let mut writer = OrcWriter::try_new(vec![], types(), WriteOptions::default())?;
+        let empty = writer.estimated_size();
+        for value in 0..1000 {
+            let [ColumnWriter::Integer(a), ColumnWriter::String(b)] = writer.columns_mut() else {
+                unreachable!()
+            };
+            a.write(Some(value));
+            b.write(Some(&value.to_string()));
+        }
+        writer.write_rows(1000)?;
+        assert!(writer.estimated_size() > empty + 1000 * 4);
+        writer.flush()?;
+        assert_eq!(writer.estimated_size(), empty);
+
+        let options = WriteOptions {
+            row_index_stride: 0,
+            stripe_size: 1024,
+            ..Default::default()
+        };
+        let file = write(options, 10_000, 100)?;
+        let metadata = read_metadata(&mut Cursor::new(&file))?;
+        let stripes = &metadata.footer.stripes;
+        assert!(stripes.len() > 1);
+        assert!(stripes[..stripes.len() - 1]
+            .iter()
+            .all(|stripe| stripe.number_of_rows() % 100 == 0));
+        let values = values(&file, &metadata)?;
+        assert_eq!(values.concat(), (0..10_000).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() -> Result<(), Error> {
+        let options = WriteOptions {
+            row_index_stride: 10,
+            ..Default::default()
+        };
+        let mut writer = OrcWriter::try_new(vec![], types(), options)?;
+        assert_eq!(writer.max_rows(), 10);
+        assert!(writer.write_rows(11).is_err());
+
+        let options = WriteOptions {
+            stripe_row_count: 0,
+            ..Default::default()
+        };
+        assert!(OrcWriter::try_new(vec![], types(), options).is_err());
+        assert!(
+            OrcWriter::try_new(vec![], types()[1..].to_vec(), WriteOptions::default()).is_err()
+        );
+        Ok(())
+    }
+}
@@ -3,36 +3,37 @@ use orc_format::{
     proto::{column_encoding::Kind as ColumnEncodingKind, stream::Kind},
     read,
     read::decode::{
-        BooleanIter, SignedRleV2Iter, SignedRleV2Run, SignedRleV2RunIter, UnsignedRleV2Run,
-        UnsignedRleV2RunIter,
+        decode_bitmap, is_set, BooleanIter, SignedRleV2Iter, SignedRleV2Run, SignedRleV2RunIter,
+        UnsignedRleV2Run, UnsignedRleV2RunIter,
     },
     read::decompress::Decompressor,
     read::Column,
 };
 
-fn deserialize_validity(column: &Column, scratch: &mut Vec<u8>) -> Result<Vec<bool>, Error> {
+/// Returns the validity of the column and its number of non-null values
+fn deserialize_validity<B: AsRef<[u8]>>(
+    column: &Column<B>,
+    scratch: &mut Vec<u8>,
+) -> Result<(Vec<bool>, usize), Error> {
     let mut reader = column.get_stream(Kind::Present, std::mem::take(scratch))?;
 
-    let mut validity = Vec::with_capacity(column.number_of_rows());
-    BooleanIter::new(&mut reader, column.number_of_rows()).try_for_each(|item| {
-        validity.push(item?);
-        Result::<(), Error>::Ok(())
-    })?;
+    let length = column.number_of_rows();
+    let mut bitmap = vec![];
+    let null_count = decode_bitmap(&mut reader, length, &mut bitmap)?;
+    let validity = (0..length).map(|i| is_set(&bitmap, i)).collect();
 
     *scratch = std::mem::take(&mut reader.into_inner());
 
-    Ok(validity)
+    Ok((validity, length - null_count))
 }
 
 pub fn deserialize_f32_array(column: &Column) -> Result<(Vec<bool>, Vec<f32>), Error> {
     let mut scratch = vec![];
 
-    let validity = deserialize_validity(column, &mut scratch)?;
+    let (validity, num_of_values) = deserialize_validity(column, &mut scratch)?;
 
     let reader = column.get_stream(Kind::Data, scratch)?;
 
-    let num_of_values: usize = validity.iter().map(|x| *x as usize).sum();
-
     let mut valid_values = Vec::with_capacity(num_of_values);
     let mut iter = read::decode::FloatIter::<f32, _>::new(reader, num_of_values);
     iter.try_for_each(|item| {
@@ -42,15 +43,20 @@ pub fn deserialize_f32_array(column: &Column) -> Result<(Vec<bool>, Vec<f32>), E
 
     let _ = iter.into_inner();
 
+    // test the batch API
+    let reader = column.get_stream(Kind::Data, vec![])?;
+    let mut valid_values1 = Vec::with_capacity(num_of_values);
+    read::decode::FloatIter::<f32, _>::new(reader, num_of_values)
+        .extend_into(&mut valid_values1)?;
+    assert_eq!(valid_values1, valid_values);
+
     Ok((validity, valid_values))
 }
 
 pub fn deserialize_int_array(column: &Column) -> Result<(Vec<bool>, Vec<i64>), Error> {
     let mut scratch = vec![];
 
-    let validity = deserialize_validity(column, &mut scratch)?;
-
-    let num_of_values: usize = validity.iter().map(|x| *x as usize).sum();
+    let (validity, num_of_values) = deserialize_validity(column, &mut scratch)?;
 
     let reader = column.get_stream(Kind::Data, scratch)?;
 
@@ -63,6 +69,7 @@ pub fn deserialize_int_array(column: &Column) -> Result<(Vec<bool>, Vec<i64>), E
             SignedRleV2Run::Direct(values) => valid_values.extend(values),
             SignedRleV2Run::Delta(values) => valid_values.extend(values),
             SignedRleV2Run::ShortRepeat(values) => valid_values.extend(values),
+            SignedRleV2Run::PatchedBase(values) => valid_values.extend(values),
         })
     })?;
 
@@ -78,15 +85,20 @@ pub fn deserialize_int_array(column: &Column) -> Result<(Vec<bool>, Vec<i64>), E
     })?;
     assert_eq!(valid_values1, valid_values);
 
+    // test the batch API
+    let reader = column.get_stream(Kind::Data, vec![])?;
+
+    let mut valid_values2 = Vec::with_capacity(num_of_values);
+    SignedRleV2Iter::new(reader, num_of_values, vec![]).extend_into(&mut valid_values2)?;
+    assert_eq!(valid_values2, valid_values);
+
     Ok((validity, valid_values))
 }
 
 pub fn deserialize_bool_array(column: &Column) -> Result<(Vec<bool>, Vec<bool>), Error> {
     let mut scratch = vec![];
 
-    let validity = deserialize_validity(column, &mut scratch)?;
-
-    let num_of_values: usize = validity.iter().map(|x| *x as usize).sum();
+    let (validity, num_of_values) = deserialize_validity(column, &mut scratch)?;
 
     let reader = column.get_stream(Kind::Data, std::mem::take(&mut scratch))?;
 
@@ -131,6 +143,10 @@ pub fn deserialize_str(
                 result.push(x?);
                 Result::<_, Error>::Ok(())
             }),
+            UnsignedRleV2Run::PatchedBase(lengths) => lengths.map(f).try_for_each(|x| {
+                result.push(x?);
+                Result::<_, Error>::Ok(())
+            }),
         }?
     }
 
@@ -139,8 +155,8 @@ pub fn deserialize_str(
     Ok(result)
 }
 
-pub fn deserialize_str_dict_array(
-    column: &Column,
+pub fn deserialize_str_dict_array<B: AsRef<[u8]>>(
+    column: &Column<B>,
     scratch: Vec<u8>,
     num_of_values: usize,
 ) -> Result<Vec<String>, Error> {
@@ -159,7 +175,12 @@ pub fn deserialize_str_dict_array(
     let indices = column.get_stream(Kind::Data, scratch)?;
     let mut indices = UnsignedRleV2RunIter::new(indices, column.number_of_rows(), vec![]);
 
-    let f = |x| values.get(x as usize).cloned().ok_or(Error::OutOfSpec);
+    let f = |x| {
+        values
+            .get(x as usize)
+            .cloned()
+            .ok_or(Error::OutOfSpec("dictionary index out of range"))
+    };
 
     let mut result = Vec::with_capacity(num_of_values);
     for run in indices.by_ref() {
@@ -176,6 +197,10 @@ pub fn deserialize_str_dict_array(
                 result.push(x?);
                 Result::<_, Error>::Ok(())
             }),
+            UnsignedRleV2Run::PatchedBase(values) => values.map(f).try_for_each(|x| {
+                result.push(x?);
+                Result::<_, Error>::Ok(())
+            }),
         })?;
     }
 
@@ -184,8 +209,8 @@ pub fn deserialize_str_dict_array(
     Ok(result)
 }
 
-fn deserialize_str_array_direct(
-    column: &Column,
+fn deserialize_str_array_direct<B: AsRef<[u8]>>(
+    column: &Column<B>,
 
     scratch: Vec<u8>,
     num_of_values: usize,
@@ -200,12 +225,12 @@ fn deserialize_str_array_direct(
     deserialize_str(lengths, &mut values, num_of_values)
 }
 
-pub fn deserialize_str_array(column: &Column) -> Result<(Vec<bool>, Vec<String>), Error> {
+pub fn deserialize_str_array<B: AsRef<[u8]>>(
+    column: &Column<B>,
+) -> Result<(Vec<bool>, Vec<String>), Error> {
     let mut scratch = vec![];
 
-    let validity = deserialize_validity(column, &mut scratch)?;
-
-    let num_of_values: usize = validity.iter().map(|x| *x as usize).sum();
+    let (validity, num_of_values) = deserialize_validity(column, &mut scratch)?;
 
     // todo: generalize to other encodings
     let encoding = column.encoding();
@@ -314,3 +314,25 @@ fn read_string_decrease() -> Result<(), Error> {
     assert_eq!(b, vec!["eeeee", "dddd", "ccc", "bb", "a"]);
     Ok(())
 }
+
+#[test]
+fn read_from_slice() -> Result<(), Error> {
+    let data = std::fs::read("string_long_long_gzip.orc").expect("no file found");
+    let mut reader = std::io::Cursor::new(data.as_slice());
+
+    let metadata = read::read_metadata(&mut reader)?;
+    let stripe_footer = read::read_stripe_footer(&mut reader, &metadata, 0, &mut vec![])?;
+    let column = read::slice_stripe_column(&data, &metadata, 0, stripe_footer, 1)?;
+
+    let (a, b) = deserialize_str_array(&column)?;
+    assert_eq!(a, vec![true; 10_000]);
+    assert_eq!(
+        b,
+        vec!["abcd", "efgh"]
+            .into_iter()
+            .cycle()
+            .take(10_000)
+            .collect::<Vec<_>>()
+    );
+    Ok(())
+}